@@ -1,6 +1,10 @@
-use raffle_shared::AdminOp;
-use soroban_sdk::{contractevent, u128, Address, BytesN};
-use soroban_sdk::{contractevent, Address, BytesN};
+use raffle_shared::roles::Role;
+use raffle_shared::{
+    AdminOp, BondPolicy, FeeHoliday, FeeQuote, FeeTier, OpKind, ReputationPolicy, SupportedToken,
+};
+use soroban_sdk::{contractevent, Address, BytesN, Vec};
+
+use crate::RegistryOp;
 
 #[allow(dead_code)]
 #[derive(Clone)]
@@ -25,8 +29,9 @@ pub struct FactoryInitialized {
 #[contractevent]
 pub struct AdminOpProposed {
     pub op_id: u32,
-    pub op: AdminOp,
+    pub ops: Vec<AdminOp>,
     pub effective_timestamp: u64,
+    pub expires_at: u64,
     pub proposed_by: Address,
 }
 
@@ -34,7 +39,7 @@ pub struct AdminOpProposed {
 #[contractevent]
 pub struct AdminOpExecuted {
     pub op_id: u32,
-    pub op: AdminOp,
+    pub ops: Vec<AdminOp>,
     pub executed_by: Address,
     pub executed_at: u64,
 }
@@ -104,10 +109,35 @@ pub struct CheckpointCreated {
     pub index: u32,
     pub raffle_count: u32,
     pub ledger_timestamp: u64,
+    pub event_count: u32,
+    pub accumulator: BytesN<32>,
+    pub previous_hash: BytesN<32>,
     pub aggregate_hash: BytesN<32>,
 }
 
-#[allow(dead_code)]
+#[derive(Clone)]
+#[contractevent]
+pub struct RegistryUpdated {
+    pub op: RegistryOp,
+    pub raffle_id: u32,
+    pub raffle_address: Address,
+    pub creator: Address,
+    pub config_hash: BytesN<32>,
+    pub accumulator: BytesN<32>,
+    pub event_count: u32,
+}
+
+/// Emitted when a payment token is listed or its limits change.
+#[derive(Clone)]
+#[contractevent]
+pub struct SupportedTokenConfigured {
+    pub token: Address,
+    pub limits: SupportedToken,
+    pub updated_by: Address,
+    pub timestamp: u64,
+}
+
+/// Emitted when a listed payment token is enabled or disabled.
 #[derive(Clone)]
 #[contractevent]
 pub struct SupportedSacUpdated {
@@ -151,3 +181,164 @@ pub struct FactoryUpgraded {
     pub new_wasm_hash: BytesN<32>,
     pub timestamp: u64,
 }
+
+#[derive(Clone)]
+#[contractevent]
+pub struct FactoryEmergencyUpgraded {
+    pub guardian: Address,
+    pub new_wasm_hash: BytesN<32>,
+    pub timestamp: u64,
+}
+
+#[derive(Clone)]
+#[contractevent]
+pub struct FactoryMigrated {
+    pub from_version: u32,
+    pub to_version: u32,
+    pub migrated_by: Address,
+    pub timestamp: u64,
+}
+
+#[derive(Clone)]
+#[contractevent]
+pub struct RoleGranted {
+    pub role: Role,
+    pub account: Address,
+    pub granted_by: Address,
+    pub timestamp: u64,
+}
+
+#[derive(Clone)]
+#[contractevent]
+pub struct RoleRevoked {
+    pub role: Role,
+    pub account: Address,
+    pub revoked_by: Address,
+    pub timestamp: u64,
+}
+
+#[derive(Clone)]
+#[contractevent]
+pub struct VetoCast {
+    pub op_id: u32,
+    pub guardian: Address,
+    pub veto_count: u32,
+    pub threshold: u32,
+    pub timestamp: u64,
+}
+
+#[derive(Clone)]
+#[contractevent]
+pub struct AdminOpVetoed {
+    pub op_id: u32,
+    pub ops: Vec<AdminOp>,
+    pub vetoed_by: Vec<Address>,
+    pub timestamp: u64,
+}
+
+#[derive(Clone)]
+#[contractevent]
+pub struct GuardianThresholdChanged {
+    pub old_threshold: u32,
+    pub new_threshold: u32,
+    pub timestamp: u64,
+}
+
+#[derive(Clone)]
+#[contractevent]
+pub struct RolesSynced {
+    pub instance: Address,
+    pub timestamp: u64,
+}
+
+#[derive(Clone)]
+#[contractevent]
+pub struct AdminOpExpired {
+    pub op_id: u32,
+    pub ops: Vec<AdminOp>,
+    pub expires_at: u64,
+    pub timestamp: u64,
+}
+
+#[derive(Clone)]
+#[contractevent]
+pub struct OpDelayChanged {
+    pub kind: OpKind,
+    pub old_delay: u64,
+    pub new_delay: u64,
+    pub timestamp: u64,
+}
+
+#[derive(Clone)]
+#[contractevent]
+pub struct OpExpiryChanged {
+    pub old_expiry: u64,
+    pub new_expiry: u64,
+    pub timestamp: u64,
+}
+
+/// Emitted when a creator's partner fee is set (`Some`) or cleared (`None`).
+#[derive(Clone)]
+#[contractevent]
+pub struct PartnerFeeUpdated {
+    pub partner: Address,
+    pub fee_bp: Option<u32>,
+    pub updated_by: Address,
+    pub timestamp: u64,
+}
+
+/// Emitted when the volume fee tiers of a payment token are replaced.
+#[derive(Clone)]
+#[contractevent]
+pub struct FeeTiersUpdated {
+    pub asset: Address,
+    pub tiers: Vec<FeeTier>,
+    pub updated_by: Address,
+    pub timestamp: u64,
+}
+
+/// Emitted when the promotional fee holidays are replaced.
+#[derive(Clone)]
+#[contractevent]
+pub struct FeeHolidaysUpdated {
+    pub holidays: Vec<FeeHoliday>,
+    pub updated_by: Address,
+    pub timestamp: u64,
+}
+
+/// Emitted by `create_raffle` with the fee it resolved for the new raffle.
+#[derive(Clone)]
+#[contractevent]
+pub struct FeeResolved {
+    pub raffle_address: Address,
+    pub creator: Address,
+    pub quote: FeeQuote,
+}
+
+/// Emitted by `sweep_fees` after moving instance fees to the treasury.
+#[derive(Clone)]
+#[contractevent]
+pub struct FeesSwept {
+    pub treasury: Address,
+    pub requested: u32,
+    pub swept: u32,
+    pub timestamp: u64,
+}
+
+/// Emitted when the creator bond policy for new raffles is replaced.
+#[derive(Clone)]
+#[contractevent]
+pub struct BondPolicyUpdated {
+    pub policy: BondPolicy,
+    pub updated_by: Address,
+    pub timestamp: u64,
+}
+
+/// Emitted when the reputation policy for new raffles is replaced.
+#[derive(Clone)]
+#[contractevent]
+pub struct ReputationPolicyUpdated {
+    pub policy: ReputationPolicy,
+    pub updated_by: Address,
+    pub timestamp: u64,
+}
@@ -0,0 +1,132 @@
+//! Secondary raffle indexes: by status and by participant.
+//!
+//! Each `RaffleStatus` owns a dense slot array (`StatusIndexLen` plus
+//! `StatusIndexSlot(status, position)` → stable_id), and every indexed raffle
+//! keeps a back-pointer to its slot in `RaffleStatusSlot`.  Moving a raffle
+//! between statuses swaps the bucket's last entry into the vacated slot, so
+//! every update touches a constant number of storage entries no matter how
+//! many raffles exist.
+//!
+//! Participants get an append-only slot array of the raffles they entered
+//! (`ParticipantRaffleCount`, `ParticipantRaffle(participant, position)`),
+//! deduplicated through `ParticipantEntered`.
+
+use raffle_shared::RaffleStatus;
+use soroban_sdk::{Address, Env};
+
+use crate::{bump_persistent, DataKey, StatusSlot};
+
+pub(crate) fn status_len(env: &Env, status: &RaffleStatus) -> u32 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::StatusIndexLen(status.clone()))
+        .unwrap_or(0u32)
+}
+
+pub(crate) fn status_entry(env: &Env, status: &RaffleStatus, position: u32) -> Option<u32> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::StatusIndexSlot(status.clone(), position))
+}
+
+/// Files `raffle_id` under `status`, moving it out of its previous bucket.
+pub(crate) fn set_status(env: &Env, raffle_id: u32, status: RaffleStatus) {
+    let current: Option<StatusSlot> = env
+        .storage()
+        .persistent()
+        .get(&DataKey::RaffleStatusSlot(raffle_id));
+    if let Some(slot) = current {
+        if slot.status == status {
+            return;
+        }
+        vacate(env, &slot);
+    }
+
+    let position = status_len(env, &status);
+    env.storage()
+        .persistent()
+        .set(&DataKey::StatusIndexSlot(status.clone(), position), &raffle_id);
+    env.storage().persistent().set(
+        &DataKey::StatusIndexLen(status.clone()),
+        &position.saturating_add(1),
+    );
+    env.storage().persistent().set(
+        &DataKey::RaffleStatusSlot(raffle_id),
+        &StatusSlot { status, position },
+    );
+}
+
+/// Drops `raffle_id` from whichever bucket currently holds it.
+pub(crate) fn remove(env: &Env, raffle_id: u32) {
+    let current: Option<StatusSlot> = env
+        .storage()
+        .persistent()
+        .get(&DataKey::RaffleStatusSlot(raffle_id));
+    if let Some(slot) = current {
+        vacate(env, &slot);
+        env.storage()
+            .persistent()
+            .remove(&DataKey::RaffleStatusSlot(raffle_id));
+    }
+}
+
+fn vacate(env: &Env, slot: &StatusSlot) {
+    let last = status_len(env, &slot.status).saturating_sub(1);
+    if slot.position != last {
+        if let Some(moved) = status_entry(env, &slot.status, last) {
+            env.storage().persistent().set(
+                &DataKey::StatusIndexSlot(slot.status.clone(), slot.position),
+                &moved,
+            );
+            env.storage().persistent().set(
+                &DataKey::RaffleStatusSlot(moved),
+                &StatusSlot {
+                    status: slot.status.clone(),
+                    position: slot.position,
+                },
+            );
+        }
+    }
+    env.storage()
+        .persistent()
+        .remove(&DataKey::StatusIndexSlot(slot.status.clone(), last));
+    env.storage()
+        .persistent()
+        .set(&DataKey::StatusIndexLen(slot.status.clone()), &last);
+}
+
+pub(crate) fn participant_len(env: &Env, participant: &Address) -> u32 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::ParticipantRaffleCount(participant.clone()))
+        .unwrap_or(0u32)
+}
+
+pub(crate) fn participant_entry(env: &Env, participant: &Address, position: u32) -> Option<u32> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::ParticipantRaffle(participant.clone(), position))
+}
+
+/// Appends `raffle_id` to `participant`'s list unless it is already there,
+/// returning whether it was added.
+pub(crate) fn add_participation(env: &Env, participant: &Address, raffle_id: u32) -> bool {
+    let entered = DataKey::ParticipantEntered(participant.clone(), raffle_id);
+    if env.storage().persistent().has(&entered) {
+        return false;
+    }
+    env.storage().persistent().set(&entered, &true);
+
+    let position = participant_len(env, participant);
+    env.storage().persistent().set(
+        &DataKey::ParticipantRaffle(participant.clone(), position),
+        &raffle_id,
+    );
+    env.storage().persistent().set(
+        &DataKey::ParticipantRaffleCount(participant.clone()),
+        &position.saturating_add(1),
+    );
+    bump_persistent(env, &DataKey::ParticipantRaffle(participant.clone(), position));
+    bump_persistent(env, &DataKey::ParticipantRaffleCount(participant.clone()));
+    true
+}
@@ -0,0 +1,221 @@
+//! Creator reputation: a `CreatorProfile` per creator, fed by instance
+//! status callbacks and outcome reports, and the `ReputationPolicy` that
+//! eases the rate limit, bond and fee for creators in good standing.
+//!
+//! Entries are keyed by `ReputationKey` rather than `DataKey`, which is at
+//! the `contracttype` variant limit.
+
+use raffle_shared::constants::{PERSISTENT_TTL_EXTEND_TO, PERSISTENT_TTL_THRESHOLD};
+use raffle_shared::{CancelReason, CreatorOutcome, CreatorProfile, ReputationPolicy};
+use soroban_sdk::{contracttype, Address, Env};
+
+use crate::{ContractError, DataKey};
+
+#[derive(Clone)]
+#[contracttype]
+pub enum ReputationKey {
+    /// `CreatorProfile` of a creator.
+    Profile(Address),
+    /// Creation timestamp of a live raffle, keyed by stable_id, for
+    /// time-to-finalize.
+    CreatedAt(u32),
+    /// Distinct buyers, other than the creator, of a live raffle.
+    Buyers(u32),
+    /// `ReputationPolicy` applied to new raffles.
+    Policy,
+}
+
+fn bump(env: &Env, key: &ReputationKey) {
+    if env.storage().persistent().has(key) {
+        env.storage()
+            .persistent()
+            .extend_ttl(key, PERSISTENT_TTL_THRESHOLD, PERSISTENT_TTL_EXTEND_TO);
+    }
+}
+
+pub(crate) fn profile(env: &Env, creator: &Address) -> CreatorProfile {
+    env.storage()
+        .persistent()
+        .get(&ReputationKey::Profile(creator.clone()))
+        .unwrap_or_default()
+}
+
+fn write_profile(env: &Env, creator: &Address, profile: &CreatorProfile) {
+    let key = ReputationKey::Profile(creator.clone());
+    env.storage().persistent().set(&key, profile);
+    bump(env, &key);
+}
+
+/// Applies `update` to the profile of the creator of `raffle_id`.  Raffles
+/// without a recorded creator are skipped.
+fn update(
+    env: &Env,
+    raffle_id: u32,
+    update: impl FnOnce(&mut CreatorProfile) -> Result<(), ContractError>,
+) -> Result<(), ContractError> {
+    let Some(creator) = env
+        .storage()
+        .persistent()
+        .get::<_, Address>(&DataKey::RaffleCreator(raffle_id))
+    else {
+        return Ok(());
+    };
+    let mut profile = profile(env, &creator);
+    update(&mut profile)?;
+    write_profile(env, &creator, &profile);
+    Ok(())
+}
+
+pub(crate) fn policy(env: &Env) -> Option<ReputationPolicy> {
+    env.storage().persistent().get(&ReputationKey::Policy)
+}
+
+pub(crate) fn set_policy(env: &Env, policy: &ReputationPolicy) {
+    env.storage().persistent().set(&ReputationKey::Policy, policy);
+}
+
+/// The reputation policy when `creator` is in good standing under it: at
+/// least `min_completed` completions reaching `min_buyers` buyers between
+/// them, with creator cancellations and `min_tickets` failures within
+/// `max_failure_bp` of ended raffles.
+pub(crate) fn standing_policy(env: &Env, creator: &Address) -> Option<ReputationPolicy> {
+    let policy = policy(env)?;
+    let profile = profile(env, creator);
+    if policy.min_completed == 0
+        || profile.completed < policy.min_completed
+        || profile.completed_buyers < policy.min_buyers as u64
+    {
+        return None;
+    }
+    let failures = profile.cancelled_by_creator as u64 + profile.failed_min_tickets as u64;
+    let ended = failures + profile.completed as u64;
+    if failures * 10_000 > policy.max_failure_bp as u64 * ended {
+        return None;
+    }
+    Some(policy)
+}
+
+pub(crate) fn record_created(env: &Env, raffle_id: u32, creator: &Address) {
+    env.storage().persistent().set(
+        &ReputationKey::CreatedAt(raffle_id),
+        &env.ledger().timestamp(),
+    );
+    let mut profile = profile(env, creator);
+    profile.raffles_created = profile.raffles_created.saturating_add(1);
+    write_profile(env, creator, &profile);
+}
+
+pub(crate) fn record_volume(
+    env: &Env,
+    creator: &Address,
+    amount: i128,
+) -> Result<(), ContractError> {
+    let mut profile = profile(env, creator);
+    profile.total_volume = profile
+        .total_volume
+        .checked_add(amount)
+        .ok_or(ContractError::ArithmeticOverflow)?;
+    write_profile(env, creator, &profile);
+    Ok(())
+}
+
+/// Counts a first-time buyer of `raffle_id` towards the creator's
+/// `completed_buyers` should the raffle finalize.  The creator's own
+/// purchases do not count.
+pub(crate) fn record_buyer(env: &Env, raffle_id: u32, buyer: &Address) {
+    let creator: Option<Address> = env
+        .storage()
+        .persistent()
+        .get(&DataKey::RaffleCreator(raffle_id));
+    if creator.as_ref() == Some(buyer) {
+        return;
+    }
+    let key = ReputationKey::Buyers(raffle_id);
+    let buyers: u32 = env.storage().persistent().get(&key).unwrap_or(0);
+    env.storage()
+        .persistent()
+        .set(&key, &buyers.saturating_add(1));
+    bump(env, &key);
+}
+
+/// Counts a raffle reaching `Finalized` as completed, together with its
+/// time since creation and its buyers.
+pub(crate) fn record_finalized(env: &Env, raffle_id: u32) -> Result<(), ContractError> {
+    let now = env.ledger().timestamp();
+    let created_at: u64 = env
+        .storage()
+        .persistent()
+        .get(&ReputationKey::CreatedAt(raffle_id))
+        .unwrap_or(now);
+    let buyers: u32 = env
+        .storage()
+        .persistent()
+        .get(&ReputationKey::Buyers(raffle_id))
+        .unwrap_or(0);
+    remove_raffle(env, raffle_id);
+    let elapsed = now.saturating_sub(created_at);
+    update(env, raffle_id, |profile| {
+        profile.completed = profile.completed.saturating_add(1);
+        profile.completed_buyers = profile.completed_buyers.saturating_add(buyers as u64);
+        profile.total_finalize_seconds = profile.total_finalize_seconds.saturating_add(elapsed);
+        profile.avg_finalize_seconds = profile.total_finalize_seconds / profile.completed as u64;
+        Ok(())
+    })
+}
+
+/// Counts a raffle reaching `Failed`, which only happens when
+/// `min_tickets` went unmet.
+pub(crate) fn record_failed(env: &Env, raffle_id: u32) -> Result<(), ContractError> {
+    remove_raffle(env, raffle_id);
+    update(env, raffle_id, |profile| {
+        profile.failed_min_tickets = profile.failed_min_tickets.saturating_add(1);
+        Ok(())
+    })
+}
+
+pub(crate) fn record_outcome(
+    env: &Env,
+    raffle_id: u32,
+    outcome: CreatorOutcome,
+) -> Result<(), ContractError> {
+    update(env, raffle_id, |profile| {
+        match outcome {
+            CreatorOutcome::Cancelled(CancelReason::CreatorCancelled) => {
+                profile.cancelled_by_creator = profile.cancelled_by_creator.saturating_add(1);
+            }
+            CreatorOutcome::Cancelled(CancelReason::MinTicketsNotMet) => {
+                profile.failed_min_tickets = profile.failed_min_tickets.saturating_add(1);
+            }
+            CreatorOutcome::Cancelled(_) => {}
+            CreatorOutcome::PrizePaid(amount) => {
+                if amount < 0 {
+                    return Err(ContractError::InvalidParameters);
+                }
+                profile.prizes_paid = profile
+                    .prizes_paid
+                    .checked_add(amount)
+                    .ok_or(ContractError::ArithmeticOverflow)?;
+            }
+        }
+        Ok(())
+    })
+}
+
+pub(crate) fn bump_policy(env: &Env) {
+    bump(env, &ReputationKey::Policy);
+}
+
+pub(crate) fn bump_raffle(env: &Env, raffle_id: u32) {
+    bump(env, &ReputationKey::CreatedAt(raffle_id));
+    bump(env, &ReputationKey::Buyers(raffle_id));
+}
+
+/// Drops the per-raffle entries once the raffle has ended.
+pub(crate) fn remove_raffle(env: &Env, raffle_id: u32) {
+    env.storage()
+        .persistent()
+        .remove(&ReputationKey::CreatedAt(raffle_id));
+    env.storage()
+        .persistent()
+        .remove(&ReputationKey::Buyers(raffle_id));
+}
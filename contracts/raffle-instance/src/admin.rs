@@ -1,80 +1,235 @@
-use soroban_sdk::{token, Address, Env};
+use soroban_sdk::{token, Address, BytesN, Env, Vec};
 
-use raffle_shared::CancelReason;
+use raffle_shared::roles::{self, Role, ALL_ROLES};
+use raffle_shared::{CancelReason, CreatorOutcome, FeeQuote, WipeProgress};
 
 use crate::events::{
-    ContractPaused, ContractUnpaused, EmergencyWithdrawn, FeesWithdrawn, OracleAddressUpdated,
-    ProtocolFeeUpdated, RaffleCancelled, SwapDeadlineUpdated, TicketSalesPaused, TicketSalesResumed,
-    TokensRescued,
+    CancelScheduled, ContractPaused, ContractUnpaused, EmergencyWithdrawn, FeesWithdrawn,
+    InstanceMigrated, InstanceUpgraded, OracleAddressUpdated, ParamChangeCancelled,
+    ParamChangeScheduled, ProtocolFeeUpdated, RaffleCancelled, RaffleStatusChanged, RolesUpdated,
+    SwapDeadlineUpdated, TicketSalesPaused, TicketSalesResumed, TokensRescued,
 };
 use crate::{
-    read_raffle, require_admin, write_raffle, DataKey, Error, RaffleStatus,
+    bump_persistent, has_role, publish_status_changed, read_raffle, report_creator_outcome,
+    require_role, write_raffle, CancelError, DataKey, Error, ParamChange, ParamChangeError,
+    PendingParamChange, Raffle, RaffleStatus, ADMIN_CANCEL_TIMELOCK_SECONDS,
     EMERGENCY_WITHDRAW_DELAY_SECONDS, MAX_PROTOCOL_FEE_BP, MAX_SWAP_DEADLINE_SECONDS,
+    PARAM_CHANGE_NOTICE_SECONDS, SCHEMA_VERSION,
 };
 
-pub(crate) fn set_admin(env: Env, new_admin: Address) -> Result<(), Error> {
-    let _old = require_admin(&env)?;
-    if !new_admin.exists() || new_admin == env.current_contract_address() {
-        return Err(Error::InvalidAdminAddress);
+/// Factory-only: replaces the member list of each listed role.
+pub(crate) fn set_roles(env: Env, assignments: Vec<(Role, Vec<Address>)>) -> Result<(), Error> {
+    let factory: Address = env.storage().instance().get(&DataKey::Factory).ok_or(Error::NotAuthorized)?;
+    factory.require_auth();
+    for (role, members) in assignments.iter() {
+        roles::set_members(&env, role, &members);
     }
-    env.storage().persistent().set(&DataKey::Admin, &new_admin);
+    RolesUpdated { updated_by: factory, timestamp: env.ledger().timestamp() }.publish(&env);
     Ok(())
 }
 
-pub(crate) fn update_oracle_address(env: Env, new_oracle: Address) -> Result<(), Error> {
-    let admin = require_admin(&env)?;
-    let mut raffle = read_raffle(&env)?;
-    if raffle.randomness_source != raffle_shared::RandomnessSource::External { return Err(Error::InvalidParameters); }
-    if new_oracle == env.current_contract_address() { return Err(Error::InvalidParameters); }
-    if raffle.status == RaffleStatus::Finalized || raffle.status == RaffleStatus::Claimed || raffle.status == RaffleStatus::Cancelled {
-        return Err(Error::InvalidStatus);
+/// Factory-only: stores the fee resolution behind `protocol_fee_bp`.
+pub(crate) fn set_fee_quote(env: Env, quote: FeeQuote) -> Result<(), Error> {
+    let factory: Address = env.storage().instance().get(&DataKey::Factory).ok_or(Error::NotAuthorized)?;
+    factory.require_auth();
+    if env.storage().instance().has(&DataKey::FeeQuote) { return Err(Error::AlreadyInitialized); }
+    env.storage().instance().set(&DataKey::FeeQuote, &quote);
+    Ok(())
+}
+
+/// `caller` is the raffle creator or holds `role`.
+fn is_creator_or(env: &Env, creator: &Address, caller: &Address, role: Role) -> bool {
+    caller == creator || has_role(env, role, caller)
+}
+
+pub(crate) fn update_oracle_address(env: Env, caller: Address, new_oracle: Address) -> Result<(), ParamChangeError> {
+    require_role(&env, Role::Moderator, &caller)?;
+    schedule_or_apply(&env, caller, ParamChange::OracleAddress(new_oracle))
+}
+
+pub(crate) fn set_protocol_fee_bp(env: Env, caller: Address, new_fee_bp: u32) -> Result<(), ParamChangeError> {
+    require_role(&env, Role::FeeManager, &caller)?;
+    schedule_or_apply(&env, caller, ParamChange::ProtocolFeeBp(new_fee_bp))
+}
+
+pub(crate) fn set_swap_deadline(env: Env, caller: Address, new_deadline_seconds: u64) -> Result<(), ParamChangeError> {
+    require_role(&env, Role::FeeManager, &caller)?;
+    schedule_or_apply(&env, caller, ParamChange::SwapDeadline(new_deadline_seconds))
+}
+
+/// Role allowed to set, and to cancel, a change of this kind.
+fn param_change_role(change: &ParamChange) -> Role {
+    match change {
+        ParamChange::OracleAddress(_) => Role::Moderator,
+        ParamChange::ProtocolFeeBp(_) | ParamChange::SwapDeadline(_) => Role::FeeManager,
+    }
+}
+
+fn validate_param_change(env: &Env, raffle: &Raffle, change: &ParamChange) -> Result<(), ParamChangeError> {
+    match change {
+        ParamChange::ProtocolFeeBp(fee_bp) => {
+            if *fee_bp > MAX_PROTOCOL_FEE_BP { return Err(ParamChangeError::InvalidParameters); }
+        }
+        ParamChange::OracleAddress(oracle) => {
+            if raffle.randomness_source != raffle_shared::RandomnessSource::External { return Err(ParamChangeError::InvalidParameters); }
+            if *oracle == env.current_contract_address() { return Err(ParamChangeError::InvalidParameters); }
+            if raffle.status == RaffleStatus::Finalized || raffle.status == RaffleStatus::Claimed || raffle.status == RaffleStatus::Cancelled {
+                return Err(ParamChangeError::InvalidStatus);
+            }
+        }
+        ParamChange::SwapDeadline(seconds) => {
+            if *seconds > MAX_SWAP_DEADLINE_SECONDS { return Err(ParamChangeError::InvalidParameters); }
+        }
     }
-    let old = raffle.oracle_address.clone();
-    raffle.oracle_address = Some(new_oracle.clone());
-    write_raffle(&env, &raffle);
-    OracleAddressUpdated { old_oracle: old, new_oracle, updated_by: admin, timestamp: env.ledger().timestamp() }.publish(&env);
     Ok(())
 }
 
-pub(crate) fn set_protocol_fee_bp(env: Env, new_fee_bp: u32) -> Result<(), Error> {
-    let admin = require_admin(&env)?;
-    if new_fee_bp > MAX_PROTOCOL_FEE_BP { return Err(Error::InvalidParameters); }
-    let mut raffle = read_raffle(&env)?;
-    if raffle.tickets_sold > 0 { return Err(Error::InvalidStatus); }
-    let old = raffle.protocol_fee_bp;
-    raffle.protocol_fee_bp = new_fee_bp;
-    write_raffle(&env, &raffle);
-    ProtocolFeeUpdated { old_fee_bp: old, new_fee_bp, updated_by: admin, timestamp: env.ledger().timestamp() }.publish(&env);
+/// Applies `change` straight away while no tickets are sold; otherwise
+/// schedules it behind `PARAM_CHANGE_NOTICE_SECONDS`, during which ticket
+/// holders may refund.
+fn schedule_or_apply(env: &Env, caller: Address, change: ParamChange) -> Result<(), ParamChangeError> {
+    let mut raffle = read_raffle(env)?;
+    validate_param_change(env, &raffle, &change)?;
+    if raffle.tickets_sold == 0 {
+        apply_param_change(env, &mut raffle, change, caller);
+        return Ok(());
+    }
+    // Notice-window refunds are only possible while sales are open.
+    if raffle.status != RaffleStatus::Active { return Err(ParamChangeError::InvalidStatus); }
+    if env.storage().instance().has(&DataKey::PendingParamChange) { return Err(ParamChangeError::ParamChangePending); }
+
+    let now = env.ledger().timestamp();
+    let pending = PendingParamChange {
+        change: change.clone(),
+        scheduled_by: caller.clone(),
+        scheduled_at: now,
+        effective_at: now + PARAM_CHANGE_NOTICE_SECONDS,
+    };
+    env.storage().instance().set(&DataKey::PendingParamChange, &pending);
+    ParamChangeScheduled { change, scheduled_by: caller, effective_at: pending.effective_at, timestamp: now }.publish(env);
     Ok(())
 }
 
-pub(crate) fn set_swap_deadline(env: Env, new_deadline_seconds: u64) -> Result<(), Error> {
-    let admin = require_admin(&env)?;
-    if new_deadline_seconds > MAX_SWAP_DEADLINE_SECONDS { return Err(Error::InvalidParameters); }
+fn apply_param_change(env: &Env, raffle: &mut Raffle, change: ParamChange, updated_by: Address) {
+    let timestamp = env.ledger().timestamp();
+    match change {
+        ParamChange::ProtocolFeeBp(new_fee_bp) => {
+            let old = raffle.protocol_fee_bp;
+            raffle.protocol_fee_bp = new_fee_bp;
+            write_raffle(env, raffle);
+            ProtocolFeeUpdated { old_fee_bp: old, new_fee_bp, updated_by, timestamp }.publish(env);
+        }
+        ParamChange::OracleAddress(new_oracle) => {
+            let old = raffle.oracle_address.clone();
+            raffle.oracle_address = Some(new_oracle.clone());
+            write_raffle(env, raffle);
+            OracleAddressUpdated { old_oracle: old, new_oracle, updated_by, timestamp }.publish(env);
+        }
+        ParamChange::SwapDeadline(new_deadline_seconds) => {
+            let old = raffle.swap_deadline_seconds;
+            raffle.swap_deadline_seconds = new_deadline_seconds;
+            write_raffle(env, raffle);
+            SwapDeadlineUpdated { old_deadline_seconds: old, new_deadline_seconds, updated_by, timestamp }.publish(env);
+        }
+    }
+}
+
+pub(crate) fn execute_param_change(env: Env, caller: Address) -> Result<(), ParamChangeError> {
+    caller.require_auth();
+    let pending: PendingParamChange = env.storage().instance().get(&DataKey::PendingParamChange).ok_or(ParamChangeError::NoPendingParamChange)?;
+    if env.ledger().timestamp() < pending.effective_at { return Err(ParamChangeError::ParamChangeNoticeActive); }
     let mut raffle = read_raffle(&env)?;
-    if raffle.tickets_sold > 0 { return Err(Error::InvalidStatus); }
-    let old = raffle.swap_deadline_seconds;
-    raffle.swap_deadline_seconds = new_deadline_seconds;
-    write_raffle(&env, &raffle);
-    SwapDeadlineUpdated { old_deadline_seconds: old, new_deadline_seconds, updated_by: admin, timestamp: env.ledger().timestamp() }.publish(&env);
+    // Sales may have closed during the notice window; the draw may still
+    // need the new oracle or deadline.
+    if raffle.status != RaffleStatus::Active && raffle.status != RaffleStatus::Drawing { return Err(ParamChangeError::InvalidStatus); }
+    validate_param_change(&env, &raffle, &pending.change)?;
+    env.storage().instance().remove(&DataKey::PendingParamChange);
+    apply_param_change(&env, &mut raffle, pending.change, pending.scheduled_by);
+    Ok(())
+}
+
+pub(crate) fn cancel_param_change(env: Env, caller: Address) -> Result<(), ParamChangeError> {
+    let pending: PendingParamChange = env.storage().instance().get(&DataKey::PendingParamChange).ok_or(ParamChangeError::NoPendingParamChange)?;
+    require_role(&env, param_change_role(&pending.change), &caller)?;
+    env.storage().instance().remove(&DataKey::PendingParamChange);
+    ParamChangeCancelled { change: pending.change, cancelled_by: caller, timestamp: env.ledger().timestamp() }.publish(&env);
     Ok(())
 }
 
 pub(crate) fn cancel_raffle(env: Env, reason: CancelReason) -> Result<(), Error> {
     let mut raffle = read_raffle(&env)?;
     match reason {
+        // Moderators cancel through the factory, which checks the role.
         CancelReason::AdminCancelled => {
-            let admin: Address = env.storage().instance().get(&DataKey::Admin).ok_or(Error::NotAuthorized)?;
-            admin.require_auth();
+            let factory: Address = env.storage().instance().get(&DataKey::Factory).ok_or(Error::NotAuthorized)?;
+            factory.require_auth();
         }
         _ => raffle.creator.require_auth(),
     }
-    if raffle.status == RaffleStatus::Finalized || raffle.status == RaffleStatus::Cancelled || raffle.status == RaffleStatus::Claimed {
+    if raffle.status == RaffleStatus::Finalized || raffle.status == RaffleStatus::Cancelled || raffle.status == RaffleStatus::Claimed || raffle.status == RaffleStatus::Failed {
         return Err(Error::InvalidStatus);
     }
+
+    // #406: an admin cancelling a raffle that has sold tickets only
+    // schedules it; holders may refund in the meantime.
+    if reason == CancelReason::AdminCancelled && raffle.tickets_sold > 0 {
+        if env.storage().instance().has(&DataKey::PendingAdminCancel) { return Err(Error::InvalidStateTransition); }
+        let factory: Address = env.storage().instance().get(&DataKey::Factory).ok_or(Error::NotAuthorized)?;
+        let now = env.ledger().timestamp();
+        let cancel_at = now + ADMIN_CANCEL_TIMELOCK_SECONDS;
+        env.storage().instance().set(&DataKey::PendingAdminCancel, &cancel_at);
+        CancelScheduled { creator: raffle.creator.clone(), scheduled_by: factory, tickets_sold: raffle.tickets_sold, cancel_at, timestamp: now }.publish(&env);
+        return Ok(());
+    }
+
+    apply_cancel(&env, &mut raffle, reason)
+}
+
+/// Executes an admin cancellation once the timelock set by `cancel_raffle`
+/// has elapsed.
+pub(crate) fn execute_admin_cancel(env: Env) -> Result<(), CancelError> {
+    let factory: Address = env.storage().instance().get(&DataKey::Factory).ok_or(Error::NotAuthorized)?;
+    factory.require_auth();
+    let cancel_at: u64 = env.storage().instance().get(&DataKey::PendingAdminCancel).ok_or(CancelError::CancelNotScheduled)?;
+    let mut raffle = read_raffle(&env)?;
+    if raffle.status == RaffleStatus::Finalized || raffle.status == RaffleStatus::Cancelled || raffle.status == RaffleStatus::Claimed || raffle.status == RaffleStatus::Failed {
+        return Err(CancelError::InvalidStatus);
+    }
+    if env.ledger().timestamp() < cancel_at { return Err(CancelError::CancelTimelockActive); }
+    env.storage().instance().remove(&DataKey::PendingAdminCancel);
+    apply_cancel(&env, &mut raffle, CancelReason::AdminCancelled)?;
+    Ok(())
+}
+
+fn apply_cancel(env: &Env, raffle: &mut Raffle, reason: CancelReason) -> Result<(), Error> {
+    let old_status = raffle.status.clone();
+    let was_drawing = old_status == RaffleStatus::Drawing;
     raffle.status = RaffleStatus::Cancelled;
-    write_raffle(&env, &raffle);
-    RaffleCancelled { creator: raffle.creator.clone(), reason, tickets_sold: raffle.tickets_sold, prize_refunded: raffle.prize_deposited, timestamp: env.ledger().timestamp() }.publish(&env);
+    write_raffle(env, raffle);
+    // Moderator cancels arrive from the factory, which re-files the raffle
+    // once this call returns; reporting back would re-enter it.
+    let from_factory = reason == CancelReason::AdminCancelled;
+    if from_factory {
+        RaffleStatusChanged { old_status: old_status.clone(), new_status: RaffleStatus::Cancelled, timestamp: env.ledger().timestamp() }.publish(env);
+    } else {
+        publish_status_changed(env, old_status.clone(), RaffleStatus::Cancelled, env.ledger().timestamp());
+    }
+    crate::bond::slash_on_cancel(env, raffle, &reason)?;
+    if !from_factory {
+        report_creator_outcome(env, CreatorOutcome::Cancelled(reason.clone()));
+    }
+
+    // A cancel during the draw drops the pending randomness and releases the
+    // lock so the raffle cannot stay bricked.
+    if was_drawing {
+        env.storage().instance().remove(&DataKey::RandomnessRequested);
+        env.storage().instance().remove(&DataKey::RandomnessRequestId);
+        env.storage().instance().remove(&DataKey::RandomnessRequestLedger);
+        env.storage().instance().remove(&DataKey::DrawCommitment);
+        env.storage().instance().set(&DataKey::DrawingLock, &false);
+    }
+
+    RaffleCancelled { creator: raffle.creator.clone(), reason, tickets_sold: raffle.tickets_sold, prize_refunded: raffle.prize_deposited, timestamp: env.ledger().timestamp() }.publish(env);
     Ok(())
 }
 
@@ -97,8 +252,7 @@ pub(crate) fn unpause(env: Env) -> Result<(), Error> {
 pub(crate) fn pause_ticket_sales(env: Env, caller: Address) -> Result<(), Error> {
     caller.require_auth();
     let mut raffle = read_raffle(&env)?;
-    let admin: Address = env.storage().instance().get(&DataKey::Admin).ok_or(Error::NotAuthorized)?;
-    if caller != raffle.creator && caller != admin { return Err(Error::NotAuthorized); }
+    if !is_creator_or(&env, &raffle.creator, &caller, Role::Pauser) { return Err(Error::NotAuthorized); }
     if raffle.status != RaffleStatus::Active { return Err(Error::InvalidStatus); }
     raffle.ticket_sales_paused = true;
     write_raffle(&env, &raffle);
@@ -109,8 +263,7 @@ pub(crate) fn pause_ticket_sales(env: Env, caller: Address) -> Result<(), Error>
 pub(crate) fn resume_ticket_sales(env: Env, caller: Address) -> Result<(), Error> {
     caller.require_auth();
     let mut raffle = read_raffle(&env)?;
-    let admin: Address = env.storage().instance().get(&DataKey::Admin).ok_or(Error::NotAuthorized)?;
-    if caller != raffle.creator && caller != admin { return Err(Error::NotAuthorized); }
+    if !is_creator_or(&env, &raffle.creator, &caller, Role::Pauser) { return Err(Error::NotAuthorized); }
     if raffle.status != RaffleStatus::Active { return Err(Error::InvalidStatus); }
     raffle.ticket_sales_paused = false;
     write_raffle(&env, &raffle);
@@ -118,74 +271,182 @@ pub(crate) fn resume_ticket_sales(env: Env, caller: Address) -> Result<(), Error
     Ok(())
 }
 
-pub(crate) fn withdraw_fees(env: Env, recipient: Address, amount: i128) -> Result<(), Error> {
-    let _admin = require_admin(&env)?;
+pub(crate) fn sweep_fees(env: Env, recipient: Address) -> Result<i128, Error> {
+    let factory: Address = env.storage().instance().get(&DataKey::Factory).ok_or(Error::NotAuthorized)?;
+    factory.require_auth();
     let raffle = read_raffle(&env)?;
-    if raffle.status != RaffleStatus::Finalized && raffle.status != RaffleStatus::Claimed { return Err(Error::InvalidStatus); }
-    if amount <= 0 { return Err(Error::InvalidParameters); }
+    // Fees of a raffle that may still refund are not ready to sweep.
+    if raffle.status != RaffleStatus::Finalized && raffle.status != RaffleStatus::Claimed { return Ok(0); }
     let acc: i128 = env.storage().instance().get(&DataKey::AccumulatedFees).unwrap_or(0);
-    if amount > acc { return Err(Error::InsufficientAccumulatedFees); }
+    if acc <= 0 { return Ok(0); }
     let tc = token::Client::new(&env, &raffle.payment_token);
-    tc.transfer(&env.current_contract_address(), &recipient, &amount);
-    env.storage().instance().set(&DataKey::AccumulatedFees, &(acc - amount));
-    FeesWithdrawn { recipient, amount, token: raffle.payment_token.clone(), timestamp: env.ledger().timestamp() }.publish(&env);
-    Ok(())
+    tc.transfer(&env.current_contract_address(), &recipient, &acc);
+    env.storage().instance().set(&DataKey::AccumulatedFees, &0i128);
+    FeesWithdrawn { recipient, amount: acc, token: raffle.payment_token.clone(), timestamp: env.ledger().timestamp() }.publish(&env);
+    Ok(acc)
 }
 
-pub(crate) fn rescue_tokens(env: Env, token: Address, recipient: Address, amount: i128) -> Result<(), Error> {
-    let admin: Address = env.storage().instance().get(&DataKey::Admin).ok_or(Error::NotAuthorized)?;
-    admin.require_auth();
+pub(crate) fn rescue_tokens(env: Env, caller: Address, token: Address, recipient: Address, amount: i128) -> Result<(), Error> {
+    require_role(&env, Role::RescueOperator, &caller)?;
     if amount <= 0 { return Err(Error::InvalidParameters); }
     if let Ok(raffle) = read_raffle(&env) {
-        if token == raffle.payment_token && raffle.prize_deposited { return Err(Error::InvalidParameters); }
+        if (token == raffle.prize_token || token == raffle.payment_token) && raffle.prize_deposited { return Err(Error::InvalidParameters); }
     }
     let tc = token::Client::new(&env, &token);
     let _ = tc.try_transfer(&env.current_contract_address(), &recipient, &amount).map_err(|_| Error::TokenTransferFailed)?;
-    TokensRescued { rescued_by: admin, token, recipient, amount, timestamp: env.ledger().timestamp() }.publish(&env);
+    TokensRescued { rescued_by: caller, token, recipient, amount, timestamp: env.ledger().timestamp() }.publish(&env);
     Ok(())
 }
 
 pub(crate) fn wipe_storage(env: Env) -> Result<(), Error> {
+    wipe_storage_batch(env, 0, u32::MAX).map(|_| ())
+}
+
+/// Removes up to `max_items` tickets or buyer records starting at `cursor`.
+/// Ticket `i` is item `i - 1`; buyer `j` in `TicketBuyers` is item
+/// `tickets_sold + j`.  The batch that reaches the end also drops every
+/// instance-level key, after which the raffle is gone.
+pub(crate) fn wipe_storage_batch(env: Env, cursor: u32, max_items: u32) -> Result<WipeProgress, Error> {
     let factory: Address = env.storage().instance().get(&DataKey::Factory).ok_or(Error::NotAuthorized)?;
     factory.require_auth();
     let raffle = read_raffle(&env)?;
     if raffle.status != RaffleStatus::Cancelled && raffle.status != RaffleStatus::Claimed && raffle.status != RaffleStatus::Failed {
         return Err(Error::InvalidStatus);
     }
+    if max_items == 0 { return Err(Error::InvalidParameters); }
+    if cursor == 0 { crate::bond::release_on_wipe(&env, &raffle)?; }
 
-    for i in 1..=raffle.tickets_sold {
-        env.storage().persistent().remove(&DataKey::Ticket(i));
-        env.storage().persistent().remove(&DataKey::TicketRefunded(i));
-        env.storage().persistent().remove(&DataKey::CommitEntry(i));
-    }
     let buyers: soroban_sdk::Vec<Address> = env.storage().persistent().get(&DataKey::TicketBuyers).unwrap_or_else(|| soroban_sdk::Vec::new(&env));
-    for b in buyers.iter() { env.storage().persistent().remove(&DataKey::TicketCount(b.clone())); }
-    env.storage().persistent().remove(&DataKey::TicketBuyers);
+    let sold = raffle.tickets_sold;
+    let total_items = sold.saturating_add(buyers.len());
+    if cursor > total_items { return Err(Error::InvalidParameters); }
+    let end = cursor.saturating_add(max_items).min(total_items);
 
-    env.storage().instance().remove(&DataKey::Raffle);
-    env.storage().instance().remove(&DataKey::Factory);
-    env.storage().instance().remove(&DataKey::Admin);
-    env.storage().instance().remove(&DataKey::Paused);
-    env.storage().instance().remove(&DataKey::ReentrancyGuard);
-    env.storage().instance().remove(&DataKey::AccumulatedFees);
-    env.storage().instance().remove(&DataKey::RandomnessRequested);
-    env.storage().instance().remove(&DataKey::RandomnessRequestLedger);
-    env.storage().instance().remove(&DataKey::RandomnessRequestId);
-    env.storage().instance().remove(&DataKey::DrawingLock);
-    env.storage().instance().remove(&DataKey::FinishTime);
-    env.storage().persistent().remove(&DataKey::RandomnessSeed);
-    env.storage().persistent().remove(&DataKey::Admin);
+    for item in cursor..end {
+        if item < sold {
+            let id = item + 1;
+            env.storage().persistent().remove(&DataKey::Ticket(id));
+            env.storage().persistent().remove(&DataKey::TicketRefunded(id));
+            env.storage().persistent().remove(&DataKey::CommitEntry(id));
+            env.storage().persistent().remove(&DataKey::TicketPaid(id));
+            env.storage().persistent().remove(&DataKey::TicketPayer(id));
+        } else if let Some(b) = buyers.get(item - sold) {
+            env.storage().persistent().remove(&DataKey::TicketCount(b.clone()));
+            env.storage().persistent().remove(&DataKey::OwnerTickets(b));
+        }
+    }
+
+    let done = end == total_items;
+    if done {
+        env.storage().persistent().remove(&DataKey::TicketBuyers);
+
+        env.storage().instance().remove(&DataKey::Raffle);
+        env.storage().instance().remove(&DataKey::Factory);
+        env.storage().instance().remove(&DataKey::Admin);
+        env.storage().instance().remove(&DataKey::Paused);
+        env.storage().instance().remove(&DataKey::ReentrancyGuard);
+        env.storage().instance().remove(&DataKey::AccumulatedFees);
+        env.storage().instance().remove(&DataKey::RandomnessRequested);
+        env.storage().instance().remove(&DataKey::RandomnessRequestLedger);
+        env.storage().instance().remove(&DataKey::RandomnessRequestId);
+        env.storage().instance().remove(&DataKey::DrawingLock);
+        env.storage().instance().remove(&DataKey::DrawCommitment);
+        env.storage().instance().remove(&DataKey::FinishTime);
+        env.storage().instance().remove(&DataKey::PendingAdminCancel);
+        env.storage().instance().remove(&DataKey::CreatedAt);
+        env.storage().instance().remove(&DataKey::SchemaVersion);
+        env.storage().instance().remove(&DataKey::GrossVolume);
+        env.storage().instance().remove(&DataKey::PendingParamChange);
+        env.storage().instance().remove(&DataKey::TicketsIssued);
+        env.storage().instance().remove(&DataKey::FeeQuote);
+        env.storage().instance().remove(&DataKey::CreatorBond);
+        env.storage().instance().remove(&DataKey::BondPerTicket);
+        env.storage().instance().remove(&DataKey::SaleGate);
+        env.storage().instance().remove(&DataKey::AddressTicketCap);
+        env.storage().instance().remove(&DataKey::RefundTarget);
+        env.storage().persistent().remove(&DataKey::RandomnessSeed);
+        env.storage().persistent().remove(&DataKey::Fairness);
+        env.storage().persistent().remove(&DataKey::Admin);
+        for role in ALL_ROLES {
+            roles::set_members(&env, role, &Vec::new(&env));
+        }
+    }
 
+    Ok(WipeProgress { next_cursor: end, total_items, done })
+}
+
+pub(crate) fn upgrade(env: Env, new_wasm_hash: BytesN<32>) -> Result<(), Error> {
+    let factory: Address = env.storage().instance().get(&DataKey::Factory).ok_or(Error::NotAuthorized)?;
+    factory.require_auth();
+    // Swapping code mid-draw could change winner selection between the
+    // commitment and its execution.
+    if env.storage().instance().get(&DataKey::DrawingLock).unwrap_or(false) {
+        return Err(Error::DrawingAlreadyInProgress);
+    }
+    let schema_version: u32 = env.storage().instance().get(&DataKey::SchemaVersion).unwrap_or(0);
+    env.deployer().update_current_contract_wasm(new_wasm_hash.clone());
+    InstanceUpgraded { new_wasm_hash, schema_version, timestamp: env.ledger().timestamp() }.publish(&env);
+    Ok(())
+}
+
+pub(crate) fn migrate(env: Env, from_version: u32) -> Result<(), Error> {
+    let factory: Address = env.storage().instance().get(&DataKey::Factory).ok_or(Error::NotAuthorized)?;
+    factory.require_auth();
+    let stored: u32 = env.storage().instance().get(&DataKey::SchemaVersion).unwrap_or(0);
+    if from_version != stored || from_version > SCHEMA_VERSION {
+        return Err(Error::InvalidParameters);
+    }
+    // One arm per layout change, applied in order.  v0 -> v1 only
+    // introduced `SchemaVersion` itself, so there is nothing to rewrite.
+    env.storage().instance().set(&DataKey::SchemaVersion, &SCHEMA_VERSION);
+    InstanceMigrated { from_version, to_version: SCHEMA_VERSION, timestamp: env.ledger().timestamp() }.publish(&env);
     Ok(())
 }
 
+/// Bumps `key` if it exists; keeper sweeps must not trap on absent entries.
+fn bump_if_present(env: &Env, key: &DataKey) {
+    if env.storage().persistent().has(key) {
+        bump_persistent(env, key);
+    }
+}
+
+pub(crate) fn extend_raffle_ttl(env: Env, cursor: u32, max_items: u32) -> Result<u32, Error> {
+    if max_items == 0 { return Err(Error::InvalidParameters); }
+    let raffle = read_raffle(&env)?;
+    bump_if_present(&env, &DataKey::Admin);
+    roles::bump_roles(&env);
+    bump_if_present(&env, &DataKey::TicketBuyers);
+    bump_if_present(&env, &DataKey::Fairness);
+    bump_if_present(&env, &DataKey::RandomnessSeed);
+
+    let buyers: soroban_sdk::Vec<Address> = env.storage().persistent().get(&DataKey::TicketBuyers).unwrap_or_else(|| soroban_sdk::Vec::new(&env));
+    let sold = raffle.tickets_sold;
+    let total_items = sold.saturating_add(buyers.len());
+    if cursor > total_items { return Err(Error::InvalidParameters); }
+    let end = cursor.saturating_add(max_items).min(total_items);
+
+    for item in cursor..end {
+        if item < sold {
+            let id = item + 1;
+            bump_if_present(&env, &DataKey::Ticket(id));
+            bump_if_present(&env, &DataKey::TicketRefunded(id));
+            bump_if_present(&env, &DataKey::CommitEntry(id));
+            bump_if_present(&env, &DataKey::TicketPaid(id));
+            bump_if_present(&env, &DataKey::TicketPayer(id));
+        } else if let Some(b) = buyers.get(item - sold) {
+            bump_if_present(&env, &DataKey::TicketCount(b.clone()));
+            bump_if_present(&env, &DataKey::OwnerTickets(b));
+        }
+    }
+    Ok(end)
+}
+
 pub(crate) fn emergency_withdraw(env: Env, caller: Address) -> Result<(), Error> {
     caller.require_auth();
     let mut raffle = read_raffle(&env)?;
     if !raffle.prize_deposited { return Err(Error::PrizeNotDeposited); }
 
-    let admin: Address = env.storage().instance().get(&DataKey::Admin).ok_or(Error::NotAuthorized)?;
-    if caller != raffle.creator && caller != admin { return Err(Error::NotAuthorized); }
+    if !is_creator_or(&env, &raffle.creator, &caller, Role::Guardian) { return Err(Error::NotAuthorized); }
 
     let now = env.ledger().timestamp();
     match raffle.status {
@@ -205,13 +466,15 @@ pub(crate) fn emergency_withdraw(env: Env, caller: Address) -> Result<(), Error>
         _ => return Err(Error::InvalidStatus),
     }
 
+    let old_status = raffle.status.clone();
     raffle.prize_deposited = false;
     raffle.status = RaffleStatus::Cancelled;
     write_raffle(&env, &raffle);
+    publish_status_changed(&env, old_status, RaffleStatus::Cancelled, now);
 
-    let tc = token::Client::new(&env, &raffle.payment_token);
+    let tc = token::Client::new(&env, &raffle.prize_token);
     tc.transfer(&env.current_contract_address(), &raffle.creator, &raffle.prize_amount);
 
-    EmergencyWithdrawn { withdrawn_by: caller, to: raffle.creator.clone(), amount: raffle.prize_amount, token: raffle.payment_token.clone(), timestamp: now }.publish(&env);
+    EmergencyWithdrawn { withdrawn_by: caller, to: raffle.creator.clone(), amount: raffle.prize_amount, token: raffle.prize_token.clone(), timestamp: now }.publish(&env);
     Ok(())
 }
@@ -0,0 +1,95 @@
+//! Creator bond.  Posted with the prize, slashed if the creator cancels
+//! after tickets have sold, and otherwise paid back once the raffle ends.
+
+use soroban_sdk::{token, Address, Env};
+
+use raffle_shared::{BondSlashTarget, BondStatus, CancelReason, CreatorBond};
+
+use crate::events::{BondPosted, BondReturned, BondSlashed};
+use crate::{read_raffle, BondError, DataKey, Error, Raffle, RaffleStatus};
+
+pub(crate) fn read_bond(env: &Env) -> Option<CreatorBond> {
+    env.storage().instance().get(&DataKey::CreatorBond)
+}
+
+/// Factory-only: records the bond the creator must post with the prize.
+pub(crate) fn set_creator_bond(env: Env, bond: CreatorBond) -> Result<(), BondError> {
+    let factory: Address = env.storage().instance().get(&DataKey::Factory).ok_or(BondError::NotAuthorized)?;
+    factory.require_auth();
+    let raffle = read_raffle(&env)?;
+    if raffle.prize_deposited || env.storage().instance().has(&DataKey::CreatorBond) { return Err(BondError::AlreadyInitialized); }
+    if bond.amount <= 0 || bond.status != BondStatus::Pending { return Err(BondError::InvalidParameters); }
+    env.storage().instance().set(&DataKey::CreatorBond, &bond);
+    Ok(())
+}
+
+/// Pulls a pending bond from the creator.  Called by `deposit_prize`.
+pub(crate) fn post(env: &Env, raffle: &Raffle) -> Result<(), Error> {
+    let Some(mut bond) = read_bond(env) else { return Ok(()) };
+    if bond.status != BondStatus::Pending { return Ok(()); }
+    let tc = token::Client::new(env, &raffle.payment_token);
+    let _ = tc.try_transfer(&raffle.creator, env.current_contract_address(), &bond.amount).map_err(|_| Error::TokenTransferFailed)?;
+    bond.status = BondStatus::Posted;
+    env.storage().instance().set(&DataKey::CreatorBond, &bond);
+    BondPosted { creator: raffle.creator.clone(), amount: bond.amount, token: raffle.payment_token.clone(), timestamp: env.ledger().timestamp() }.publish(env);
+    Ok(())
+}
+
+/// Slashes the bond when the creator cancels after sales.  Holders get
+/// an even share per ticket, paid with each refund; the remainder after
+/// integer division goes to the treasury.
+pub(crate) fn slash_on_cancel(env: &Env, raffle: &Raffle, reason: &CancelReason) -> Result<(), Error> {
+    if *reason != CancelReason::CreatorCancelled || raffle.tickets_sold == 0 { return Ok(()); }
+    let Some(mut bond) = read_bond(env) else { return Ok(()) };
+    if bond.status != BondStatus::Posted { return Ok(()); }
+
+    let per_ticket = match bond.slash_target {
+        BondSlashTarget::Holders => bond.amount / raffle.tickets_sold as i128,
+        BondSlashTarget::Treasury => 0,
+    };
+    let to_treasury = bond.amount - per_ticket * raffle.tickets_sold as i128;
+    if per_ticket > 0 { env.storage().instance().set(&DataKey::BondPerTicket, &per_ticket); }
+    if to_treasury > 0 {
+        let tc = token::Client::new(env, &raffle.payment_token);
+        let _ = tc.try_transfer(&env.current_contract_address(), &bond.treasury, &to_treasury).map_err(|_| Error::TokenTransferFailed)?;
+    }
+    bond.status = BondStatus::Slashed;
+    env.storage().instance().set(&DataKey::CreatorBond, &bond);
+    BondSlashed { creator: raffle.creator.clone(), amount: bond.amount, slash_target: bond.slash_target, per_ticket, to_treasury, timestamp: env.ledger().timestamp() }.publish(env);
+    Ok(())
+}
+
+/// Bond share added to each ticket refund after a slash to holders.
+pub(crate) fn refund_share(env: &Env) -> i128 {
+    env.storage().instance().get(&DataKey::BondPerTicket).unwrap_or(0)
+}
+
+fn pay_back(env: &Env, raffle: &Raffle, mut bond: CreatorBond) -> Result<i128, Error> {
+    bond.status = BondStatus::Returned;
+    env.storage().instance().set(&DataKey::CreatorBond, &bond);
+    let tc = token::Client::new(env, &raffle.payment_token);
+    let _ = tc.try_transfer(&env.current_contract_address(), &raffle.creator, &bond.amount).map_err(|_| Error::TokenTransferFailed)?;
+    BondReturned { creator: raffle.creator.clone(), amount: bond.amount, timestamp: env.ledger().timestamp() }.publish(env);
+    Ok(bond.amount)
+}
+
+/// Pays a posted bond back to the creator once the raffle has ended
+/// without a slash.
+pub(crate) fn reclaim_bond(env: Env) -> Result<i128, BondError> {
+    let raffle = read_raffle(&env)?;
+    raffle.creator.require_auth();
+    let bond = read_bond(&env).filter(|b| b.status == BondStatus::Posted).ok_or(BondError::NoBond)?;
+    match raffle.status {
+        RaffleStatus::Finalized | RaffleStatus::Claimed | RaffleStatus::Cancelled | RaffleStatus::Failed => {}
+        _ => return Err(BondError::InvalidStatus),
+    }
+    Ok(pay_back(&env, &raffle, bond)?)
+}
+
+/// Pays back a bond the creator never reclaimed before storage is wiped.
+pub(crate) fn release_on_wipe(env: &Env, raffle: &Raffle) -> Result<(), Error> {
+    if let Some(bond) = read_bond(env).filter(|b| b.status == BondStatus::Posted) {
+        pay_back(env, raffle, bond)?;
+    }
+    Ok(())
+}
@@ -1,8 +1,13 @@
-use soroban_sdk::{token, Address, Env};
+use soroban_sdk::{token, Address, Env, Vec};
 
-use crate::events::{PrizeClaimed, PrizeRefunded, RaffleStatusChanged, TicketRefunded};
+use raffle_shared::{CreatorOutcome, Ticket};
+
+use crate::events::{PrizeClaimed, PrizeRefunded, TicketRefunded, TicketRenumbered};
+use crate::tickets::refund_recipient;
 use crate::{
-    calculate_tier_prize, read_raffle, write_raffle, DataKey, Error, Guard, RaffleStatus,
+    bump_persistent, calculate_tier_prize, publish_status_changed, read_raffle,
+    report_creator_outcome, report_volume, write_raffle,
+    CommitRevealEntry, DataKey, Error, Guard, Raffle, RaffleStatus, TicketPayment,
 };
 
 pub(crate) fn claim_prize(env: Env, winner: Address, tier_index: u32) -> Result<i128, Error> {
@@ -25,16 +30,17 @@ pub(crate) fn claim_prize(env: Env, winner: Address, tier_index: u32) -> Result<
 
     let mut all_claimed = true;
     for c in raffle.claimed_winners.iter() { if !c { all_claimed = false; break; } }
+    if all_claimed { raffle.status = RaffleStatus::Claimed; }
+    write_raffle(&env, &raffle);
     if all_claimed {
-        raffle.status = RaffleStatus::Claimed;
-        RaffleStatusChanged { old_status: RaffleStatus::Finalized, new_status: RaffleStatus::Claimed, timestamp: env.ledger().timestamp() }.publish(&env);
+        publish_status_changed(&env, RaffleStatus::Finalized, RaffleStatus::Claimed, env.ledger().timestamp());
     }
-    write_raffle(&env, &raffle);
 
-    let tc = token::Client::new(&env, &raffle.payment_token);
+    let tc = token::Client::new(&env, &raffle.prize_token);
     let _ = tc.try_transfer(&env.current_contract_address(), &winner, &amount).map_err(|_| Error::TokenTransferFailed)?;
 
-    PrizeClaimed { winner, tier_index, payment_token: raffle.payment_token.clone(), gross_amount: amount, net_amount: amount, platform_fee: 0, claimed_at: env.ledger().timestamp() }.publish(&env);
+    PrizeClaimed { winner, tier_index, payment_token: raffle.prize_token.clone(), gross_amount: amount, net_amount: amount, platform_fee: 0, claimed_at: env.ledger().timestamp() }.publish(&env);
+    report_creator_outcome(&env, CreatorOutcome::PrizePaid(amount));
     Ok(amount)
 }
 
@@ -48,27 +54,162 @@ pub(crate) fn refund_prize(env: Env) -> Result<(), Error> {
     raffle.prize_deposited = false;
     write_raffle(&env, &raffle);
 
-    let tc = token::Client::new(&env, &raffle.payment_token);
+    let tc = token::Client::new(&env, &raffle.prize_token);
     let _ = tc.try_transfer(&env.current_contract_address(), &raffle.creator, &raffle.prize_amount).map_err(|_| Error::TokenTransferFailed)?;
 
-    PrizeRefunded { creator: raffle.creator.clone(), amount: raffle.prize_amount, token: raffle.payment_token.clone(), timestamp: env.ledger().timestamp() }.publish(&env);
+    PrizeRefunded { creator: raffle.creator.clone(), amount: raffle.prize_amount, token: raffle.prize_token.clone(), timestamp: env.ledger().timestamp() }.publish(&env);
     Ok(())
 }
 
 pub(crate) fn refund_ticket(env: Env, ticket_id: u32) -> Result<i128, Error> {
     let raffle = read_raffle(&env)?;
-    if raffle.status != RaffleStatus::Cancelled && raffle.status != RaffleStatus::Failed { return Err(Error::InvalidStatus); }
+    // #406: holders may refund as soon as an admin cancel is scheduled,
+    // without waiting for the timelock.
+    let cancel_scheduled = env.storage().instance().has(&DataKey::PendingAdminCancel);
+    if raffle.status == RaffleStatus::Active && !cancel_scheduled && env.storage().instance().has(&DataKey::PendingParamChange) {
+        return refund_ticket_during_notice(&env, raffle, ticket_id);
+    }
+    if raffle.status != RaffleStatus::Cancelled && raffle.status != RaffleStatus::Failed && !cancel_scheduled {
+        return Err(Error::InvalidStatus);
+    }
 
     let _guard = Guard::new(&env)?;
-    let ticket: crate::Ticket = env.storage().persistent().get(&DataKey::Ticket(ticket_id)).ok_or(Error::TicketNotFound)?;
+    let ticket: Ticket = env.storage().persistent().get(&DataKey::Ticket(ticket_id)).ok_or(Error::TicketNotFound)?;
     ticket.owner.require_auth();
 
     if env.storage().persistent().has(&DataKey::TicketRefunded(ticket_id)) { return Err(Error::PrizeAlreadyClaimed); }
     env.storage().persistent().set(&DataKey::TicketRefunded(ticket_id), &true);
+    bump_persistent(&env, &DataKey::TicketRefunded(ticket_id));
 
+    let amount = ticket_payment(&env, &raffle, ticket_id).gross + crate::bond::refund_share(&env);
+    let refund_to = refund_recipient(&env, &ticket);
     let tc = token::Client::new(&env, &raffle.payment_token);
-    let _ = tc.try_transfer(&env.current_contract_address(), &ticket.owner, &raffle.ticket_price).map_err(|_| Error::TokenTransferFailed)?;
+    let _ = tc.try_transfer(&env.current_contract_address(), &refund_to, &amount).map_err(|_| Error::TokenTransferFailed)?;
+
+    TicketRefunded { buyer: refund_to, ticket_number: ticket.ticket_number, amount, timestamp: env.ledger().timestamp() }.publish(&env);
+    Ok(amount)
+}
+
+/// Refunds up to 50 of `owner`'s tickets in one transfer, skipping those
+/// already refunded.
+pub(crate) fn batch_refund_tickets(env: Env, owner: Address, ticket_ids: Vec<u32>) -> Result<i128, Error> {
+    owner.require_auth();
+    let _guard = Guard::new(&env)?;
+    let raffle = read_raffle(&env)?;
+
+    if raffle.status != RaffleStatus::Cancelled && raffle.status != RaffleStatus::Failed { return Err(Error::InvalidStatus); }
+    // Per-call cap to stay within compute limits.
+    if ticket_ids.len() > 50 { return Err(Error::InvalidParameters); }
+
+    let mut total_refund = 0i128;
+    let mut owner_refund = 0i128;
+    let bond_share = crate::bond::refund_share(&env);
+    let tc = token::Client::new(&env, &raffle.payment_token);
+    for ticket_id in ticket_ids.iter() {
+        let ticket: crate::Ticket = env.storage().persistent().get(&DataKey::Ticket(ticket_id)).ok_or(Error::TicketNotFound)?;
+        if ticket.owner != owner { return Err(Error::NotAuthorized); }
+        if env.storage().persistent().has(&DataKey::TicketRefunded(ticket_id)) { continue; }
+        env.storage().persistent().set(&DataKey::TicketRefunded(ticket_id), &true);
+        let amount = ticket_payment(&env, &raffle, ticket_id).gross + bond_share;
+        total_refund += amount;
+        // Tickets bought for the owner by someone else may refund the payer.
+        let refund_to = refund_recipient(&env, &ticket);
+        if refund_to == owner {
+            owner_refund += amount;
+        } else {
+            let _ = tc.try_transfer(&env.current_contract_address(), &refund_to, &amount).map_err(|_| Error::TokenTransferFailed)?;
+        }
+        TicketRefunded { buyer: refund_to, ticket_number: ticket.ticket_number, amount, timestamp: env.ledger().timestamp() }.publish(&env);
+    }
+
+    if owner_refund > 0 {
+        let _ = tc.try_transfer(&env.current_contract_address(), &owner, &owner_refund).map_err(|_| Error::TokenTransferFailed)?;
+    }
+    Ok(total_refund)
+}
+
+/// What `ticket_id`'s payer was charged.  Tickets sold before per-ticket
+/// payments were recorded paid the full price at the current fee.
+fn ticket_payment(env: &Env, raffle: &Raffle, ticket_id: u32) -> TicketPayment {
+    env.storage().persistent().get(&DataKey::TicketPaid(ticket_id)).unwrap_or_else(|| {
+        TicketPayment { gross: raffle.ticket_price, fee: raffle.ticket_price * raffle.protocol_fee_bp as i128 / 10000 }
+    })
+}
+
+/// Refunds a ticket while a parameter change waits out its notice window.
+///
+/// The raffle stays live, so the ticket leaves the draw: the last ticket
+/// takes over its ID (see `TicketRenumbered`) and whoever paid gets back
+/// the full amount, protocol fee included.  The sale is taken out of the
+/// raffle's fees and gross volume and out of the factory's volume totals.
+pub(crate) fn refund_ticket_during_notice(env: &Env, mut raffle: Raffle, ticket_id: u32) -> Result<i128, Error> {
+    let _guard = Guard::new(env)?;
+    let ticket: Ticket = env.storage().persistent().get(&DataKey::Ticket(ticket_id)).ok_or(Error::TicketNotFound)?;
+    ticket.owner.require_auth();
+
+    let payment = ticket_payment(env, &raffle, ticket_id);
+    // Read before the last ticket's payer moves into this ID.
+    let refund_to = refund_recipient(env, &ticket);
+
+    let mut owned: Vec<u32> = env.storage().persistent().get(&DataKey::OwnerTickets(ticket.owner.clone())).unwrap_or_else(|| Vec::new(env));
+    if let Some(index) = owned.first_index_of(ticket_id) {
+        owned.remove(index);
+    }
+    env.storage().persistent().set(&DataKey::OwnerTickets(ticket.owner.clone()), &owned);
+    let count: u32 = env.storage().persistent().get(&DataKey::TicketCount(ticket.owner.clone())).unwrap_or(0);
+    env.storage().persistent().set(&DataKey::TicketCount(ticket.owner.clone()), &count.saturating_sub(1));
+
+    let last = raffle.tickets_sold;
+    if ticket_id != last {
+        let mut moved: Ticket = env.storage().persistent().get(&DataKey::Ticket(last)).ok_or(Error::TicketNotFound)?;
+        moved.id = ticket_id;
+        env.storage().persistent().set(&DataKey::Ticket(ticket_id), &moved);
+        bump_persistent(env, &DataKey::Ticket(ticket_id));
+        match env.storage().persistent().get::<_, TicketPayment>(&DataKey::TicketPaid(last)) {
+            Some(paid) => {
+                env.storage().persistent().set(&DataKey::TicketPaid(ticket_id), &paid);
+                bump_persistent(env, &DataKey::TicketPaid(ticket_id));
+            }
+            None => env.storage().persistent().remove(&DataKey::TicketPaid(ticket_id)),
+        }
+        match env.storage().persistent().get::<_, Address>(&DataKey::TicketPayer(last)) {
+            Some(payer) => {
+                env.storage().persistent().set(&DataKey::TicketPayer(ticket_id), &payer);
+                bump_persistent(env, &DataKey::TicketPayer(ticket_id));
+            }
+            None => env.storage().persistent().remove(&DataKey::TicketPayer(ticket_id)),
+        }
+        match env.storage().persistent().get::<_, CommitRevealEntry>(&DataKey::CommitEntry(last)) {
+            Some(entry) => {
+                env.storage().persistent().set(&DataKey::CommitEntry(ticket_id), &entry);
+                bump_persistent(env, &DataKey::CommitEntry(ticket_id));
+            }
+            None => env.storage().persistent().remove(&DataKey::CommitEntry(ticket_id)),
+        }
+        let mut moved_owned: Vec<u32> = env.storage().persistent().get(&DataKey::OwnerTickets(moved.owner.clone())).unwrap_or_else(|| Vec::new(env));
+        if let Some(index) = moved_owned.first_index_of(last) {
+            moved_owned.set(index, ticket_id);
+        }
+        env.storage().persistent().set(&DataKey::OwnerTickets(moved.owner.clone()), &moved_owned);
+        TicketRenumbered { owner: moved.owner, ticket_number: moved.ticket_number, old_ticket_id: last, new_ticket_id: ticket_id, timestamp: env.ledger().timestamp() }.publish(env);
+    }
+    env.storage().persistent().remove(&DataKey::Ticket(last));
+    env.storage().persistent().remove(&DataKey::TicketPaid(last));
+    env.storage().persistent().remove(&DataKey::TicketPayer(last));
+    env.storage().persistent().remove(&DataKey::CommitEntry(last));
+
+    raffle.tickets_sold = last - 1;
+    write_raffle(env, &raffle);
+
+    let fees: i128 = env.storage().instance().get(&DataKey::AccumulatedFees).unwrap_or(0);
+    env.storage().instance().set(&DataKey::AccumulatedFees, &(fees - payment.fee).max(0));
+    let gross: i128 = env.storage().instance().get(&DataKey::GrossVolume).unwrap_or(0);
+    env.storage().instance().set(&DataKey::GrossVolume, &(gross - payment.gross).max(0));
+    report_volume(env, "reverse_volume", &raffle.payment_token, payment.gross);
+
+    let tc = token::Client::new(env, &raffle.payment_token);
+    let _ = tc.try_transfer(&env.current_contract_address(), &refund_to, &payment.gross).map_err(|_| Error::TokenTransferFailed)?;
 
-    TicketRefunded { buyer: ticket.owner, ticket_number: ticket.ticket_number, amount: raffle.ticket_price, timestamp: env.ledger().timestamp() }.publish(&env);
-    Ok(raffle.ticket_price)
+    TicketRefunded { buyer: refund_to, ticket_number: ticket.ticket_number, amount: payment.gross, timestamp: env.ledger().timestamp() }.publish(env);
+    Ok(payment.gross)
 }
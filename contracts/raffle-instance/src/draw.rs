@@ -1,21 +1,21 @@
-use soroban_sdk::{Address, Bytes, BytesN, Env};
+use soroban_sdk::{xdr::ToXdr, Address, Bytes, BytesN, Env};
 
+use raffle_shared::roles::Role;
 use raffle_shared::{CancelReason, FailureReason, RandomnessType};
 
 use crate::events::{
-    DrawTriggered, RaffleCancelled, RaffleFailed, RandomnessFallbackTriggered,
+    DrawCommitted, DrawExecuted, DrawTriggered, RaffleCancelled, RaffleFailed, RandomnessFallbackTriggered,
     RandomnessReceived, RandomnessRequested,
 };
 use crate::randomness::build_vrf_proof_message;
 use crate::{
-    build_internal_seed_u64, do_finalize_with_seed, read_raffle, request_randomness,
-    transition_to_drawing, write_raffle, CommitRevealEntry, DataKey, Error, RaffleStatus,
-    ORACLE_TIMEOUT_LEDGERS,
+    build_fallback_seed, do_finalize_with_seed, has_role, publish_status_changed, read_raffle, request_randomness,
+    transition_to_drawing, write_raffle, CommitRevealEntry, DataKey, DrawCommitment, Error,
+    RaffleStatus, DRAW_EXECUTION_WINDOW_LEDGERS, ORACLE_TIMEOUT_LEDGERS, RANDOMNESS_MIN_DELAY_LEDGERS,
 };
 
 pub(crate) fn finalize_raffle(env: Env) -> Result<(), Error> {
-    let drawing_lock: bool = env.storage().instance().get(&DataKey::DrawingLock).unwrap_or(false);
-    if drawing_lock {
+    if env.storage().instance().has(&DataKey::DrawCommitment) {
         return Err(Error::DrawingAlreadyInProgress);
     }
     let mut raffle = read_raffle(&env)?;
@@ -39,15 +39,21 @@ pub(crate) fn finalize_raffle(env: Env) -> Result<(), Error> {
         } else {
             FailureReason::MinTicketsNotMet
         };
+        let old_status = raffle.status.clone();
         raffle.status = RaffleStatus::Failed;
         write_raffle(&env, &raffle);
+        publish_status_changed(&env, old_status, RaffleStatus::Failed, now);
         RaffleFailed { creator: raffle.creator.clone(), reason: failure_reason, tickets_sold: raffle.tickets_sold, timestamp: now }.publish(&env);
         return Ok(());
     }
 
     let caller = raffle.creator.clone();
     let pre_status = raffle.status.clone();
-    transition_to_drawing(&env, &mut raffle, now)?;
+    // A sell-out in `buy_tickets` already moved the raffle into Drawing.
+    let transitioned = raffle.status == RaffleStatus::Active;
+    if transitioned {
+        transition_to_drawing(&env, &mut raffle, now)?;
+    }
 
     if raffle.randomness_source == raffle_shared::RandomnessSource::External {
         match request_randomness(&env) {
@@ -60,9 +66,11 @@ pub(crate) fn finalize_raffle(env: Env) -> Result<(), Error> {
                 return Ok(());
             }
             Err(err) => {
-                raffle.status = pre_status;
-                write_raffle(&env, &raffle);
-                env.storage().instance().set(&DataKey::DrawingLock, &false);
+                if transitioned {
+                    raffle.status = pre_status;
+                    write_raffle(&env, &raffle);
+                    env.storage().instance().set(&DataKey::DrawingLock, &false);
+                }
                 return Err(err);
             }
         }
@@ -70,32 +78,123 @@ pub(crate) fn finalize_raffle(env: Env) -> Result<(), Error> {
 
     DrawTriggered { caller: caller.clone(), total_tickets_sold: raffle.tickets_sold, timestamp: now }.publish(&env);
 
+    // Commit-reveal hashes are folded into the commitment rather than used as
+    // the seed directly: buyers know every commit before the draw, so on their
+    // own they would let a late buyer grind the outcome.
+    let mut combined = Bytes::new(&env);
     if raffle.randomness_source == raffle_shared::RandomnessSource::CommitReveal {
-        let mut combined = Bytes::new(&env);
-        let mut commits_found: u32 = 0;
         for ticket_id in 1..=raffle.tickets_sold {
             if let Some(entry) = env.storage().persistent().get::<_, CommitRevealEntry>(&DataKey::CommitEntry(ticket_id)) {
                 combined.extend_from_array(&entry.hash.to_array());
-                commits_found += 1;
             }
         }
-        if commits_found > 0 {
-            let hash: BytesN<32> = env.crypto().sha256(&combined).into();
-            let arr = hash.to_array();
-            let mut seed_bytes = [0u8; 8];
-            seed_bytes.copy_from_slice(&arr[..8]);
-            let seed = u64::from_be_bytes(seed_bytes);
-            return do_finalize_with_seed(&env, raffle, seed, RandomnessType::Prng);
-        }
     }
 
-    let seed = build_internal_seed_u64(&env);
+    // Internal draws are two-step: commit to the current raffle state and a
+    // target ledger now, and only derive the seed in `execute_draw` once that
+    // ledger has closed. The caller of `finalize_raffle` therefore cannot pick
+    // the ledger whose entropy decides the winners. Ticket sales stay locked
+    // by the Drawing status and DrawingLock set above.
+    let commit_ledger = env.ledger().sequence();
+    let target_ledger = commit_ledger + RANDOMNESS_MIN_DELAY_LEDGERS;
+    let commitment: BytesN<32> = env
+        .crypto()
+        .sha256(
+            &(
+                env.current_contract_address(),
+                raffle.tickets_sold,
+                combined,
+                commit_ledger,
+                now,
+                env.ledger().network_id(),
+            )
+                .to_xdr(&env),
+        )
+        .into();
+    env.storage().instance().set(&DataKey::DrawCommitment, &DrawCommitment {
+        commitment: commitment.clone(),
+        commit_ledger,
+        target_ledger,
+    });
+    DrawCommitted { commitment, commit_ledger, target_ledger, timestamp: now }.publish(&env);
+    Ok(())
+}
+
+/// Completes a delayed internal draw started by `finalize_raffle`.
+///
+/// Permissionless so a creator who dislikes the odds cannot stall the draw by
+/// withholding the second transaction. The seed hashes the stored commitment
+/// together with PRNG output of the executing ledger, which is derived by the
+/// network after the transaction set is fixed and is not knowable at commit
+/// time.
+///
+/// Winners are only selected within `DRAW_EXECUTION_WINDOW_LEDGERS` of the
+/// target ledger. A call after that re-commits the draw to a new target
+/// ledger, deriving the new commitment from the old one, so waiting for a
+/// more convenient ledger buys the caller nothing they can choose.
+pub(crate) fn execute_draw(env: Env, caller: Address) -> Result<(), Error> {
+    caller.require_auth();
+    let drawing_lock: bool = env.storage().instance().get(&DataKey::DrawingLock).unwrap_or(false);
+    if !drawing_lock {
+        return Err(Error::DrawingAlreadyComplete);
+    }
+
+    let raffle = read_raffle(&env)?;
+    if raffle.status != RaffleStatus::Drawing {
+        return Err(Error::InvalidStateTransition);
+    }
+    let pending: DrawCommitment = env
+        .storage()
+        .instance()
+        .get(&DataKey::DrawCommitment)
+        .ok_or(Error::NoRandomnessRequest)?;
+    let sequence = env.ledger().sequence();
+    if sequence < pending.target_ledger {
+        return Err(Error::RandomnessTooEarly);
+    }
+
+    if sequence > pending.target_ledger + DRAW_EXECUTION_WINDOW_LEDGERS {
+        let commitment: BytesN<32> = env
+            .crypto()
+            .sha256(&(pending.commitment, pending.target_ledger).to_xdr(&env))
+            .into();
+        let target_ledger = sequence + RANDOMNESS_MIN_DELAY_LEDGERS;
+        env.storage().instance().set(&DataKey::DrawCommitment, &DrawCommitment {
+            commitment: commitment.clone(),
+            commit_ledger: sequence,
+            target_ledger,
+        });
+        DrawCommitted { commitment, commit_ledger: sequence, target_ledger, timestamp: env.ledger().timestamp() }
+            .publish(&env);
+        return Ok(());
+    }
+
+    let ledger_entropy: BytesN<32> = env.prng().gen();
+    let seed: BytesN<32> = env
+        .crypto()
+        .sha256(&(pending.commitment.clone(), ledger_entropy, sequence, env.ledger().timestamp()).to_xdr(&env))
+        .into();
+
+    env.storage().instance().remove(&DataKey::DrawCommitment);
+    DrawExecuted {
+        caller,
+        commitment: pending.commitment,
+        target_ledger: pending.target_ledger,
+        executed_ledger: sequence,
+        timestamp: env.ledger().timestamp(),
+    }
+    .publish(&env);
+
     do_finalize_with_seed(&env, raffle, seed, RandomnessType::Prng)
 }
 
+pub(crate) fn get_draw_commitment(env: Env) -> Option<DrawCommitment> {
+    env.storage().instance().get(&DataKey::DrawCommitment)
+}
+
 pub(crate) fn provide_randomness(
     env: Env,
-    random_seed: u64,
+    random_seed: BytesN<32>,
     public_key: BytesN<32>,
     proof: BytesN<64>,
     request_id: u64,
@@ -120,23 +219,24 @@ pub(crate) fn provide_randomness(
     let stored: u64 = env.storage().instance().get(&DataKey::RandomnessRequestId).ok_or(Error::NoRandomnessRequest)?;
     if stored != request_id { return Err(Error::InvalidParameters); }
 
-    let message = build_vrf_proof_message(&env, request_id, random_seed);
+    let message = build_vrf_proof_message(&env, request_id, &random_seed);
     env.crypto().ed25519_verify(&public_key, &message, &proof);
 
-    RandomnessReceived { oracle, seed: random_seed, request_id, timestamp: env.ledger().timestamp() }.publish(&env);
+    RandomnessReceived { oracle, seed: random_seed.clone(), request_id, timestamp: env.ledger().timestamp() }.publish(&env);
     do_finalize_with_seed(&env, raffle, random_seed, RandomnessType::Vrf)?;
     Ok(env.current_contract_address())
 }
 
 pub(crate) fn trigger_randomness_fallback(env: Env, caller: Address, do_refund: bool) -> Result<(), Error> {
+    // # SECURITY: fallback is only valid while a draw is in progress.
+    // If DrawingLock is already false, the draw has completed or never started.
     let drawing_lock: bool = env.storage().instance().get(&DataKey::DrawingLock).unwrap_or(false);
-    if drawing_lock { return Err(Error::DrawingAlreadyInProgress); }
+    if !drawing_lock { return Err(Error::DrawingAlreadyComplete); }
 
     caller.require_auth();
     let mut raffle = read_raffle(&env)?;
 
-    let admin: Address = env.storage().instance().get(&DataKey::Admin).ok_or(Error::NotAuthorized)?;
-    if caller != raffle.creator && caller != admin { return Err(Error::NotAuthorized); }
+    if caller != raffle.creator && !has_role(&env, Role::Moderator, &caller) { return Err(Error::NotAuthorized); }
     if raffle.status != RaffleStatus::Drawing { return Err(Error::InvalidStateTransition); }
 
     let pending: bool = env.storage().instance().get(&DataKey::RandomnessRequested).unwrap_or(false);
@@ -148,6 +248,7 @@ pub(crate) fn trigger_randomness_fallback(env: Env, caller: Address, do_refund:
     if do_refund {
         raffle.status = RaffleStatus::Cancelled;
         write_raffle(&env, &raffle);
+        publish_status_changed(&env, RaffleStatus::Drawing, RaffleStatus::Cancelled, env.ledger().timestamp());
         env.storage().instance().remove(&DataKey::RandomnessRequested);
         env.storage().instance().remove(&DataKey::RandomnessRequestId);
         env.storage().instance().remove(&DataKey::RandomnessRequestLedger);
@@ -156,9 +257,9 @@ pub(crate) fn trigger_randomness_fallback(env: Env, caller: Address, do_refund:
         return Ok(());
     }
 
-    let seed = build_internal_seed_u64(&env);
+    let seed = build_fallback_seed(&env);
     RandomnessFallbackTriggered {
-        triggered_by: caller, seed_used: seed, request_ledger: req_ledger,
+        triggered_by: caller, seed_used: seed.clone(), request_ledger: req_ledger,
         fallback_ledger: env.ledger().sequence(), timestamp: env.ledger().timestamp(),
     }.publish(&env);
 
@@ -1,7 +1,8 @@
-use raffle_shared::{CancelReason, RandomnessSource, RandomnessType};
-use raffle_shared::{CancelReason, FailureReason, RandomnessSource, RandomnessType};
+use raffle_shared::{BondSlashTarget, CancelReason, FailureReason, RandomnessSource, RandomnessType, SelectionAlgorithm};
 use soroban_sdk::{contractevent, Address, BytesN, String, Vec};
 
+use crate::ParamChange;
+
 #[derive(Clone)]
 #[contractevent]
 pub struct RaffleCreated {
@@ -50,6 +51,17 @@ pub struct TicketPurchased {
     pub timestamp: u64,
 }
 
+/// Emitted with `TicketPurchased` when `buy_tickets_for` buys tickets for
+/// another address.
+#[derive(Clone)]
+#[contractevent]
+pub struct TicketsGifted {
+    pub payer: Address,
+    pub recipient: Address,
+    pub ticket_ids: Vec<u32>,
+    pub timestamp: u64,
+}
+
 #[allow(dead_code)]
 #[derive(Clone)]
 #[contractevent]
@@ -68,6 +80,28 @@ pub struct DrawTriggered {
     pub timestamp: u64,
 }
 
+/// Emitted when `finalize_raffle` commits an internal draw to a future ledger.
+/// Winners are selected by `execute_draw` once `target_ledger` is reached.
+#[derive(Clone)]
+#[contractevent]
+pub struct DrawCommitted {
+    pub commitment: BytesN<32>,
+    pub commit_ledger: u32,
+    pub target_ledger: u32,
+    pub timestamp: u64,
+}
+
+/// Emitted when a committed internal draw is executed.
+#[derive(Clone)]
+#[contractevent]
+pub struct DrawExecuted {
+    pub caller: Address,
+    pub commitment: BytesN<32>,
+    pub target_ledger: u32,
+    pub executed_ledger: u32,
+    pub timestamp: u64,
+}
+
 #[derive(Clone)]
 #[contractevent]
 pub struct RandomnessRequested {
@@ -80,7 +114,7 @@ pub struct RandomnessRequested {
 #[contractevent]
 pub struct RandomnessReceived {
     pub oracle: Address,
-    pub seed: u64,
+    pub seed: BytesN<32>,
     pub request_id: u64,
     pub timestamp: u64,
 }
@@ -94,6 +128,7 @@ pub struct RaffleFinalized {
     pub total_tickets_sold: u32,
     pub randomness_source: RandomnessSource,
     pub randomness_type: RandomnessType,
+    pub selection_algorithm: SelectionAlgorithm,
     pub finalized_at: u64,
 }
 
@@ -149,6 +184,19 @@ pub struct TicketRefunded {
     pub timestamp: u64,
 }
 
+/// Emitted when a notice-window refund moves the last ticket into the
+/// refunded ticket's ID.
+#[derive(Clone)]
+#[contractevent]
+pub struct TicketRenumbered {
+    pub owner: Address,
+    /// Unchanged by the move.
+    pub ticket_number: u32,
+    pub old_ticket_id: u32,
+    pub new_ticket_id: u32,
+    pub timestamp: u64,
+}
+
 #[derive(Clone)]
 #[contractevent]
 pub struct PrizeClaimed {
@@ -174,7 +222,7 @@ pub struct FeesWithdrawn {
 #[contractevent]
 pub struct RandomnessFallbackTriggered {
     pub triggered_by: Address,
-    pub seed_used: u64,
+    pub seed_used: BytesN<32>,
     pub request_ledger: u32,
     pub fallback_ledger: u32,
     pub timestamp: u64,
@@ -216,6 +264,13 @@ pub struct TicketSalesResumed {
     pub timestamp: u64,
 }
 
+#[derive(Clone)]
+#[contractevent]
+pub struct RolesUpdated {
+    pub updated_by: Address,
+    pub timestamp: u64,
+}
+
 #[derive(Clone)]
 #[contractevent]
 pub struct TokensRescued {
@@ -244,21 +299,32 @@ pub struct ProtocolFeeUpdated {
     pub timestamp: u64,
 }
 
+/// Emitted when a parameter change to a raffle with sold tickets is
+/// scheduled instead of applied.
 #[derive(Clone)]
 #[contractevent]
-pub struct SwapDeadlineUpdated {
-    pub old_deadline_seconds: u64,
-    pub new_deadline_seconds: u64,
-    pub updated_by: Address,
+pub struct ParamChangeScheduled {
+    pub change: ParamChange,
+    pub scheduled_by: Address,
+    pub effective_at: u64,
     pub timestamp: u64,
 }
 
+/// Emitted when a scheduled parameter change is dropped before it applies.
 #[derive(Clone)]
 #[contractevent]
-pub struct EndTimeExtended {
-    pub old_end_time: u64,
-    pub new_end_time: u64,
-    pub extended_by: Address,
+pub struct ParamChangeCancelled {
+    pub change: ParamChange,
+    pub cancelled_by: Address,
+    pub timestamp: u64,
+}
+
+#[derive(Clone)]
+#[contractevent]
+pub struct SwapDeadlineUpdated {
+    pub old_deadline_seconds: u64,
+    pub new_deadline_seconds: u64,
+    pub updated_by: Address,
     pub timestamp: u64,
 }
 
@@ -283,6 +349,25 @@ pub struct AdminChanged {
     pub timestamp: u64,
 }
 
+/// Emitted when the factory swaps this instance's code.
+#[derive(Clone)]
+#[contractevent]
+pub struct InstanceUpgraded {
+    pub new_wasm_hash: BytesN<32>,
+    /// Schema version stored at the time of the upgrade.
+    pub schema_version: u32,
+    pub timestamp: u64,
+}
+
+/// Emitted when `migrate` advances the stored schema version.
+#[derive(Clone)]
+#[contractevent]
+pub struct InstanceMigrated {
+    pub from_version: u32,
+    pub to_version: u32,
+    pub timestamp: u64,
+}
+
 /// Emitted once per ticket after an NFT receipt is successfully minted
 /// by the configured `nft_contract`.
 #[derive(Clone)]
@@ -298,3 +383,36 @@ pub struct TicketNftMinted {
     pub nft_contract: Address,
     pub timestamp: u64,
 }
+
+/// Emitted when the creator posts the bond with the prize.
+#[derive(Clone)]
+#[contractevent]
+pub struct BondPosted {
+    pub creator: Address,
+    pub amount: i128,
+    pub token: Address,
+    pub timestamp: u64,
+}
+
+/// Emitted when the creator cancels after sales and forfeits the bond.
+#[derive(Clone)]
+#[contractevent]
+pub struct BondSlashed {
+    pub creator: Address,
+    pub amount: i128,
+    pub slash_target: BondSlashTarget,
+    /// Added to each ticket refund; zero for treasury slashes.
+    pub per_ticket: i128,
+    /// Sent to the treasury, including integer-division dust.
+    pub to_treasury: i128,
+    pub timestamp: u64,
+}
+
+/// Emitted when a posted bond is paid back to the creator.
+#[derive(Clone)]
+#[contractevent]
+pub struct BondReturned {
+    pub creator: Address,
+    pub amount: i128,
+    pub timestamp: u64,
+}
@@ -0,0 +1,99 @@
+//! Sale gate.  Limits ticket purchases to a Merkle allowlist or to holders
+//! of a minimum token balance, for the whole sale or only during a presale
+//! that gives way to a public sale.
+
+use soroban_sdk::{token, xdr::ToXdr, Address, Bytes, BytesN, Env};
+
+use raffle_shared::{AllowlistProof, Eligibility, GateRule, SaleGate};
+
+use crate::{validate_token_address, DataKey, Error};
+
+/// Deepest Merkle proof accepted, enough for any allowlist that fits on chain.
+const MAX_PROOF_DEPTH: u32 = 32;
+
+pub(crate) fn read_gate(env: &Env) -> SaleGate {
+    env.storage().instance().get(&DataKey::SaleGate).unwrap_or(SaleGate::Open)
+}
+
+/// Checks `config.sale_gate` at init.  A presale must end while the sale
+/// is still open.
+pub(crate) fn validate(env: &Env, gate: &SaleGate, end_time: u64, no_deadline: bool) -> Result<(), Error> {
+    let rule = match gate {
+        SaleGate::Open => return Ok(()),
+        SaleGate::Gated(rule) => rule,
+        SaleGate::Presale(rule, presale_end) => {
+            if *presale_end <= env.ledger().timestamp() || (!no_deadline && *presale_end >= end_time) { return Err(Error::InvalidParameters); }
+            rule
+        }
+    };
+    if let GateRule::MinBalance(token, min_balance) = rule {
+        if *min_balance <= 0 { return Err(Error::InvalidParameters); }
+        validate_token_address(env, token)?;
+    }
+    Ok(())
+}
+
+/// The rule buyers must meet right now, `None` once the sale is open.
+fn active_rule(env: &Env) -> Option<GateRule> {
+    match read_gate(env) {
+        SaleGate::Open => None,
+        SaleGate::Gated(rule) => Some(rule),
+        SaleGate::Presale(rule, presale_end) => (env.ledger().timestamp() < presale_end).then_some(rule),
+    }
+}
+
+fn leaf(env: &Env, buyer: &Address, allocation: u32) -> BytesN<32> {
+    let mut data = buyer.clone().to_xdr(env);
+    data.extend_from_array(&allocation.to_be_bytes());
+    env.crypto().sha256(&data).into()
+}
+
+fn verify(env: &Env, root: &BytesN<32>, buyer: &Address, entry: &AllowlistProof) -> bool {
+    if entry.proof.len() > MAX_PROOF_DEPTH { return false; }
+    let mut node = leaf(env, buyer, entry.allocation).to_array();
+    for sibling in entry.proof.iter() {
+        let sibling = sibling.to_array();
+        let (first, second) = if node <= sibling { (node, sibling) } else { (sibling, node) };
+        let mut data = Bytes::from_array(env, &first);
+        data.extend_from_array(&second);
+        node = env.crypto().sha256(&data).to_array();
+    }
+    node == root.to_array()
+}
+
+/// Tickets `buyer` may still buy under the gate, `None` when it does not
+/// cap them.  Fails with `NotEligible` when the buyer does not qualify.
+fn allowance(env: &Env, rule: &GateRule, buyer: &Address, owned: u32, entry: Option<&AllowlistProof>) -> Result<Option<u32>, Error> {
+    match rule {
+        GateRule::MerkleAllowlist(root) => {
+            let entry = entry.filter(|entry| verify(env, root, buyer, entry)).ok_or(Error::NotEligible)?;
+            Ok(Some(entry.allocation.saturating_sub(owned)))
+        }
+        GateRule::MinBalance(token, min_balance) => {
+            // Read live, not snapshotted: see `GateRule::MinBalance`.
+            if token::Client::new(env, token).balance(buyer) < *min_balance { return Err(Error::NotEligible); }
+            Ok(None)
+        }
+    }
+}
+
+/// Rejects a purchase of `quantity` more tickets by a buyer already holding
+/// `owned` while the sale is gated.
+pub(crate) fn require_eligible(env: &Env, buyer: &Address, owned: u32, quantity: u32, entry: Option<&AllowlistProof>) -> Result<(), Error> {
+    let Some(rule) = active_rule(env) else { return Ok(()) };
+    if let Some(remaining) = allowance(env, &rule, buyer, owned, entry)? {
+        if quantity > remaining { return Err(Error::NotEligible); }
+    }
+    Ok(())
+}
+
+pub(crate) fn eligibility(env: &Env, buyer: &Address, entry: Option<AllowlistProof>) -> Eligibility {
+    let Some(rule) = active_rule(env) else {
+        return Eligibility { eligible: true, gated: false, remaining_allocation: None };
+    };
+    let owned: u32 = env.storage().persistent().get(&DataKey::TicketCount(buyer.clone())).unwrap_or(0);
+    match allowance(env, &rule, buyer, owned, entry.as_ref()) {
+        Ok(remaining) => Eligibility { eligible: remaining != Some(0), gated: true, remaining_allocation: remaining },
+        Err(_) => Eligibility { eligible: false, gated: true, remaining_allocation: None },
+    }
+}
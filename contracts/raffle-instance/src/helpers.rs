@@ -1,27 +1,69 @@
-use soroban_sdk::{token, Address, BytesN, Env, Vec};
+use soroban_sdk::{
+    auth::{ContractContext, InvokerContractAuthEntry, SubContractInvocation},
+    token, Address, BytesN, Env, IntoVal, Symbol, Val, Vec,
+};
+
+use raffle_shared::constants::{
+    INSTANCE_TTL_EXTEND_TO, INSTANCE_TTL_THRESHOLD, PERSISTENT_TTL_EXTEND_TO, PERSISTENT_TTL_THRESHOLD,
+};
+use raffle_shared::roles::{self, Role};
+use raffle_shared::CreatorOutcome;
 
 use crate::events::{RaffleFinalized, RaffleStatusChanged, WinnerDrawn};
-use crate::randomness::{OracleSeedWinnerSelection, WinnerSelectionStrategy};
+use crate::randomness::select_winners_with;
 use crate::{
-    DataKey, Error, FairnessMetadata, Raffle, RaffleStatus, RandomnessType, Ticket,
+    DataKey, Error, FairnessMetadata, FairnessRecord, LegacyFairnessMetadata, Raffle, RaffleStatus,
 };
+use raffle_shared::{RandomnessType, Ticket};
+
+pub(crate) fn bump_instance(env: &Env) {
+    env.storage().instance().extend_ttl(INSTANCE_TTL_THRESHOLD, INSTANCE_TTL_EXTEND_TO);
+}
+
+/// Extends a persistent entry's TTL.  The entry must exist.
+pub(crate) fn bump_persistent(env: &Env, key: &DataKey) {
+    env.storage().persistent().extend_ttl(key, PERSISTENT_TTL_THRESHOLD, PERSISTENT_TTL_EXTEND_TO);
+}
 
 pub(crate) fn read_raffle(env: &Env) -> Result<Raffle, Error> {
-    env.storage().instance().get(&DataKey::Raffle).ok_or(Error::NotInitialized)
+    let raffle = env.storage().instance().get(&DataKey::Raffle).ok_or(Error::NotInitialized)?;
+    bump_instance(env);
+    Ok(raffle)
 }
 
 pub(crate) fn write_raffle(env: &Env, raffle: &Raffle) {
     env.storage().instance().set(&DataKey::Raffle, raffle);
+    bump_instance(env);
+}
+
+/// Whether `account` holds `role` here and still holds it at the factory.
+/// Grants reach an instance through the factory's `sync_roles`; revocations
+/// take effect at once, without waiting for the next sync.
+pub(crate) fn has_role(env: &Env, role: Role, account: &Address) -> bool {
+    if !roles::has_role(env, role, account) {
+        return false;
+    }
+    match env.storage().instance().get::<_, Address>(&DataKey::Factory) {
+        Some(factory) => env.invoke_contract::<bool>(
+            &factory,
+            &Symbol::new(env, "has_role"),
+            (role, account.clone()).into_val(env),
+        ),
+        None => true,
+    }
 }
 
-pub(crate) fn require_admin(env: &Env) -> Result<Address, Error> {
-    let admin: Address = env.storage().persistent().get(&DataKey::Admin).ok_or(Error::NotAuthorized)?;
-    admin.require_auth();
-    Ok(admin)
+pub(crate) fn require_role(env: &Env, role: Role, caller: &Address) -> Result<(), Error> {
+    caller.require_auth();
+    if !has_role(env, role, caller) { return Err(Error::NotAuthorized); }
+    Ok(())
 }
 
 pub(crate) fn get_ticket_owner(env: &Env, ticket_id: u32) -> Option<Address> {
-    env.storage().persistent().get::<_, Ticket>(&DataKey::Ticket(ticket_id)).map(|t| t.owner)
+    let key = DataKey::Ticket(ticket_id);
+    let ticket = env.storage().persistent().get::<_, Ticket>(&key)?;
+    bump_persistent(env, &key);
+    Some(ticket.owner)
 }
 
 pub(crate) fn acquire_guard(env: &Env) -> Result<(), Error> {
@@ -95,17 +137,76 @@ pub(crate) fn transition_to_drawing(env: &Env, raffle: &mut Raffle, timestamp: u
 
     if raffle.status != RaffleStatus::Active {
         if raffle.status == RaffleStatus::Drawing { return Err(Error::DrawingAlreadyInProgress); }
-        return Err(Error::InvalidStatusForDrawingTransition);
+        return Err(Error::InvalidStateTransition);
     }
 
     let old_status = raffle.status.clone();
     raffle.status = RaffleStatus::Drawing;
     write_raffle(env, raffle);
-    RaffleStatusChanged { old_status, new_status: RaffleStatus::Drawing, timestamp }.publish(env);
+    publish_status_changed(env, old_status, RaffleStatus::Drawing, timestamp);
     env.storage().instance().set(&DataKey::DrawingLock, &true);
     Ok(())
 }
 
+/// Publishes `RaffleStatusChanged` and reports the new status to the factory,
+/// which keeps the per-status index behind `get_raffles_by_status`.
+pub(crate) fn publish_status_changed(env: &Env, old_status: RaffleStatus, new_status: RaffleStatus, timestamp: u64) {
+    RaffleStatusChanged { old_status, new_status: new_status.clone(), timestamp }.publish(env);
+    notify_factory(env, "notify_status_change", (env.current_contract_address(), new_status).into_val(env));
+}
+
+/// Reports a cancellation or a claimed prize to the factory's creator
+/// profiles.  Completions and failures travel with the status change.
+pub(crate) fn report_creator_outcome(env: &Env, outcome: CreatorOutcome) {
+    notify_factory(env, "report_creator_outcome", (env.current_contract_address(), outcome).into_val(env));
+}
+
+/// Reports to the factory's discovery indexes and creator profiles.  A
+/// failing callback reverts the call, so the factory's indexes never drift
+/// from the instance.  Calls the factory drives itself skip the callback
+/// (see `admin::apply_cancel`), since it would re-enter the factory.
+pub(crate) fn notify_factory(env: &Env, func: &str, args: Vec<Val>) {
+    if let Some(factory) = env.storage().instance().get::<_, Address>(&DataKey::Factory) {
+        env.invoke_contract::<()>(&factory, &Symbol::new(env, func), args);
+    }
+}
+
+/// Reports `amount` of ticket sales in `token` to the factory's `func`
+/// (`record_volume` or `reverse_volume`), which only raffles may call.
+pub(crate) fn report_volume(env: &Env, func: &str, token: &Address, amount: i128) {
+    if let Some(factory) = env.storage().instance().get::<_, Address>(&DataKey::Factory) {
+        let args: Vec<Val> = (env.current_contract_address(), token.clone(), amount).into_val(env);
+        env.authorize_as_current_contract(Vec::from_array(env, [
+            InvokerContractAuthEntry::Contract(SubContractInvocation {
+                context: ContractContext {
+                    contract: factory.clone(),
+                    fn_name: Symbol::new(env, func),
+                    args: args.clone(),
+                },
+                sub_invocations: Vec::new(env),
+            }),
+        ]));
+        env.invoke_contract::<()>(&factory, &Symbol::new(env, func), args);
+    }
+}
+
+/// Refuses tokens the factory has not confirmed as enabled, including when
+/// the allowlist cannot be read.  Instances created outside a factory have
+/// no allowlist to consult.
+pub(crate) fn require_token_enabled(env: &Env, payment_token: &Address) -> Result<(), Error> {
+    if let Some(factory) = env.storage().instance().get::<_, Address>(&DataKey::Factory) {
+        let enabled = env.try_invoke_contract::<bool, soroban_sdk::Error>(
+            &factory,
+            &Symbol::new(env, "is_token_enabled"),
+            (payment_token.clone(),).into_val(env),
+        );
+        if !matches!(enabled, Ok(Ok(true))) {
+            return Err(Error::TokenDisabled);
+        }
+    }
+    Ok(())
+}
+
 pub(crate) fn require_not_paused(env: &Env) -> Result<(), Error> {
     if env.storage().instance().get(&DataKey::Paused).unwrap_or(false) {
         return Err(Error::ContractPaused);
@@ -119,18 +220,26 @@ pub(crate) fn validate_token_address(env: &Env, token_address: &Address) -> Resu
     Ok(())
 }
 
-pub(crate) fn build_internal_seed_u64(env: &Env) -> u64 {
+pub(crate) fn build_fallback_seed(env: &Env) -> BytesN<32> {
     use soroban_sdk::xdr::ToXdr;
     let xdr = (
         env.ledger().timestamp(),
         env.ledger().sequence(),
         env.current_contract_address(),
     ).to_xdr(env);
-    let hash: BytesN<32> = env.crypto().sha256(&xdr).into();
-    let arr = hash.to_array();
-    let mut bytes = [0u8; 8];
-    bytes.copy_from_slice(&arr[..8]);
-    u64::from_be_bytes(bytes)
+    env.crypto().sha256(&xdr).into()
+}
+
+/// Reads the fairness record, decoding raffles finalized under the legacy
+/// 64-bit layout as [`FairnessRecord::V1`].
+pub(crate) fn read_fairness_record(env: &Env) -> Option<FairnessRecord> {
+    if let Some(record) = env.storage().persistent().get(&DataKey::Fairness) {
+        return Some(record);
+    }
+    env.storage()
+        .persistent()
+        .get::<_, LegacyFairnessMetadata>(&DataKey::RandomnessSeed)
+        .map(FairnessRecord::V1)
 }
 
 pub(crate) fn calculate_tier_prize(raffle: &Raffle, tier_index: u32) -> Result<i128, Error> {
@@ -138,26 +247,27 @@ pub(crate) fn calculate_tier_prize(raffle: &Raffle, tier_index: u32) -> Result<i
     if tier_index == last_tier_index {
         let mut allocated = 0i128;
         for i in 0..last_tier_index {
-            let bp = raffle.prizes.get(i).unwrap();
+            let bp = raffle.prizes.get(i).ok_or(Error::InvalidIndex)?;
             let amt = raffle.prize_amount.checked_mul(bp as i128).ok_or(Error::ArithmeticOverflow)? / 10000;
             allocated = allocated.checked_add(amt).ok_or(Error::ArithmeticOverflow)?;
         }
         return raffle.prize_amount.checked_sub(allocated).ok_or(Error::ArithmeticOverflow);
     }
-    let bp = raffle.prizes.get(tier_index).unwrap();
+    let bp = raffle.prizes.get(tier_index).ok_or(Error::InvalidIndex)?;
     raffle.prize_amount.checked_mul(bp as i128).ok_or(Error::ArithmeticOverflow).map(|a| a / 10000)
 }
 
 pub(crate) fn do_finalize_with_seed(
-    env: &Env, mut raffle: Raffle, seed: u64, randomness_type: RandomnessType,
+    env: &Env, mut raffle: Raffle, seed: BytesN<32>, randomness_type: RandomnessType,
 ) -> Result<(), Error> {
     let total_tickets = raffle.tickets_sold;
     if total_tickets == 0 { return Err(Error::NoTicketsSold); }
     if raffle.prizes.len() > total_tickets { return Err(Error::MorePrizesThanTickets); }
     if raffle.tickets_sold == 0 { return Err(Error::NoActiveTickets); }
 
-    let selector = OracleSeedWinnerSelection::new(seed);
-    let winning_ticket_ids = selector.select_winner_indices(env, total_tickets, raffle.prizes.len());
+    let winning_ticket_ids = select_winners_with(
+        env, &raffle.selection_algorithm, &seed, total_tickets, raffle.prizes.len(),
+    );
     let mut winners = Vec::new(env);
 
     for i in 0..winning_ticket_ids.len() {
@@ -170,19 +280,23 @@ pub(crate) fn do_finalize_with_seed(
     let mut claimed_winners = Vec::new(env);
     for _ in 0..raffle.prizes.len() { claimed_winners.push_back(false); }
 
-    env.storage().persistent().set(&DataKey::RandomnessSeed, &FairnessMetadata {
+    env.storage().persistent().set(&DataKey::Fairness, &FairnessRecord::V2(FairnessMetadata {
         seed,
         randomness_source: raffle.randomness_source.clone(),
+        selection_algorithm: raffle.selection_algorithm,
         winning_ticket_indices: winning_ticket_ids.clone(),
         draw_timestamp: env.ledger().timestamp(),
         draw_sequence: env.ledger().sequence(),
-    });
+    }));
+    bump_persistent(env, &DataKey::Fairness);
 
+    let old_status = raffle.status.clone();
     raffle.status = RaffleStatus::Finalized;
     raffle.winners = winners.clone();
     raffle.claimed_winners = claimed_winners;
     raffle.finalized_at = Some(env.ledger().timestamp());
     write_raffle(env, &raffle);
+    publish_status_changed(env, old_status, RaffleStatus::Finalized, env.ledger().timestamp());
 
     env.storage().instance().remove(&DataKey::RandomnessRequested);
     env.storage().instance().remove(&DataKey::RandomnessRequestId);
@@ -195,6 +309,7 @@ pub(crate) fn do_finalize_with_seed(
         total_tickets_sold: raffle.tickets_sold,
         randomness_source: raffle.randomness_source.clone(),
         randomness_type,
+        selection_algorithm: raffle.selection_algorithm,
         finalized_at: env.ledger().timestamp(),
     }.publish(env);
 
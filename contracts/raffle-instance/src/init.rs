@@ -1,12 +1,12 @@
 use soroban_sdk::{token, Address, BytesN, Env};
 
-use raffle_shared::{RaffleConfig, RandomnessSource};
+use raffle_shared::{RaffleConfig, RandomnessSource, RefundTarget, SaleGate, SelectionAlgorithm};
 
-use crate::events::{PrizeDeposited, RaffleCreated, RaffleStatusChanged};
+use crate::events::{PrizeDeposited, RaffleCreated};
 use crate::{
-    read_raffle, require_not_paused, validate_token_address, write_raffle, DataKey, Error, Raffle,
+    publish_status_changed, read_raffle, require_not_paused, validate_token_address, write_raffle, DataKey, Error, Raffle,
     MAX_CLAIM_LOCKUP_SECONDS, MAX_DESCRIPTION_LENGTH, MAX_PRIZES, MAX_PRIZE_AMOUNT,
-    MAX_SWAP_DEADLINE_SECONDS, MAX_TICKETS_LIMIT, MIN_TICKET_PRICE, RaffleStatus,
+    MAX_SWAP_DEADLINE_SECONDS, MAX_TICKETS_LIMIT, RaffleStatus, SCHEMA_VERSION,
 };
 
 pub(crate) fn init(
@@ -43,7 +43,8 @@ pub(crate) fn init(
     if config.max_tickets_per_tx == 0 || config.max_tickets_per_tx > config.max_tickets {
         return Err(Error::InvalidParameters);
     }
-    if config.ticket_price < MIN_TICKET_PRICE {
+    // Per-token price floors are enforced by the factory's registry.
+    if config.ticket_price <= 0 {
         return Err(Error::InvalidParameters);
     }
     if config.prize_amount < config.ticket_price {
@@ -81,8 +82,17 @@ pub(crate) fn init(
     if config.metadata_hash == BytesN::from_array(&env, &[0u8; 32]) {
         return Err(Error::InvalidParameters);
     }
+    if config.selection_algorithm == SelectionAlgorithm::LcgRejectionV1 {
+        return Err(Error::InvalidParameters);
+    }
 
     validate_token_address(&env, &config.payment_token)?;
+    if let Some(ref pt) = config.prize_token {
+        if *pt != config.payment_token {
+            validate_token_address(&env, pt)?;
+        }
+    }
+    let prize_token = config.prize_token.clone().unwrap_or_else(|| config.payment_token.clone());
     let config = config.resolve_defaults();
 
     if config.claim_lockup_seconds > MAX_CLAIM_LOCKUP_SECONDS {
@@ -91,6 +101,22 @@ pub(crate) fn init(
     if config.swap_deadline_seconds > MAX_SWAP_DEADLINE_SECONDS {
         return Err(Error::InvalidParameters);
     }
+    if config.early_bird_ticket_percentage > 100 {
+        return Err(Error::InvalidParameters);
+    }
+    if config.early_bird_ticket_percentage > 0 && config.early_bird_discount_bp > 10000 {
+        return Err(Error::InvalidParameters);
+    }
+    if config.sale_gate != SaleGate::Open {
+        crate::gate::validate(&env, &config.sale_gate, config.end_time, config.no_deadline)?;
+        env.storage().instance().set(&DataKey::SaleGate, &config.sale_gate);
+    }
+    if let Some(cap) = crate::tickets::address_cap(&config)? {
+        env.storage().instance().set(&DataKey::AddressTicketCap, &cap);
+    }
+    if config.refund_target == RefundTarget::Payer {
+        env.storage().instance().set(&DataKey::RefundTarget, &config.refund_target);
+    }
 
     let raffle = Raffle {
         creator: creator.clone(),
@@ -103,6 +129,7 @@ pub(crate) fn init(
         allow_multiple: config.allow_multiple,
         ticket_price: config.ticket_price,
         payment_token: config.payment_token.clone(),
+        prize_token,
         prize_amount: config.prize_amount,
         prizes: config.prizes.clone(),
         tickets_sold: 0,
@@ -120,10 +147,16 @@ pub(crate) fn init(
         claim_lockup_seconds: config.claim_lockup_seconds,
         swap_deadline_seconds: config.swap_deadline_seconds,
         ticket_sales_paused: false,
+        early_bird_ticket_percentage: config.early_bird_ticket_percentage,
+        early_bird_discount_bp: config.early_bird_discount_bp,
+        nft_contract: config.nft_contract.clone(),
+        selection_algorithm: config.selection_algorithm,
     };
     write_raffle(&env, &raffle);
     env.storage().instance().set(&DataKey::Factory, &factory);
     env.storage().instance().set(&DataKey::Admin, &admin);
+    env.storage().instance().set(&DataKey::CreatedAt, &env.ledger().timestamp());
+    env.storage().instance().set(&DataKey::SchemaVersion, &SCHEMA_VERSION);
 
     RaffleCreated {
         raffle_id: env.current_contract_address(),
@@ -153,18 +186,20 @@ pub(crate) fn deposit_prize(env: Env) -> Result<(), Error> {
 
     let old_status = raffle.status.clone();
 
-    let token_client = token::Client::new(&env, &raffle.payment_token);
+    let token_client = token::Client::new(&env, &raffle.prize_token);
     let _ = token_client
         .try_transfer(&raffle.creator, env.current_contract_address(), &raffle.prize_amount)
         .map_err(|_| Error::TokenTransferFailed)?;
 
+    crate::bond::post(&env, &raffle)?;
+
     raffle.prize_deposited = true;
     raffle.status = RaffleStatus::Active;
     write_raffle(&env, &raffle);
 
     let ts = env.ledger().timestamp();
-    PrizeDeposited { creator: raffle.creator.clone(), amount: raffle.prize_amount, token: raffle.payment_token.clone(), timestamp: ts }.publish(&env);
-    RaffleStatusChanged { old_status, new_status: RaffleStatus::Active, timestamp: ts }.publish(&env);
+    PrizeDeposited { creator: raffle.creator.clone(), amount: raffle.prize_amount, token: raffle.prize_token.clone(), timestamp: ts }.publish(&env);
+    publish_status_changed(&env, old_status, RaffleStatus::Active, ts);
 
     Ok(())
 }
@@ -1,439 +1,374 @@
-use soroban_sdk::{xdr::ToXdr, Address, Bytes, BytesN, Env, Vec};
+use raffle_shared::SelectionAlgorithm;
+use soroban_sdk::{xdr::ToXdr, Bytes, BytesN, Env, Vec};
+
+/// Common winner-selection interface used by both PRNG and oracle paths.
+pub trait WinnerSelectionStrategy {
+    fn select_winner_indices(&self, env: &Env, total_tickets: u32, winner_count: u32) -> Vec<u32>;
+}
+
+/// Builds the Ed25519 message that binds a VRF proof to a specific raffle request.
+///
+/// The oracle must sign this exact byte sequence when calling `provide_randomness`.
+pub fn build_vrf_proof_message(env: &Env, request_id: u64, random_seed: &BytesN<32>) -> Bytes {
+    (env.current_contract_address(), request_id, random_seed.clone()).to_xdr(env)
+}
 
 // ============================================================================
-// build_internal_seed
+// Versioned selection algorithms
 // ============================================================================
 //
-// ⚠️  LOW-STAKES RAFFLES ONLY
-//
-// This seed is deterministic and visible on-chain.  Any participant who knows
-// the ledger state at the time `finalize_raffle` is called can reproduce the
-// exact output.  Miners / validators can also influence the ledger timestamp and
-// sequence to bias the result.
+// Every `SelectionAlgorithm` id maps to exactly one function below.  Past
+// draws are replayed by re-running the algorithm recorded in their
+// `FairnessMetadata`, so an id's behaviour must never change once shipped —
+// add a new variant instead.  The known-answer vectors in the tests module pin
+// each algorithm's output.
 //
-// For high-stakes or high-value raffles, use `RandomnessSource::External` so
-// that a VRF oracle provides a verifiably-unbiased seed that cannot be
-// predicted or manipulated before `provide_randomness` is called.
-//
-// Entropy sources mixed into the seed:
-//   1. `ledger_timestamp`  – wall-clock time in seconds
-//   2. `ledger_sequence`   – monotonically-increasing ledger counter
-//   3. `network_id`        – SHA-256 of the network passphrase (32 bytes),
-//                            ensuring seeds are network-partitioned (mainnet ≠
-//                            testnet ≠ futurenet)
-//   4. `raffle_id`         – the raffle contract address in XDR encoding,
-//                            making every raffle's draw independent even when
-//                            finalized in the same ledger
-//   5. `tickets_sold`      – ticket count at draw time, so otherwise-identical
-//                            draws with different participation produce
-//                            different seeds
-//
-// All five inputs are packed together and passed through `env.crypto().sha256`
-// to produce a uniformly-distributed 32-byte value that is used as the PRNG
-// seed via `env.prng().seed()`.
+// All algorithms take the full 256-bit draw seed.  Seeds recorded by
+// 64-bit fairness records are replayed through [`widen_legacy_seed`].
 
-/// Builds a 32-byte internal PRNG seed by hashing ledger and raffle entropy sources.
-///
-/// # Arguments
-///
-/// * `env`       – the contract execution environment
-/// * `raffle_id`    - the current contract's address (distinguishes concurrent raffles)
-/// * `tickets_sold` - number of tickets sold when the draw is finalized
-///
-/// # Returns
-///
-/// A `BytesN<32>` suitable for passing directly to `env.prng().seed()`.
-///
-/// # Security note
+const LCG_MULTIPLIER: u64 = 6364136223846793005;
+const LCG_INCREMENT: u64 = 1442695040888963407;
+
+/// Ordered set of selected winner indices.
 ///
-/// **For low-stakes raffles only.**  See the module-level comment for a full
-/// explanation of the limitations and the recommended alternative for
-/// high-value draws.
-pub fn build_internal_seed(env: &Env, raffle_id: &Address) -> BytesN<32> {
-    let timestamp = env.ledger().timestamp();
-    let sequence = env.ledger().sequence();
-    let network_id: BytesN<32> = env.ledger().network_id();
-
-    // Pack all sources into a single byte buffer, then SHA-256 hash it.
-    // Using XDR serialisation guarantees an unambiguous, length-delimited
-    // encoding so there are no collisions between differently-typed fields.
-    let raw: Bytes = (timestamp, sequence, network_id, raffle_id.clone()).to_xdr(env);
-    hash_bytes32(env, &raw)
+/// Implemented for `soroban_sdk::Vec` (on-chain) and `std::vec::Vec`
+/// (off-chain verification) so both run the same selection code.
+pub trait WinnerIndexSink {
+    fn contains_index(&self, idx: u32) -> bool;
+    fn push_index(&mut self, idx: u32);
 }
 
-/// Hashes the input with SHA-256 and validates the result.
-///
-/// On congested ledgers, the crypto operation may fail due to resource limits.
-/// In that case we expect the returned hash to be invalid rather than silently
-/// falling back to a zeroed seed, which would make winner selection
-/// deterministic and insecure.
-fn hash_bytes32(env: &Env, input: &Bytes) -> BytesN<32> {
-    let hash: BytesN<32> = env.crypto().sha256(input).into();
-    if hash.to_array() == [0u8; 32] {
-        panic!("crypto.sha256() failed: invalid hash output");
+impl WinnerIndexSink for Vec<u32> {
+    fn contains_index(&self, idx: u32) -> bool {
+        self.contains(idx)
+    }
+
+    fn push_index(&mut self, idx: u32) {
+        self.push_back(idx);
     }
-    hash
 }
 
-/// Common winner-selection interface used by both PRNG and oracle paths.
-pub trait WinnerSelectionStrategy {
-    fn select_winner_indices(&self, env: &Env, total_tickets: u32, winner_count: u32) -> Vec<u32>;
+#[cfg(any(test, feature = "std"))]
+impl WinnerIndexSink for std::vec::Vec<u32> {
+    fn contains_index(&self, idx: u32) -> bool {
+        self.contains(&idx)
+    }
+
+    fn push_index(&mut self, idx: u32) {
+        self.push(idx);
+    }
 }
 
-/// Internal PRNG-based winner selection.
-///
-/// Uses [`build_internal_seed`] to construct a multi-source seed that is then
-/// fed into `env.prng()`.  The same inputs always produce the same winners,
-/// which allows off-chain verification of draws.
+/// Widens a 64-bit seed from a legacy fairness record into the 256-bit form:
+/// the big-endian seed followed by 24 zero bytes.
 ///
-/// **For low-stakes raffles only** — see [`build_internal_seed`] for the full
-/// security caveat.
-pub struct PrngWinnerSelection {
-    _timestamp: u64,
-    _sequence: u32,
-    raffle_id: Address,
-    tickets_sold: u32,
-    pub timestamp: u64,
-    pub sequence: u32,
-    pub raffle_id: Address,
-    pub tickets_sold: u32,
+/// [`SelectionAlgorithm::LcgRejectionV1`] reads exactly those first eight
+/// bytes, so legacy draws replay to the same winners.
+pub const fn widen_legacy_seed(seed: u64) -> [u8; 32] {
+    let be = seed.to_be_bytes();
+    let mut out = [0u8; 32];
+    let mut i = 0;
+    while i < 8 {
+        out[i] = be[i];
+        i += 1;
+    }
+    out
 }
 
-impl PrngWinnerSelection {
-    pub fn new(raffle_id: Address, tickets_sold: u32) -> Self {
-        Self {
-            _timestamp: timestamp,
-            _sequence: sequence,
-            raffle_id,
-            tickets_sold,
-        }
-    }
+fn lcg_next(state: u64) -> u64 {
+    state.wrapping_mul(LCG_MULTIPLIER).wrapping_add(LCG_INCREMENT)
+}
 
-    /// Returns a compact u64 fingerprint of the seed for inclusion in the
-    /// on-chain `FairnessMetadata` event.  This is derived from the same
-    /// inputs as the actual seed so it can be used to spot-check draws.
-    pub fn seed_fingerprint(&self, env: &Env) -> u64 {
-        let hashed = hash_bytes32(env, &self.seed_bytes(env));
-        let arr = hashed.to_array();
-        u64::from_be_bytes([
-            arr[0], arr[1], arr[2], arr[3], arr[4], arr[5], arr[6], arr[7],
-        ])
+/// [`SelectionAlgorithm::LcgRejectionV1`]: steps a 64-bit LCG and uses
+/// rejection sampling to pick distinct indices in `[0, total_tickets)`.
+///
+/// The LCG state is the first eight seed bytes (big-endian), so this
+/// algorithm only carries 64 bits of the seed.  It is kept for replaying
+/// draws made before 256-bit seeds; prefer [`SelectionAlgorithm::Sha256CounterV2`].
+pub fn select_lcg_rejection_v1<S: WinnerIndexSink>(
+    seed: &[u8; 32],
+    total_tickets: u32,
+    winner_count: u32,
+    out: &mut S,
+) {
+    if total_tickets == 0 || winner_count == 0 {
+        return;
     }
 
-    /// Returns the raw 32-byte seed as `Bytes` for `env.prng().seed()`.
-    fn seed_bytes(&self, env: &Env) -> Bytes {
-        let base: BytesN<32> = build_internal_seed(env, &self.raffle_id);
-        // XDR-pack the base seed + tickets_sold and re-hash to include the
-        // extra entropy source without truncating the network_id contribution.
-        let combined: Bytes = (base, self.tickets_sold).to_xdr(env);
-        hash_bytes32(env, &combined).into()
+    // #257: Use rejection sampling to eliminate modulo bias.
+    // We discard samples that fall in the biased tail so every ticket in
+    // [0, total_tickets) is chosen with exactly equal probability.
+    //
+    // largest_multiple = floor(u64::MAX / total_tickets) * total_tickets
+    // Any sample >= largest_multiple is rejected and the seed advanced.
+    let n = total_tickets as u64;
+    let largest_multiple = (u64::MAX / n) * n;
+
+    let mut state = [0u8; 8];
+    state.copy_from_slice(&seed[..8]);
+
+    let effective_count = winner_count.min(total_tickets);
+    let mut current_seed = u64::from_be_bytes(state);
+    for _ in 0..effective_count {
+        let idx = loop {
+            let candidate = loop {
+                if current_seed < largest_multiple {
+                    break (current_seed % n) as u32;
+                }
+                current_seed = lcg_next(current_seed);
+            };
+            if !out.contains_index(candidate) {
+                break candidate;
+            }
+            current_seed = lcg_next(current_seed);
+        };
+        out.push_index(idx);
+        current_seed = lcg_next(current_seed);
     }
 }
 
-impl WinnerSelectionStrategy for PrngWinnerSelection {
-    fn select_winner_indices(&self, env: &Env, total_tickets: u32, winner_count: u32) -> Vec<u32> {
-        let mut indices = Vec::new(env);
-        if total_tickets == 0 || winner_count == 0 {
-            return indices;
-        }
-
-        // Seed the PRNG with the multi-source hash — see build_internal_seed
-        // for details on the entropy inputs.
-        env.prng().seed(self.seed_bytes(env));
+/// Message hashed for block `counter` of [`SelectionAlgorithm::Sha256CounterV2`]:
+/// the 32-byte seed followed by the big-endian counter.
+pub fn sha256_counter_block_input(seed: &[u8; 32], counter: u32) -> [u8; 36] {
+    let mut input = [0u8; 36];
+    input[..32].copy_from_slice(seed);
+    input[32..].copy_from_slice(&counter.to_be_bytes());
+    input
+}
 
-        let effective_count = winner_count.min(total_tickets);
-        for _ in 0..effective_count {
-            // Keep sampling until we find an index that hasn't been selected yet
-            loop {
-                #[allow(deprecated)]
-                let idx = env.prng().u64_in_range(0..(total_tickets as u64)) as u32;
-
-                // Check if this index is already in the selected indices
-                let mut found = false;
-                for i in 0..indices.len() {
-                    if indices.get(i) == Some(idx) {
-                        found = true;
-                        break;
-                    }
-                }
+/// [`SelectionAlgorithm::Sha256CounterV2`]: draws one big-endian u64 from the
+/// first eight bytes of each SHA-256 block and rejection-samples it into
+/// `[0, total_tickets)`, skipping indices that were already selected.
+///
+/// `block` returns the digest of [`sha256_counter_block_input`] for a counter
+/// value; it is a parameter so the host and off-chain hashers share this code.
+pub fn select_sha256_counter_v2<S, H>(
+    total_tickets: u32,
+    winner_count: u32,
+    out: &mut S,
+    mut block: H,
+) where
+    S: WinnerIndexSink,
+    H: FnMut(u32) -> [u8; 32],
+{
+    if total_tickets == 0 || winner_count == 0 {
+        return;
+    }
 
-                // If not found, add it and break; otherwise resample
-                if !found {
-                    indices.push_back(idx);
-                    break;
-                }
+    let n = total_tickets as u64;
+    let largest_multiple = (u64::MAX / n) * n;
+
+    let effective_count = winner_count.min(total_tickets);
+    let mut counter: u32 = 0;
+    for _ in 0..effective_count {
+        let idx = loop {
+            let digest = block(counter);
+            counter = counter.wrapping_add(1);
+            let mut word = [0u8; 8];
+            word.copy_from_slice(&digest[..8]);
+            let sample = u64::from_be_bytes(word);
+            if sample >= largest_multiple {
+                continue;
             }
-        }
+            let candidate = (sample % n) as u32;
+            if !out.contains_index(candidate) {
+                break candidate;
+            }
+        };
+        out.push_index(idx);
+    }
+}
+
+/// [`SelectionAlgorithm::LcgRejectionV1`] strategy.
+pub struct LcgRejectionWinnerSelection {
+    seed: [u8; 32],
+}
 
+impl LcgRejectionWinnerSelection {
+    pub fn new(seed: [u8; 32]) -> Self {
+        Self { seed }
+    }
+
+    #[cfg(any(test, feature = "std"))]
+    pub fn select_winner_indices_pure(&self, total_tickets: u32, winner_count: u32) -> std::vec::Vec<u32> {
+        let mut indices = std::vec::Vec::new();
+        select_lcg_rejection_v1(&self.seed, total_tickets, winner_count, &mut indices);
         indices
     }
 }
 
-/// Builds the Ed25519 message that binds a VRF proof to a specific raffle request.
-///
-/// The oracle must sign this exact byte sequence when calling `provide_randomness`.
-pub fn build_vrf_proof_message(env: &Env, request_id: u64, random_seed: u64) -> Bytes {
-    (env.current_contract_address(), request_id, random_seed).to_xdr(env)
+impl WinnerSelectionStrategy for LcgRejectionWinnerSelection {
+    fn select_winner_indices(&self, env: &Env, total_tickets: u32, winner_count: u32) -> Vec<u32> {
+        let mut indices = Vec::new(env);
+        select_lcg_rejection_v1(&self.seed, total_tickets, winner_count, &mut indices);
+        indices
+    }
 }
 
-/// Oracle-backed strategy using an externally provided VRF seed.
-///
-/// Used by [`provide_randomness`] after the oracle has delivered a
-/// cryptographically-verified random value.  Not subject to the
-/// manipulability concerns of the PRNG path.
-pub struct OracleSeedWinnerSelection {
-    seed: u64,
+/// [`SelectionAlgorithm::Sha256CounterV2`] strategy.
+pub struct Sha256CounterWinnerSelection {
+    seed: [u8; 32],
 }
 
-impl OracleSeedWinnerSelection {
-    pub fn new(seed: u64) -> Self {
+impl Sha256CounterWinnerSelection {
+    pub fn new(seed: [u8; 32]) -> Self {
         Self { seed }
     }
 
     #[cfg(any(test, feature = "std"))]
     pub fn select_winner_indices_pure(&self, total_tickets: u32, winner_count: u32) -> std::vec::Vec<u32> {
+        use sha2::{Digest, Sha256};
+
         let mut indices = std::vec::Vec::new();
-        if total_tickets == 0 || winner_count == 0 {
-            return indices;
-        }
+        select_sha256_counter_v2(total_tickets, winner_count, &mut indices, |counter| {
+            Sha256::digest(sha256_counter_block_input(&self.seed, counter)).into()
+        });
+        indices
+    }
+}
 
-        let n = total_tickets as u64;
-        let largest_multiple = (u64::MAX / n) * n;
+impl WinnerSelectionStrategy for Sha256CounterWinnerSelection {
+    fn select_winner_indices(&self, env: &Env, total_tickets: u32, winner_count: u32) -> Vec<u32> {
+        let mut indices = Vec::new(env);
+        select_sha256_counter_v2(total_tickets, winner_count, &mut indices, |counter| {
+            let input = Bytes::from_array(env, &sha256_counter_block_input(&self.seed, counter));
+            env.crypto().sha256(&input).to_array()
+        });
+        indices
+    }
+}
 
-        let mut current_seed = self.seed;
-        for _ in 0..winner_count {
-            let idx = loop {
-                if current_seed < largest_multiple {
-                    break (current_seed % n) as u32;
-                }
-                current_seed = current_seed
-                    .wrapping_mul(6364136223846793005)
-                    .wrapping_add(1442695040888963407);
-            };
-            indices.push(idx);
-            current_seed = current_seed
-                .wrapping_mul(6364136223846793005)
-                .wrapping_add(1442695040888963407);
-        }
+/// [`SelectionAlgorithm::EnvPrng`] strategy: reseeds the host PRNG with the
+/// draw seed and samples distinct indices from it.
+///
+/// Replayable only through a Soroban host, since the output depends on the
+/// host's PRNG implementation.
+pub struct SeededPrngWinnerSelection {
+    seed: [u8; 32],
+}
 
-        indices
+impl SeededPrngWinnerSelection {
+    pub fn new(seed: [u8; 32]) -> Self {
+        Self { seed }
     }
 }
 
-impl WinnerSelectionStrategy for OracleSeedWinnerSelection {
+impl WinnerSelectionStrategy for SeededPrngWinnerSelection {
     fn select_winner_indices(&self, env: &Env, total_tickets: u32, winner_count: u32) -> Vec<u32> {
         let mut indices = Vec::new(env);
         if total_tickets == 0 || winner_count == 0 {
             return indices;
         }
 
-        // #257: Use rejection sampling to eliminate modulo bias.
-        // We discard samples that fall in the biased tail so every ticket in
-        // [0, total_tickets) is chosen with exactly equal probability.
-        //
-        // largest_multiple = floor(u64::MAX / total_tickets) * total_tickets
-        // Any sample >= largest_multiple is rejected and the seed advanced.
-        let n = total_tickets as u64;
-        let largest_multiple = (u64::MAX / n) * n;
+        env.prng().seed(Bytes::from_array(env, &self.seed));
 
         let effective_count = winner_count.min(total_tickets);
-        let mut current_seed = self.seed;
         for _ in 0..effective_count {
-            let idx = loop {
-                let candidate = loop {
-                    if current_seed < largest_multiple {
-                        break (current_seed % n) as u32;
-                    }
-                    current_seed = current_seed
-                        .wrapping_mul(6364136223846793005)
-                        .wrapping_add(1442695040888963407);
-                };
-                let mut found = false;
-                for i in 0..indices.len() {
-                    if indices.get(i).unwrap() == candidate {
-                        found = true;
-                        break;
-                    }
-                }
-                if !found {
-                    break candidate;
+            loop {
+                let idx: u64 = env.prng().gen_range(0..(total_tickets as u64));
+                let idx = idx as u32;
+                if !indices.contains(idx) {
+                    indices.push_back(idx);
+                    break;
                 }
-                current_seed = current_seed
-                    .wrapping_mul(6364136223846793005)
-                    .wrapping_add(1442695040888963407);
-            };
-            indices.push_back(idx);
-            current_seed = current_seed
-                .wrapping_mul(6364136223846793005)
-                .wrapping_add(1442695040888963407);
+            }
         }
 
         indices
     }
 }
 
+/// Selects winner indices with the raffle's recorded `algorithm`.
+///
+/// Single dispatch point used by finalization, so a raffle always replays
+/// through the algorithm it was created with.
+pub fn select_winners_with(
+    env: &Env,
+    algorithm: &SelectionAlgorithm,
+    seed: &BytesN<32>,
+    total_tickets: u32,
+    winner_count: u32,
+) -> Vec<u32> {
+    let seed = seed.to_array();
+    match algorithm {
+        SelectionAlgorithm::LcgRejectionV1 => LcgRejectionWinnerSelection::new(seed)
+            .select_winner_indices(env, total_tickets, winner_count),
+        SelectionAlgorithm::Sha256CounterV2 => Sha256CounterWinnerSelection::new(seed)
+            .select_winner_indices(env, total_tickets, winner_count),
+        SelectionAlgorithm::EnvPrng => SeededPrngWinnerSelection::new(seed)
+            .select_winner_indices(env, total_tickets, winner_count),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use soroban_sdk::{testutils::Address as _, Address, Env};
 
-    /// build_internal_seed produces different values for different raffle IDs.
-    #[test]
-    fn build_internal_seed_differs_by_raffle_id() {
-        let env = Env::default();
-        let id_a = Address::generate(&env);
-        let id_b = Address::generate(&env);
-        let contract = env
-            .register_stellar_asset_contract_v2(Address::generate(&env))
-            .address();
-
-        let (seed_a, seed_b) = env.as_contract(&contract, || {
-            (
-                build_internal_seed(&env, &id_a),
-                build_internal_seed(&env, &id_b),
-            )
-        });
-
-        assert_ne!(
-            seed_a, seed_b,
-            "different raffle IDs must produce different seeds"
-        );
-    }
-
-    /// build_internal_seed is deterministic: same inputs → same output.
-    #[test]
-    fn build_internal_seed_is_deterministic() {
-        let env = Env::default();
-        let raffle_id = Address::generate(&env);
-        let contract = env
-            .register_stellar_asset_contract_v2(Address::generate(&env))
-            .address();
-
-        let (first, second) = env.as_contract(&contract, || {
-            (
-                build_internal_seed(&env, &raffle_id),
-                build_internal_seed(&env, &raffle_id),
-            )
-        });
+    // ------------------------------------------------------------------------
+    // Known-answer vectors.  These pin each `SelectionAlgorithm` id; if one of
+    // them fails, the algorithm changed and past draws no longer replay.
+    // ------------------------------------------------------------------------
 
-        assert_eq!(first, second, "same inputs must always yield the same seed");
-    }
+    const KAT_CASES: [([u8; 32], u32, u32); 3] = [
+        (widen_legacy_seed(42), 10, 3),
+        (widen_legacy_seed(0xDEAD_BEEF_CAFE_BABE), 1000, 5),
+        ([0x5A; 32], 5, 5),
+    ];
 
-    /// build_internal_seed output is exactly 32 bytes.
-    #[test]
-    fn build_internal_seed_is_32_bytes() {
+    fn host_selection(algorithm: SelectionAlgorithm, seed: &[u8; 32], total: u32, count: u32) -> std::vec::Vec<u32> {
         let env = Env::default();
-        let raffle_id = Address::generate(&env);
         let contract = env
             .register_stellar_asset_contract_v2(Address::generate(&env))
             .address();
-
-        let seed = env.as_contract(&contract, || build_internal_seed(&env, &raffle_id));
-        // BytesN<32> is always 32 bytes by construction; this is a compile-time
-        // guarantee, but we also verify the array conversion is loss-free.
-        assert_eq!(seed.to_array().len(), 32);
+        env.as_contract(&contract, || {
+            select_winners_with(&env, &algorithm, &BytesN::from_array(&env, seed), total, count)
+                .iter()
+                .collect()
+        })
     }
 
-    /// build_internal_seed must not produce the all-zero hash.
     #[test]
-    fn build_internal_seed_is_not_zero() {
-        let env = Env::default();
-        let raffle_id = Address::generate(&env);
-        let contract = env
-            .register_stellar_asset_contract_v2(Address::generate(&env))
-            .address();
-
-        let seed = env.as_contract(&contract, || build_internal_seed(&env, &raffle_id));
-        assert_ne!(
-            seed.to_array(),
-            [0u8; 32],
-            "sha256 output must not be all zero"
-        );
+    fn lcg_rejection_v1_known_answers() {
+        // The first two cases are widened 64-bit seeds and must match the
+        // winners the pre-256-bit contract drew for the same u64 seed.
+        let expected: [&[u32]; 3] = [&[2, 3, 0], &[46, 181, 416, 623, 402], &[0, 1, 4, 3, 2]];
+        for ((seed, total, count), want) in KAT_CASES.iter().zip(expected) {
+            let pure = LcgRejectionWinnerSelection::new(*seed).select_winner_indices_pure(*total, *count);
+            assert_eq!(pure, want);
+            assert_eq!(host_selection(SelectionAlgorithm::LcgRejectionV1, seed, *total, *count), want);
+        }
     }
 
-    /// PRNG selections fall within [0, total_tickets).
     #[test]
-    fn prng_selection_is_in_ticket_range() {
-        let env = Env::default();
-        let raffle_id = Address::generate(&env);
-        let strategy = PrngWinnerSelection::new(raffle_id, 17);
-
-        let contract_id = env
-            .register_stellar_asset_contract_v2(Address::generate(&env))
-            .address();
-        let indices = env.as_contract(&contract_id, || {
-            strategy.select_winner_indices(&env, 17, 25)
-        });
-        assert_eq!(indices.len(), 17);
-        for idx in indices.iter() {
-            assert!(idx < 17, "winner index {idx} must be < total_tickets 17");
+    fn sha256_counter_v2_known_answers() {
+        let expected: [&[u32]; 3] = [&[3, 1, 4], &[42, 765, 574, 793, 674], &[3, 2, 4, 0, 1]];
+        for ((seed, total, count), want) in KAT_CASES.iter().zip(expected) {
+            let pure = Sha256CounterWinnerSelection::new(*seed).select_winner_indices_pure(*total, *count);
+            assert_eq!(pure, want);
+            assert_eq!(host_selection(SelectionAlgorithm::Sha256CounterV2, seed, *total, *count), want);
         }
     }
 
-    /// Same PRNG inputs always produce the same winner sequence.
     #[test]
-    fn prng_selection_is_deterministic_for_same_inputs() {
-        let env = Env::default();
-        let raffle_id = Address::generate(&env);
-
-        let contract_id = env
-            .register_stellar_asset_contract_v2(Address::generate(&env))
-            .address();
-        let first = env.as_contract(&contract_id, || {
-            PrngWinnerSelection::new(raffle_id.clone(), 17)
-                .select_winner_indices(&env, 17, 8)
-        });
-        let second = env.as_contract(&contract_id, || {
-            PrngWinnerSelection::new(raffle_id, 17)
-                .select_winner_indices(&env, 17, 8)
-        });
-
-        assert_eq!(
-            first, second,
-            "identical inputs must yield identical winners"
-        );
+    fn env_prng_known_answers() {
+        let expected: [&[u32]; 3] = [&[8, 9, 4], &[402, 478, 958, 516, 443], &[1, 2, 4, 3, 0]];
+        for ((seed, total, count), want) in KAT_CASES.iter().zip(expected) {
+            assert_eq!(host_selection(SelectionAlgorithm::EnvPrng, seed, *total, *count), want);
+        }
     }
 
-    /// Seed fingerprint changes when raffle_id changes.
     #[test]
-    fn seed_fingerprint_differs_by_raffle_id() {
-        let env = Env::default();
-        let id_a = Address::generate(&env);
-        let id_b = Address::generate(&env);
-        let contract = env
-            .register_stellar_asset_contract_v2(Address::generate(&env))
-            .address();
-
-        let (fp_a, fp_b) = env.as_contract(&contract, || {
-            let s_a = PrngWinnerSelection::new(id_a, 10);
-            let s_b = PrngWinnerSelection::new(id_b, 10);
-            (s_a.seed_fingerprint(&env), s_b.seed_fingerprint(&env))
-        });
-
-        assert_ne!(
-            fp_a, fp_b,
-            "fingerprints must differ for different raffle IDs"
-        );
+    fn sha256_counter_block_input_is_seed_then_counter() {
+        let input = sha256_counter_block_input(&[0x11; 32], 0x0A0B_0C0D);
+        assert_eq!(input[..32], [0x11; 32]);
+        assert_eq!(input[32..], [0x0A, 0x0B, 0x0C, 0x0D]);
     }
 
-    /// Seed fingerprint changes when ticket count changes.
     #[test]
-    fn seed_fingerprint_differs_by_ticket_count() {
-        let env = Env::default();
-        let raffle_id = Address::generate(&env);
-        let contract = env
-            .register_stellar_asset_contract_v2(Address::generate(&env))
-            .address();
-
-        let (fp_a, fp_b) = env.as_contract(&contract, || {
-            let s_a = PrngWinnerSelection::new(raffle_id.clone(), 10);
-            let s_b = PrngWinnerSelection::new(raffle_id, 11);
-            (s_a.seed_fingerprint(&env), s_b.seed_fingerprint(&env))
-        });
-
-        assert_ne!(
-            fp_a, fp_b,
-            "fingerprints must differ for different ticket counts"
-        );
+    fn widen_legacy_seed_is_big_endian_then_zero() {
+        let wide = widen_legacy_seed(0x0102_0304_0506_0708);
+        assert_eq!(wide[..8], [1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(wide[8..], [0u8; 24]);
     }
 }
@@ -1,14 +1,26 @@
 #![cfg(test)]
 
 use super::*;
+use crate::randomness::build_vrf_proof_message;
 use ed25519_dalek::{Signer, SigningKey};
 use soroban_sdk::{
-    testutils::{Address as _, Ledger},
-    token::StellarAssetClient,
+    testutils::{Address as _, Events as _, Ledger},
+    token::{self, StellarAssetClient},
+    vec,
     xdr::ToXdr,
-    Address, Bytes, BytesN, Env, String,
+    Address, Bytes, BytesN, Env, String, Symbol, TryFromVal,
 };
-use raffle_shared::{DEFAULT_CLAIM_LOCKUP_SECONDS, DEFAULT_SWAP_DEADLINE_SECONDS};
+use raffle_shared::constants::{FAIRNESS_VERSION_SEED256, FAIRNESS_VERSION_SEED64};
+use raffle_shared::roles::Role;
+use raffle_shared::{CreatorOutcome, GateRule, RandomnessType, RefundTarget, SelectionAlgorithm, DEFAULT_CLAIM_LOCKUP_SECONDS, DEFAULT_SWAP_DEADLINE_SECONDS};
+
+// Deploy a Stellar Asset Contract we control, return (token_address, admin_client).
+fn create_token<'a>(env: &Env, admin: &Address) -> (Address, token::StellarAssetClient<'a>) {
+    let sac = env.register_stellar_asset_contract_v2(admin.clone());
+    let addr = sac.address();
+    (addr.clone(), token::StellarAssetClient::new(env, &addr))
+}
+
 
 fn assert_drawing_lock_cleared(env: &Env, contract_id: &Address) {
     let is_set: bool = env.as_contract(contract_id, || {
@@ -20,13 +32,31 @@ fn assert_drawing_lock_cleared(env: &Env, contract_id: &Address) {
     assert!(!is_set, "DrawingLock must be cleared");
 }
 
+/// Installs `account` as the sole holder of `role`, as the factory's
+/// `sync_roles` would.
+fn set_role(env: &Env, client: &ContractClient<'_>, role: Role, account: &Address) {
+    client.set_roles(&soroban_sdk::vec![
+        env,
+        (role, soroban_sdk::vec![env, account.clone()])
+    ]);
+}
+
+/// Runs both steps of a delayed internal draw: the `finalize_raffle` commit,
+/// then `execute_draw` once the target ledger is reached.
+fn finalize_and_execute_draw(env: &Env, client: &ContractClient<'_>, caller: &Address) {
+    client.finalize_raffle();
+    env.ledger()
+        .with_mut(|l| l.sequence_number += RANDOMNESS_MIN_DELAY_LEDGERS);
+    client.execute_draw(caller);
+}
+
 #[test]
 fn test_oracle_fallback_with_ledger_delays() {
     let env = Env::default();
     env.mock_all_auths();
 
     // 1. Setup factory, admin, creator
-    let factory = Address::generate(&env);
+    let factory = env.register(MockFactory, ());
     let admin = Address::generate(&env);
     let creator = Address::generate(&env);
     let oracle = Address::generate(&env);
@@ -37,8 +67,8 @@ fn test_oracle_fallback_with_ledger_delays() {
     let token_client = StellarAssetClient::new(&env, &payment_token);
     token_client.mint(&creator, &100_000_000);
 
-    let contract_id = env.register(RaffleInstance, ());
-    let client = RaffleInstanceClient::new(&env, &contract_id);
+    let contract_id = env.register(Contract, ());
+    let client = ContractClient::new(&env, &contract_id);
 
     // 2. Initialize Raffle with External Randomness
     let config = RaffleConfig {
@@ -64,6 +94,13 @@ fn test_oracle_fallback_with_ledger_delays() {
         swap_deadline_seconds: 0,
         early_bird_ticket_percentage: 0,
         early_bird_discount_bp: 0,
+        prize_token: None,
+        nft_contract: None,
+        selection_algorithm: SelectionAlgorithm::Sha256CounterV2,
+        sale_gate: SaleGate::Open,
+        max_tickets_per_address: 0,
+        max_holding_bp: 0,
+        refund_target: RefundTarget::Owner,
     };
 
     client.init(&factory, &admin, &creator, &config);
@@ -82,8 +119,7 @@ fn test_oracle_fallback_with_ledger_delays() {
     client.deposit_prize();
     client.buy_tickets(&creator, &10);
 
-    // 4. Finalize raffle (requests randomness)
-    client.finalize_raffle();
+    // 4. Selling out requests randomness from the oracle
 
     // 5. Ensure it's in Drawing state and requested randomness
     let raffle = client.get_raffle();
@@ -116,14 +152,14 @@ fn test_admin_updates_oracle_address() {
     let env = Env::default();
     env.mock_all_auths();
 
-    let factory = Address::generate(&env);
+    let factory = env.register(MockFactory, ());
     let admin = Address::generate(&env);
     let creator = Address::generate(&env);
     let oracle = Address::generate(&env);
     let new_oracle = Address::generate(&env);
 
-    let contract_id = env.register(RaffleInstance, ());
-    let client = RaffleInstanceClient::new(&env, &contract_id);
+    let contract_id = env.register(Contract, ());
+    let client = ContractClient::new(&env, &contract_id);
 
     let config = RaffleConfig {
         description: String::from_str(&env, "Oracle migration"),
@@ -150,6 +186,13 @@ fn test_admin_updates_oracle_address() {
         swap_deadline_seconds: 0,
         early_bird_ticket_percentage: 0,
         early_bird_discount_bp: 0,
+        prize_token: None,
+        nft_contract: None,
+        selection_algorithm: SelectionAlgorithm::Sha256CounterV2,
+        sale_gate: SaleGate::Open,
+        max_tickets_per_address: 0,
+        max_holding_bp: 0,
+        refund_target: RefundTarget::Owner,
     };
 
     client.init(&factory, &admin, &creator, &config);
@@ -159,20 +202,42 @@ fn test_admin_updates_oracle_address() {
     assert_eq!(raffle.claim_lockup_seconds, DEFAULT_CLAIM_LOCKUP_SECONDS);
     assert_eq!(raffle.swap_deadline_seconds, DEFAULT_SWAP_DEADLINE_SECONDS);
 
+    set_role(&env, &client, Role::Moderator, &admin);
+    client.update_oracle_address(&admin, &new_oracle);
+    assert_eq!(client.get_raffle().oracle_address, Some(new_oracle));
+}
+
+#[contract]
+pub struct MockFactory;
+
+
 #[contractimpl]
 impl MockFactory {
-    pub fn record_volume(_env: Env, _token: Address, _amount: i128) {}
-    pub fn track_participant(_env: Env, _participant: Address) {}
+    pub fn record_volume(_env: Env, _raffle: Address, _token: Address, _amount: i128) {}
+    pub fn reverse_volume(_env: Env, _raffle: Address, _token: Address, _amount: i128) {}
+    pub fn notify_status_change(_env: Env, _raffle: Address, _new_status: RaffleStatus) {}
+    pub fn report_creator_outcome(_env: Env, _raffle: Address, _outcome: CreatorOutcome) {}
+    pub fn record_participation(_env: Env, _raffle: Address, _participant: Address) {}
+    pub fn has_role(_env: Env, _role: Role, _account: Address) -> bool {
+        true
+    }
+    pub fn is_token_enabled(_env: Env, _token: Address) -> bool {
+        true
+    }
 }
 
 #[test]
-fn non_winner_cannot_claim() {
+fn test_admin_updates_protocol_fee() {
     let env = Env::default();
     env.mock_all_auths();
     env.ledger().set_timestamp(1_000);
 
-    let contract_id = env.register(RaffleInstance, ());
-    let client = RaffleInstanceClient::new(&env, &contract_id);
+    let contract_id = env.register(Contract, ());
+    let client = ContractClient::new(&env, &contract_id);
+
+    let factory = env.register(MockFactory, ());
+    let admin = Address::generate(&env);
+    let creator = Address::generate(&env);
 
     let config = RaffleConfig {
         description: String::from_str(&env, "Fee update"),
@@ -199,6 +264,13 @@ fn non_winner_cannot_claim() {
         swap_deadline_seconds: 0,
         early_bird_ticket_percentage: 0,
         early_bird_discount_bp: 0,
+        prize_token: None,
+        nft_contract: None,
+        selection_algorithm: SelectionAlgorithm::Sha256CounterV2,
+        sale_gate: SaleGate::Open,
+        max_tickets_per_address: 0,
+        max_holding_bp: 0,
+        refund_target: RefundTarget::Owner,
     };
 
     client.init(&factory, &admin, &creator, &config);
@@ -208,19 +280,92 @@ fn non_winner_cannot_claim() {
     assert_eq!(raffle.claim_lockup_seconds, DEFAULT_CLAIM_LOCKUP_SECONDS);
     assert_eq!(raffle.swap_deadline_seconds, DEFAULT_SWAP_DEADLINE_SECONDS);
 
-    client.set_protocol_fee_bp(&500);
+    set_role(&env, &client, Role::FeeManager, &admin);
+    client.set_protocol_fee_bp(&admin, &500);
 
     let raffle = client.get_raffle();
     assert_eq!(raffle.protocol_fee_bp, 500);
 }
 
 #[test]
-fn test_admin_withdraws_accumulated_fees() {
+fn test_accumulated_fees_leave_only_through_factory_sweep() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1_000);
+
+    let contract_id = env.register(Contract, ());
+    let client = ContractClient::new(&env, &contract_id);
+
+    let factory = env.register(MockFactory, ());
+    let admin = Address::generate(&env);
+    let creator = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let treasury = Address::generate(&env);
+
+    let token_admin = Address::generate(&env);
+    let (token_addr, token_mint) = create_token(&env, &token_admin);
+    token_mint.mint(&creator, &1_000_000);
+    token_mint.mint(&buyer, &1_000_000);
+
+    let config = RaffleConfig {
+        description: String::from_str(&env, "fee sweep"),
+        end_time: 0,
+        no_deadline: true,
+        max_tickets: 2,
+        max_tickets_per_tx: 2,
+        min_tickets: 1,
+        allow_multiple: true,
+        ticket_price: MIN_TICKET_PRICE,
+        payment_token: token_addr.clone(),
+        prize_amount: MIN_TICKET_PRICE * 10,
+        prizes: vec![&env, 10000u32],
+        randomness_source: RandomnessSource::Internal,
+        oracle_address: None,
+        protocol_fee_bp: 1_000,
+        treasury_address: None,
+        swap_router: None,
+        tikka_token: None,
+        metadata_hash: BytesN::from_array(&env, &[2u8; 32]),
+        claim_lockup_seconds: 0,
+        swap_deadline_seconds: 0,
+        early_bird_ticket_percentage: 0,
+        early_bird_discount_bp: 0,
+        prize_token: None,
+        nft_contract: None,
+        selection_algorithm: SelectionAlgorithm::Sha256CounterV2,
+        sale_gate: SaleGate::Open,
+        max_tickets_per_address: 0,
+        max_holding_bp: 0,
+        refund_target: RefundTarget::Owner,
+    };
+
+    client.init(&factory, &admin, &creator, &config);
+    client.deposit_prize();
+    client.buy_tickets(&buyer, &2);
+    finalize_and_execute_draw(&env, &client, &creator);
+
+    let fees = 2 * MIN_TICKET_PRICE * 1_000 / 10_000;
+    assert_eq!(client.get_accumulated_fees(), fees);
+
+    assert_eq!(client.sweep_fees(&treasury), fees);
+    assert_eq!(env.auths()[0].0, factory);
+    assert_eq!(token::Client::new(&env, &token_addr).balance(&treasury), fees);
+    assert_eq!(client.get_accumulated_fees(), 0);
+
+    assert_eq!(client.sweep_fees(&treasury), 0);
+}
+
+
+#[test]
+fn non_winner_cannot_claim() {
     let env = Env::default();
     env.mock_all_auths();
     env.ledger().set_timestamp(1_000);
 
-    let factory = Address::generate(&env);
+    let contract_id = env.register(Contract, ());
+    let client = ContractClient::new(&env, &contract_id);
+
+    let factory = env.register(MockFactory, ());
     let admin = Address::generate(&env);
     let creator = Address::generate(&env);
     let buyer = Address::generate(&env);
@@ -254,6 +399,13 @@ fn test_admin_withdraws_accumulated_fees() {
         swap_deadline_seconds: 0,
         early_bird_ticket_percentage: 0,
         early_bird_discount_bp: 0,
+        prize_token: None,
+        nft_contract: None,
+        selection_algorithm: SelectionAlgorithm::Sha256CounterV2,
+        sale_gate: SaleGate::Open,
+        max_tickets_per_address: 0,
+        max_holding_bp: 0,
+        refund_target: RefundTarget::Owner,
     };
 
     client.init(&factory, &admin, &creator, &config);
@@ -261,7 +413,7 @@ fn test_admin_withdraws_accumulated_fees() {
     client.buy_tickets(&buyer, &1);
     env.ledger().set_timestamp(2_000);
     env.ledger().set_timestamp(2_000);
-    client.finalize_raffle();
+    finalize_and_execute_draw(&env, &client, &creator);
 
     let raffle = client.get_raffle();
     assert_eq!(raffle.winners.len(), 1);
@@ -315,6 +467,13 @@ fn buy_tickets_rejects_quantity_above_per_tx_cap() {
         swap_deadline_seconds: 0,
         early_bird_ticket_percentage: 0,
         early_bird_discount_bp: 0,
+        prize_token: None,
+        nft_contract: None,
+        selection_algorithm: SelectionAlgorithm::Sha256CounterV2,
+        sale_gate: SaleGate::Open,
+        max_tickets_per_address: 0,
+        max_holding_bp: 0,
+        refund_target: RefundTarget::Owner,
     };
 
     client.init(&factory, &admin, &creator, &config);
@@ -354,8 +513,8 @@ fn setup_active_raffle(
         description: String::from_str(env, "ticket sales pause"),
         end_time: 0,
         no_deadline: true,
-        max_tickets: 1,
-        max_tickets_per_tx: 1,
+        max_tickets: 10,
+        max_tickets_per_tx: 10,
         min_tickets: 1,
         allow_multiple: true,
         ticket_price: MIN_TICKET_PRICE,
@@ -373,6 +532,13 @@ fn setup_active_raffle(
         swap_deadline_seconds: 0,
         early_bird_ticket_percentage: 0,
         early_bird_discount_bp: 0,
+        prize_token: None,
+        nft_contract: None,
+        selection_algorithm: SelectionAlgorithm::Sha256CounterV2,
+        sale_gate: SaleGate::Open,
+        max_tickets_per_address: 0,
+        max_holding_bp: 0,
+        refund_target: RefundTarget::Owner,
     };
 
     client.init(&factory, &admin, &creator, &config);
@@ -381,6 +547,21 @@ fn setup_active_raffle(
     (client, admin, creator, buyer, factory, token_mint)
 }
 
+#[test]
+fn buy_tickets_fails_closed_when_token_allowlist_is_unreadable() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1_000);
+
+    let (client, _admin, _creator, buyer, _factory, token_mint) = setup_active_raffle(&env);
+
+    // A factory without `is_token_enabled` cannot confirm the token.
+    env.as_contract(&client.address, || {
+        env.storage().instance().set(&DataKey::Factory, &token_mint.address);
+    });
+    assert_eq!(client.try_buy_tickets(&buyer, &1), Err(Ok(Error::TokenDisabled)));
+}
+
 #[test]
 fn pause_resume_ticket_sales_controls_buy_tickets() {
     let env = Env::default();
@@ -392,32 +573,12 @@ fn pause_resume_ticket_sales_controls_buy_tickets() {
     assert_eq!(client.get_raffle().status, RaffleStatus::Active);
     assert!(!client.is_ticket_sales_paused());
 
-    let config = RaffleConfig {
-        description: String::from_str(&env, "Rollback test"),
-        end_time: 0,
-        no_deadline: true,
-        max_tickets: 1,
-        max_tickets_per_tx: 1,
-        min_tickets: 1,
-        allow_multiple: true,
-        ticket_price: MIN_TICKET_PRICE,
-        payment_token: payment_token.clone(),
-        prize_amount: MIN_TICKET_PRICE,
-        prizes: soroban_sdk::vec![&env, 10000],
-        randomness_source: RandomnessSource::External,
-        oracle_address: Some(Address::generate(&env)),
-        protocol_fee_bp: 0,
-        treasury_address: None,
-        swap_router: None,
-        tikka_token: None,
-        metadata_hash: BytesN::from_array(&env, &[8; 32]),
-        claim_lockup_seconds: 0,
-        swap_deadline_seconds: 0,
-        early_bird_ticket_percentage: 0,
-        early_bird_discount_bp: 0,
-    };
-
-    client.init(&factory, &admin, &creator, &config);
+    client.pause_ticket_sales(&creator);
+    assert!(client.is_ticket_sales_paused());
+    assert_eq!(
+        client.try_buy_tickets(&buyer, &1),
+        Err(Ok(Error::ContractPaused))
+    );
 
     client.resume_ticket_sales(&creator);
     assert!(!client.is_ticket_sales_paused());
@@ -432,6 +593,7 @@ fn admin_can_pause_and_resume_ticket_sales() {
     env.ledger().set_timestamp(1_000);
 
     let (client, admin, _creator, buyer, _factory, _token_mint) = setup_active_raffle(&env);
+    set_role(&env, &client, Role::Pauser, &admin);
 
     client.pause_ticket_sales(&admin);
     assert!(client.is_ticket_sales_paused());
@@ -445,6 +607,35 @@ fn admin_can_pause_and_resume_ticket_sales() {
     assert_eq!(client.buy_tickets(&buyer, &1), 1);
 }
 
+#[test]
+fn roles_gate_privileged_calls_until_factory_clears_them() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1_000);
+
+    let (client, _admin, _creator, _buyer, _factory, _token_mint) = setup_active_raffle(&env);
+    let fee_manager = Address::generate(&env);
+
+    assert_eq!(
+        client.try_set_protocol_fee_bp(&fee_manager, &300),
+        Err(Ok(ParamChangeError::NotAuthorized))
+    );
+
+    set_role(&env, &client, Role::FeeManager, &fee_manager);
+    assert!(client.has_role(&Role::FeeManager, &fee_manager));
+    assert!(!client.has_role(&Role::Pauser, &fee_manager));
+    client.set_protocol_fee_bp(&fee_manager, &300);
+    assert_eq!(client.get_raffle().protocol_fee_bp, 300);
+
+    // A later sync with an empty member list revokes the role here too.
+    client.set_roles(&soroban_sdk::vec![&env, (Role::FeeManager, Vec::new(&env))]);
+    assert!(client.get_role_members(&Role::FeeManager).is_empty());
+    assert_eq!(
+        client.try_set_protocol_fee_bp(&fee_manager, &400),
+        Err(Ok(ParamChangeError::NotAuthorized))
+    );
+}
+
 #[test]
 fn test_wipe_storage_removes_all_keys() {
     let env = Env::default();
@@ -489,6 +680,13 @@ fn test_wipe_storage_removes_all_keys() {
         swap_deadline_seconds: 0,
         early_bird_ticket_percentage: 0,
         early_bird_discount_bp: 0,
+        prize_token: None,
+        nft_contract: None,
+        selection_algorithm: SelectionAlgorithm::Sha256CounterV2,
+        sale_gate: SaleGate::Open,
+        max_tickets_per_address: 0,
+        max_holding_bp: 0,
+        refund_target: RefundTarget::Owner,
     };
 
     client.init(&factory, &admin, &creator, &config);
@@ -496,7 +694,13 @@ fn test_wipe_storage_removes_all_keys() {
     client.buy_tickets(&buyer_a, &3);
     client.buy_tickets(&buyer_b, &2);
 
+    // #406: with tickets sold, an admin cancel is scheduled behind a
+    // timelock and only takes effect after `execute_admin_cancel`.
     client.cancel_raffle(&CancelReason::AdminCancelled);
+    assert_eq!(client.get_raffle().status, RaffleStatus::Active);
+    env.ledger()
+        .set_timestamp(1_000 + ADMIN_CANCEL_TIMELOCK_SECONDS);
+    client.execute_admin_cancel();
 
     assert_eq!(client.get_raffle().status, RaffleStatus::Cancelled);
 
@@ -523,20 +727,17 @@ fn test_wipe_storage_removes_all_keys() {
         assert!(!env.storage().instance().has(&DataKey::DrawingLock));
         assert!(!env.storage().instance().has(&DataKey::FinishTime));
         assert!(!env.storage().persistent().has(&DataKey::RandomnessSeed));
+        assert!(!env.storage().persistent().has(&DataKey::Fairness));
         assert!(!env.storage().persistent().has(&DataKey::Admin));
     });
 }
 
 #[test]
 fn emergency_withdraw_fails_before_delay_in_finalized_state() {
-fn test_refund_ticket_after_cancel() {
     let env = Env::default();
     env.mock_all_auths();
     env.ledger().set_timestamp(1_000);
 
-    let contract_id = env.register(Contract, ());
-    let client = ContractClient::new(&env, &contract_id);
-
     let factory = env.register(MockFactory, ());
     let admin = Address::generate(&env);
     let creator = Address::generate(&env);
@@ -547,8 +748,8 @@ fn test_refund_ticket_after_cancel() {
     let token_client = StellarAssetClient::new(&env, &payment_token);
     token_client.mint(&creator, &1_000_000);
 
-    let contract_id = env.register(RaffleInstance, ());
-    let client = RaffleInstanceClient::new(&env, &contract_id);
+    let contract_id = env.register(Contract, ());
+    let client = ContractClient::new(&env, &contract_id);
 
     let config = RaffleConfig {
         description: String::from_str(&env, "Test"),
@@ -570,27 +771,113 @@ fn test_refund_ticket_after_cancel() {
         tikka_token: None,
         metadata_hash: BytesN::from_array(&env, &[9; 32]),
         claim_lockup_seconds: 0,
+        swap_deadline_seconds: 0,
+        early_bird_ticket_percentage: 0,
+        early_bird_discount_bp: 0,
+        prize_token: None,
+        nft_contract: None,
+        selection_algorithm: SelectionAlgorithm::Sha256CounterV2,
+        sale_gate: SaleGate::Open,
+        max_tickets_per_address: 0,
+        max_holding_bp: 0,
+        refund_target: RefundTarget::Owner,
     };
 
     client.init(&factory, &admin, &creator, &config);
     client.deposit_prize();
     client.buy_tickets(&creator, &1);
-    client.finalize_raffle();
+    finalize_and_execute_draw(&env, &client, &creator);
 
     let result = client.try_emergency_withdraw(&creator);
     assert_eq!(result.err(), Some(Ok(Error::EmergencyTooEarly)));
 }
 
 #[test]
-fn emergency_withdraw_succeeds_after_delay_in_finalized_state() {
+fn test_refund_ticket_after_cancel() {
     let env = Env::default();
     env.mock_all_auths();
     env.ledger().set_timestamp(1_000);
 
-    let factory = Address::generate(&env);
+    let contract_id = env.register(Contract, ());
+    let client = ContractClient::new(&env, &contract_id);
+
+    let factory = env.register(MockFactory, ());
     let admin = Address::generate(&env);
     let creator = Address::generate(&env);
     let buyer = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let payment_token = env
+        .register_stellar_asset_contract_v2(token_admin.clone())
+        .address();
+    let token_client = StellarAssetClient::new(&env, &payment_token);
+    token_client.mint(&creator, &1_000_000);
+    token_client.mint(&buyer, &1_000_000);
+
+    let config = RaffleConfig {
+        description: String::from_str(&env, "Refund test"),
+        end_time: 0,
+        no_deadline: true,
+        max_tickets: 5,
+        max_tickets_per_tx: 5,
+        min_tickets: 1,
+        allow_multiple: true,
+        ticket_price: MIN_TICKET_PRICE,
+        payment_token: payment_token.clone(),
+        prize_amount: MIN_TICKET_PRICE * 5,
+        prizes: vec![&env, 10000u32],
+        randomness_source: RandomnessSource::Internal,
+        oracle_address: None,
+        protocol_fee_bp: 0,
+        treasury_address: None,
+        swap_router: None,
+        tikka_token: None,
+        metadata_hash: BytesN::from_array(&env, &[5; 32]),
+        claim_lockup_seconds: 0,
+        swap_deadline_seconds: 0,
+        early_bird_ticket_percentage: 0,
+        early_bird_discount_bp: 0,
+        prize_token: None,
+        nft_contract: None,
+        selection_algorithm: SelectionAlgorithm::Sha256CounterV2,
+        sale_gate: SaleGate::Open,
+        max_tickets_per_address: 0,
+        max_holding_bp: 0,
+        refund_target: RefundTarget::Owner,
+    };
+
+    client.init(&factory, &admin, &creator, &config);
+    env.as_contract(&contract_id, || {
+        env.storage().instance().remove(&DataKey::Factory);
+    });
+
+    client.deposit_prize();
+    client.buy_tickets(&buyer, &1);
+
+    let balance_before = soroban_sdk::token::Client::new(&env, &payment_token).balance(&buyer);
+    client.cancel_raffle(&CancelReason::CreatorCancelled);
+
+    let refunded = client.refund_ticket(&1);
+    assert_eq!(refunded, MIN_TICKET_PRICE);
+
+    let balance_after = soroban_sdk::token::Client::new(&env, &payment_token).balance(&buyer);
+    assert_eq!(balance_after, balance_before + MIN_TICKET_PRICE);
+
+    let second_refund = client.try_refund_ticket(&1);
+    assert_eq!(second_refund.err(), Some(Ok(Error::PrizeAlreadyClaimed)));
+}
+
+#[test]
+fn emergency_withdraw_succeeds_after_delay_in_finalized_state() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1_000);
+
+    let contract_id = env.register(Contract, ());
+    let client = ContractClient::new(&env, &contract_id);
+
+    let factory = env.register(MockFactory, ());
+    let admin = Address::generate(&env);
+    let creator = Address::generate(&env);
 
     let token_admin = Address::generate(&env);
     let (token_addr, token_mint) = create_token(&env, &token_admin);
@@ -605,7 +892,7 @@ fn emergency_withdraw_succeeds_after_delay_in_finalized_state() {
         min_tickets: 1,
         allow_multiple: true,
         ticket_price: MIN_TICKET_PRICE,
-        payment_token: payment_token.clone(),
+        payment_token: token_addr.clone(),
         prize_amount: MIN_TICKET_PRICE * 10,
         prizes: soroban_sdk::vec![&env, 10000],
         randomness_source: RandomnessSource::Internal,
@@ -616,12 +903,22 @@ fn emergency_withdraw_succeeds_after_delay_in_finalized_state() {
         tikka_token: None,
         metadata_hash: BytesN::from_array(&env, &[10; 32]),
         claim_lockup_seconds: 0,
+        swap_deadline_seconds: 0,
+        early_bird_ticket_percentage: 0,
+        early_bird_discount_bp: 0,
+        prize_token: None,
+        nft_contract: None,
+        selection_algorithm: SelectionAlgorithm::Sha256CounterV2,
+        sale_gate: SaleGate::Open,
+        max_tickets_per_address: 0,
+        max_holding_bp: 0,
+        refund_target: RefundTarget::Owner,
     };
 
     client.init(&factory, &admin, &creator, &config);
     client.deposit_prize();
     client.buy_tickets(&creator, &1);
-    client.finalize_raffle();
+    finalize_and_execute_draw(&env, &client, &creator);
 
     env.ledger().set_timestamp(1_000 + EMERGENCY_WITHDRAW_DELAY_SECONDS + 1);
 
@@ -643,6 +940,7 @@ fn emergency_withdraw_fails_for_no_deadline_raffle_before_timeout() {
     let factory = env.register(MockFactory, ());
     let admin = Address::generate(&env);
     let creator = Address::generate(&env);
+    let oracle = Address::generate(&env);
     let token_admin = Address::generate(&env);
     let payment_token = env
         .register_stellar_asset_contract_v2(token_admin.clone())
@@ -650,27 +948,17 @@ fn emergency_withdraw_fails_for_no_deadline_raffle_before_timeout() {
     let token_client = StellarAssetClient::new(&env, &payment_token);
     token_client.mint(&creator, &1_000_000);
 
-    let contract_id = env.register(RaffleInstance, ());
-    let client = RaffleInstanceClient::new(&env, &contract_id);
-
-    let end_time = 5_000u64;
     let config = RaffleConfig {
         description: String::from_str(&env, "Test"),
         end_time: 0,
         no_deadline: true,
         max_tickets: 1,
         max_tickets_per_tx: 1,
-        description: String::from_str(&env, "Refund test"),
-        end_time: 0,
-        no_deadline: true,
-        max_tickets: 5,
-        max_tickets_per_tx: 5,
         min_tickets: 1,
         allow_multiple: true,
         ticket_price: MIN_TICKET_PRICE,
         payment_token: payment_token.clone(),
         prize_amount: MIN_TICKET_PRICE * 10,
-        prize_amount: MIN_TICKET_PRICE * 5,
         prizes: vec![&env, 10000u32],
         randomness_source: RandomnessSource::External,
         oracle_address: Some(oracle.clone()),
@@ -680,12 +968,21 @@ fn emergency_withdraw_fails_for_no_deadline_raffle_before_timeout() {
         tikka_token: None,
         metadata_hash: BytesN::from_array(&env, &[11; 32]),
         claim_lockup_seconds: 0,
+        swap_deadline_seconds: 0,
+        early_bird_ticket_percentage: 0,
+        early_bird_discount_bp: 0,
+        prize_token: None,
+        nft_contract: None,
+        selection_algorithm: SelectionAlgorithm::Sha256CounterV2,
+        sale_gate: SaleGate::Open,
+        max_tickets_per_address: 0,
+        max_holding_bp: 0,
+        refund_target: RefundTarget::Owner,
     };
 
     client.init(&factory, &admin, &creator, &config);
     client.deposit_prize();
     client.buy_tickets(&creator, &1);
-    client.finalize_raffle();
 
     let result = client.try_emergency_withdraw(&creator);
     assert_eq!(result.err(), Some(Ok(Error::EmergencyTooEarly)));
@@ -697,7 +994,7 @@ fn emergency_withdraw_succeeds_for_no_deadline_drawing_raffle_after_timeout() {
     env.mock_all_auths();
     env.ledger().set_timestamp(1_000);
 
-    let factory = Address::generate(&env);
+    let factory = env.register(MockFactory, ());
     let admin = Address::generate(&env);
     let creator = Address::generate(&env);
     let oracle = Address::generate(&env);
@@ -713,8 +1010,8 @@ fn emergency_withdraw_succeeds_for_no_deadline_drawing_raffle_after_timeout() {
 
     let config = RaffleConfig {
         description: String::from_str(&env, "Test"),
-        end_time: 2_000,
-        no_deadline: false,
+        end_time: 0,
+        no_deadline: true,
         max_tickets: 1,
         max_tickets_per_tx: 1,
         min_tickets: 1,
@@ -731,14 +1028,26 @@ fn emergency_withdraw_succeeds_for_no_deadline_drawing_raffle_after_timeout() {
         tikka_token: None,
         metadata_hash: BytesN::from_array(&env, &[12; 32]),
         claim_lockup_seconds: 0,
+        swap_deadline_seconds: 0,
+        early_bird_ticket_percentage: 0,
+        early_bird_discount_bp: 0,
+        prize_token: None,
+        nft_contract: None,
+        selection_algorithm: SelectionAlgorithm::Sha256CounterV2,
+        sale_gate: SaleGate::Open,
+        max_tickets_per_address: 0,
+        max_holding_bp: 0,
+        refund_target: RefundTarget::Owner,
     };
 
     client.init(&factory, &admin, &creator, &config);
     client.deposit_prize();
     client.buy_tickets(&creator, &1);
-    client.finalize_raffle();
 
-    env.ledger().set_timestamp(2_000 + EMERGENCY_WITHDRAW_DELAY_SECONDS + 1);
+    let ledgers_for_delay = (EMERGENCY_WITHDRAW_DELAY_SECONDS / 5) as u32 + 1;
+    env.ledger().with_mut(|l| {
+        l.sequence_number += ledgers_for_delay;
+    });
 
     client.emergency_withdraw(&creator);
     let raffle = client.get_raffle();
@@ -752,7 +1061,7 @@ fn emergency_withdraw_fails_in_active_state() {
     env.mock_all_auths();
     env.ledger().set_timestamp(1_000);
 
-    let factory = Address::generate(&env);
+    let factory = env.register(MockFactory, ());
     let admin = Address::generate(&env);
     let creator = Address::generate(&env);
     let token_admin = Address::generate(&env);
@@ -785,6 +1094,16 @@ fn emergency_withdraw_fails_in_active_state() {
         tikka_token: None,
         metadata_hash: BytesN::from_array(&env, &[13; 32]),
         claim_lockup_seconds: 0,
+        swap_deadline_seconds: 0,
+        early_bird_ticket_percentage: 0,
+        early_bird_discount_bp: 0,
+        prize_token: None,
+        nft_contract: None,
+        selection_algorithm: SelectionAlgorithm::Sha256CounterV2,
+        sale_gate: SaleGate::Open,
+        max_tickets_per_address: 0,
+        max_holding_bp: 0,
+        refund_target: RefundTarget::Owner,
     };
 
     client.init(&factory, &admin, &creator, &config);
@@ -800,7 +1119,7 @@ fn emergency_withdraw_fails_in_cancelled_state() {
     env.mock_all_auths();
     env.ledger().set_timestamp(1_000);
 
-    let factory = Address::generate(&env);
+    let factory = env.register(MockFactory, ());
     let admin = Address::generate(&env);
     let creator = Address::generate(&env);
     let token_admin = Address::generate(&env);
@@ -810,20 +1129,8 @@ fn emergency_withdraw_fails_in_cancelled_state() {
     let token_client = StellarAssetClient::new(&env, &payment_token);
     token_client.mint(&creator, &1_000_000);
 
-fn setup_external_drawing_raffle(
-    env: &Env,
-) -> (Address, ContractClient<'_>, Address, Address, Address, u64) {
     let contract_id = env.register(Contract, ());
-    let client = ContractClient::new(env, &contract_id);
-
-    let factory = env.register(MockFactory, ());
-    let admin = Address::generate(env);
-    let creator = Address::generate(env);
-    let oracle = Address::generate(env);
-
-    let token_admin = Address::generate(env);
-    let (token_addr, token_mint) = create_token(env, &token_admin);
-    token_mint.mint(&creator, &10_000_000);
+    let client = ContractClient::new(&env, &contract_id);
 
     let config = RaffleConfig {
         description: String::from_str(&env, "Test"),
@@ -845,53 +1152,33 @@ fn setup_external_drawing_raffle(
         tikka_token: None,
         metadata_hash: BytesN::from_array(&env, &[14; 32]),
         claim_lockup_seconds: 0,
-    };
-
-    client.init(&factory, &admin, &creator, &config);
-    client.deposit_prize();
-    client.cancel(&creator, &CancelReason::Other);
-
-    let result = client.try_emergency_withdraw(&creator);
-    assert_eq!(result.err(), Some(Ok(Error::InvalidStatus)));
-}
-
-#[test]
-fn emergency_withdraw_fails_if_prize_not_deposited() {
-        metadata_hash: BytesN::from_array(&env, &[5; 32]),
-        claim_lockup_seconds: 0,
         swap_deadline_seconds: 0,
+        early_bird_ticket_percentage: 0,
+        early_bird_discount_bp: 0,
         prize_token: None,
         nft_contract: None,
+        selection_algorithm: SelectionAlgorithm::Sha256CounterV2,
+        sale_gate: SaleGate::Open,
+        max_tickets_per_address: 0,
+        max_holding_bp: 0,
+        refund_target: RefundTarget::Owner,
     };
 
     client.init(&factory, &admin, &creator, &config);
-    env.as_contract(&contract_id, || {
-        env.storage().instance().remove(&DataKey::Factory);
-    });
-
     client.deposit_prize();
-    client.buy_tickets(&buyer, &1);
-
-    let balance_before = soroban_sdk::token::Client::new(&env, &payment_token).balance(&buyer);
     client.cancel_raffle(&CancelReason::CreatorCancelled);
 
-    let refunded = client.refund_ticket(&1);
-    assert_eq!(refunded, MIN_TICKET_PRICE);
-
-    let balance_after = soroban_sdk::token::Client::new(&env, &payment_token).balance(&buyer);
-    assert_eq!(balance_after, balance_before + MIN_TICKET_PRICE);
-
-    let second_refund = client.try_refund_ticket(&1);
-    assert_eq!(second_refund.err(), Some(Ok(Error::PrizeAlreadyClaimed)));
+    let result = client.try_emergency_withdraw(&creator);
+    assert_eq!(result.err(), Some(Ok(Error::InvalidStatus)));
 }
 
 #[test]
-fn test_refund_guard_released_after_success() {
+fn emergency_withdraw_fails_if_prize_not_deposited() {
     let env = Env::default();
     env.mock_all_auths();
     env.ledger().set_timestamp(1_000);
 
-    let factory = Address::generate(&env);
+    let factory = env.register(MockFactory, ());
     let admin = Address::generate(&env);
     let creator = Address::generate(&env);
     let token_admin = Address::generate(&env);
@@ -922,6 +1209,16 @@ fn test_refund_guard_released_after_success() {
         tikka_token: None,
         metadata_hash: BytesN::from_array(&env, &[15; 32]),
         claim_lockup_seconds: 0,
+        swap_deadline_seconds: 0,
+        early_bird_ticket_percentage: 0,
+        early_bird_discount_bp: 0,
+        prize_token: None,
+        nft_contract: None,
+        selection_algorithm: SelectionAlgorithm::Sha256CounterV2,
+        sale_gate: SaleGate::Open,
+        max_tickets_per_address: 0,
+        max_holding_bp: 0,
+        refund_target: RefundTarget::Owner,
     };
 
     client.init(&factory, &admin, &creator, &config);
@@ -931,17 +1228,15 @@ fn test_refund_guard_released_after_success() {
 }
 
 #[test]
-fn emergency_withdraw_only_callable_by_creator_or_admin() {
+fn test_refund_guard_released_after_success() {
     let env = Env::default();
     env.mock_all_auths();
     env.ledger().set_timestamp(1_000);
 
-    let factory = Address::generate(&env);
+    let factory = env.register(MockFactory, ());
     let admin = Address::generate(&env);
     let creator = Address::generate(&env);
-    let stranger = Address::generate(&env);
     let buyer = Address::generate(&env);
-
     let token_admin = Address::generate(&env);
     let payment_token = env
         .register_stellar_asset_contract_v2(token_admin.clone())
@@ -954,11 +1249,6 @@ fn emergency_withdraw_only_callable_by_creator_or_admin() {
     let client = ContractClient::new(&env, &contract_id);
 
     let config = RaffleConfig {
-        description: String::from_str(&env, "Test"),
-        end_time: 2_000,
-        no_deadline: false,
-        max_tickets: 1,
-        max_tickets_per_tx: 1,
         description: String::from_str(&env, "Guard release"),
         end_time: 0,
         no_deadline: true,
@@ -968,7 +1258,6 @@ fn emergency_withdraw_only_callable_by_creator_or_admin() {
         allow_multiple: true,
         ticket_price: MIN_TICKET_PRICE,
         payment_token: payment_token.clone(),
-        prize_amount: MIN_TICKET_PRICE * 10,
         prize_amount: MIN_TICKET_PRICE * 5,
         prizes: soroban_sdk::vec![&env, 10000],
         randomness_source: RandomnessSource::Internal,
@@ -977,28 +1266,18 @@ fn emergency_withdraw_only_callable_by_creator_or_admin() {
         treasury_address: None,
         swap_router: None,
         tikka_token: None,
-        metadata_hash: BytesN::from_array(&env, &[16; 32]),
-        claim_lockup_seconds: 0,
-    };
-
-    client.init(&factory, &admin, &creator, &config);
-    client.deposit_prize();
-    client.buy_tickets(&creator, &1);
-    client.finalize_raffle();
-
-    env.ledger().set_timestamp(1_000 + EMERGENCY_WITHDRAW_DELAY_SECONDS + 1);
-
-    let stranger_result = client.try_emergency_withdraw(&stranger);
-    assert_eq!(stranger_result.err(), Some(Ok(Error::NotAuthorized)));
-
-    client.emergency_withdraw(&admin);
-}
-
-#[test]
-fn emergency_withdraw_sets_status_to_cancelled_and_clears_prize_deposited() {
         metadata_hash: BytesN::from_array(&env, &[6; 32]),
         claim_lockup_seconds: 0,
         swap_deadline_seconds: 0,
+        early_bird_ticket_percentage: 0,
+        early_bird_discount_bp: 0,
+        prize_token: None,
+        nft_contract: None,
+        selection_algorithm: SelectionAlgorithm::Sha256CounterV2,
+        sale_gate: SaleGate::Open,
+        max_tickets_per_address: 0,
+        max_holding_bp: 0,
+        refund_target: RefundTarget::Owner,
     };
 
     client.init(&factory, &admin, &creator, &config);
@@ -1024,14 +1303,15 @@ fn emergency_withdraw_sets_status_to_cancelled_and_clears_prize_deposited() {
 }
 
 #[test]
-fn test_claim_prize_pays_full_gross_with_protocol_fee() {
+fn emergency_withdraw_only_callable_by_creator_or_admin() {
     let env = Env::default();
     env.mock_all_auths();
     env.ledger().set_timestamp(1_000);
 
-    let factory = Address::generate(&env);
+    let factory = env.register(MockFactory, ());
     let admin = Address::generate(&env);
     let creator = Address::generate(&env);
+    let stranger = Address::generate(&env);
     let buyer = Address::generate(&env);
 
     let token_admin = Address::generate(&env);
@@ -1049,9 +1329,6 @@ fn test_claim_prize_pays_full_gross_with_protocol_fee() {
         description: String::from_str(&env, "Test"),
         end_time: 2_000,
         no_deadline: false,
-        description: String::from_str(&env, "Claim gross"),
-        end_time: 0,
-        no_deadline: true,
         max_tickets: 1,
         max_tickets_per_tx: 1,
         min_tickets: 1,
@@ -1066,21 +1343,91 @@ fn test_claim_prize_pays_full_gross_with_protocol_fee() {
         treasury_address: None,
         swap_router: None,
         tikka_token: None,
-        metadata_hash: BytesN::from_array(&env, &[17; 32]),
+        metadata_hash: BytesN::from_array(&env, &[16; 32]),
         claim_lockup_seconds: 0,
-        protocol_fee_bp: 1_000,
+        swap_deadline_seconds: 0,
+        early_bird_ticket_percentage: 0,
+        early_bird_discount_bp: 0,
+        prize_token: None,
+        nft_contract: None,
+        selection_algorithm: SelectionAlgorithm::Sha256CounterV2,
+        sale_gate: SaleGate::Open,
+        max_tickets_per_address: 0,
+        max_holding_bp: 0,
+        refund_target: RefundTarget::Owner,
+    };
+
+    client.init(&factory, &admin, &creator, &config);
+    client.deposit_prize();
+    client.buy_tickets(&buyer, &1);
+    finalize_and_execute_draw(&env, &client, &creator);
+
+    env.ledger().set_timestamp(1_000 + EMERGENCY_WITHDRAW_DELAY_SECONDS + 1);
+
+    let stranger_result = client.try_emergency_withdraw(&stranger);
+    assert_eq!(stranger_result.err(), Some(Ok(Error::NotAuthorized)));
+
+    // The factory admin alone no longer suffices; it needs the Guardian role.
+    assert_eq!(client.try_emergency_withdraw(&admin).err(), Some(Ok(Error::NotAuthorized)));
+    set_role(&env, &client, Role::Guardian, &admin);
+    client.emergency_withdraw(&admin);
+}
+
+#[test]
+fn emergency_withdraw_sets_status_to_cancelled_and_clears_prize_deposited() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1_000);
+
+    let factory = env.register(MockFactory, ());
+    let admin = Address::generate(&env);
+    let creator = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let payment_token = env
+        .register_stellar_asset_contract_v2(token_admin.clone())
+        .address();
+    let token_client = StellarAssetClient::new(&env, &payment_token);
+    token_client.mint(&creator, &1_000_000);
+
+    let contract_id = env.register(Contract, ());
+    let client = ContractClient::new(&env, &contract_id);
+
+    let config = RaffleConfig {
+        description: String::from_str(&env, "Test"),
+        end_time: 2_000,
+        no_deadline: false,
+        max_tickets: 1,
+        max_tickets_per_tx: 1,
+        min_tickets: 1,
+        allow_multiple: true,
+        ticket_price: MIN_TICKET_PRICE,
+        payment_token: payment_token.clone(),
+        prize_amount: MIN_TICKET_PRICE * 10,
+        prizes: soroban_sdk::vec![&env, 10000],
+        randomness_source: RandomnessSource::Internal,
+        oracle_address: None,
+        protocol_fee_bp: 0,
         treasury_address: None,
         swap_router: None,
         tikka_token: None,
-        metadata_hash: BytesN::from_array(&env, &[7; 32]),
+        metadata_hash: BytesN::from_array(&env, &[17; 32]),
         claim_lockup_seconds: 0,
         swap_deadline_seconds: 0,
+        early_bird_ticket_percentage: 0,
+        early_bird_discount_bp: 0,
+        prize_token: None,
+        nft_contract: None,
+        selection_algorithm: SelectionAlgorithm::Sha256CounterV2,
+        sale_gate: SaleGate::Open,
+        max_tickets_per_address: 0,
+        max_holding_bp: 0,
+        refund_target: RefundTarget::Owner,
     };
 
     client.init(&factory, &admin, &creator, &config);
     client.deposit_prize();
     client.buy_tickets(&creator, &1);
-    client.finalize_raffle();
+    finalize_and_execute_draw(&env, &client, &creator);
 
     let before = client.get_raffle();
     assert_eq!(before.status, RaffleStatus::Finalized);
@@ -1093,8 +1440,66 @@ fn test_claim_prize_pays_full_gross_with_protocol_fee() {
     let after = client.get_raffle();
     assert_eq!(after.status, RaffleStatus::Cancelled);
     assert!(!after.prize_deposited);
+}
+
+#[test]
+fn test_claim_prize_pays_full_gross_with_protocol_fee() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1_000);
+
+    let factory = env.register(MockFactory, ());
+    let admin = Address::generate(&env);
+    let creator = Address::generate(&env);
+    let buyer = Address::generate(&env);
+
+    let token_admin = Address::generate(&env);
+    let payment_token = env
+        .register_stellar_asset_contract_v2(token_admin.clone())
+        .address();
+    let token_client = StellarAssetClient::new(&env, &payment_token);
+    token_client.mint(&creator, &1_000_000);
+    token_client.mint(&buyer, &1_000_000);
+
+    let contract_id = env.register(Contract, ());
+    let client = ContractClient::new(&env, &contract_id);
+
+    let config = RaffleConfig {
+        description: String::from_str(&env, "Claim gross"),
+        end_time: 0,
+        no_deadline: true,
+        max_tickets: 1,
+        max_tickets_per_tx: 1,
+        min_tickets: 1,
+        allow_multiple: true,
+        ticket_price: MIN_TICKET_PRICE,
+        payment_token: payment_token.clone(),
+        prize_amount: MIN_TICKET_PRICE * 10,
+        prizes: soroban_sdk::vec![&env, 10000],
+        randomness_source: RandomnessSource::Internal,
+        oracle_address: None,
+        protocol_fee_bp: 1_000,
+        treasury_address: None,
+        swap_router: None,
+        tikka_token: None,
+        metadata_hash: BytesN::from_array(&env, &[7; 32]),
+        claim_lockup_seconds: 0,
+        swap_deadline_seconds: 0,
+        early_bird_ticket_percentage: 0,
+        early_bird_discount_bp: 0,
+        prize_token: None,
+        nft_contract: None,
+        selection_algorithm: SelectionAlgorithm::Sha256CounterV2,
+        sale_gate: SaleGate::Open,
+        max_tickets_per_address: 0,
+        max_holding_bp: 0,
+        refund_target: RefundTarget::Owner,
+    };
+
+    client.init(&factory, &admin, &creator, &config);
+    client.deposit_prize();
     client.buy_tickets(&buyer, &1);
-    client.finalize_raffle();
+    finalize_and_execute_draw(&env, &client, &creator);
 
     env.ledger().set_timestamp(1_000 + DEFAULT_CLAIM_LOCKUP_SECONDS + 1);
     let winner = client.get_raffle().winners.get(0).unwrap();
@@ -1130,7 +1535,7 @@ fn prize_distribution_invariant_holds_for_multiple_tiers() {
             env.mock_all_auths();
             env.ledger().set_timestamp(1_000);
 
-            let factory = Address::generate(&env);
+            let factory = env.register(MockFactory, ());
             let admin = Address::generate(&env);
             let creator = Address::generate(&env);
             let treasury = Address::generate(&env);
@@ -1184,6 +1589,15 @@ fn prize_distribution_invariant_holds_for_multiple_tiers() {
                 metadata_hash: BytesN::from_array(&env, &[33; 32]),
                 claim_lockup_seconds: 0,
                 swap_deadline_seconds: 0,
+                early_bird_ticket_percentage: 0,
+                early_bird_discount_bp: 0,
+                prize_token: None,
+                nft_contract: None,
+                selection_algorithm: SelectionAlgorithm::Sha256CounterV2,
+                sale_gate: SaleGate::Open,
+                max_tickets_per_address: 0,
+                max_holding_bp: 0,
+                refund_target: RefundTarget::Owner,
             };
 
             client.init(&factory, &admin, &creator, &config);
@@ -1197,7 +1611,7 @@ fn prize_distribution_invariant_holds_for_multiple_tiers() {
                 client.buy_tickets(&buyer_c, &1);
             }
 
-            client.finalize_raffle();
+            finalize_and_execute_draw(&env, &client, &creator);
 
             let token = soroban_sdk::token::Client::new(&env, &payment_token);
             let contract_balance_before_claims = token.balance(&contract_id);
@@ -1221,7 +1635,9 @@ fn prize_distribution_invariant_holds_for_multiple_tiers() {
             assert_eq!(total_claimed + fee_from_prize, prize_amount);
             assert_eq!(fee_from_prize, 0);
 
-            assert_eq!(token.balance(&treasury), expected_ticket_fees);
+            // Ticket fees wait in the raffle for the factory's sweep.
+            assert_eq!(token.balance(&treasury), 0);
+            assert_eq!(client.get_accumulated_fees(), expected_ticket_fees);
 
             let contract_balance_after_claims = token.balance(&contract_id);
             assert_eq!(
@@ -1232,13 +1648,31 @@ fn prize_distribution_invariant_holds_for_multiple_tiers() {
     }
 }
 
+/// The `DrawCommitment` that `finalize_raffle` is expected to store for
+/// `combined` commit-reveal hashes at the current ledger.
+fn expected_draw_commitment(env: &Env, client: &ContractClient<'_>, combined: Bytes) -> BytesN<32> {
+    env.crypto()
+        .sha256(
+            &(
+                client.address.clone(),
+                client.get_raffle().tickets_sold,
+                combined,
+                env.ledger().sequence(),
+                env.ledger().timestamp(),
+                env.ledger().network_id(),
+            )
+                .to_xdr(env),
+        )
+        .into()
+}
+
 #[test]
 fn commit_reveal_entropy_is_mixed_from_all_tickets() {
-    fn run_seed(commit_b: [u8; 32], metadata_byte: u8) -> u64 {
+    fn run_seed(commit_b: [u8; 32], metadata_byte: u8) -> BytesN<32> {
         let env = Env::default();
         env.mock_all_auths();
 
-        let factory = Address::generate(&env);
+        let factory = env.register(MockFactory, ());
         let admin = Address::generate(&env);
         let creator = Address::generate(&env);
         let buyer_a = Address::generate(&env);
@@ -1279,6 +1713,15 @@ fn commit_reveal_entropy_is_mixed_from_all_tickets() {
             metadata_hash: BytesN::from_array(&env, &[metadata_byte; 32]),
             claim_lockup_seconds: 0,
             swap_deadline_seconds: 0,
+            early_bird_ticket_percentage: 0,
+            early_bird_discount_bp: 0,
+            prize_token: None,
+            nft_contract: None,
+            selection_algorithm: SelectionAlgorithm::Sha256CounterV2,
+            sale_gate: SaleGate::Open,
+            max_tickets_per_address: 0,
+            max_holding_bp: 0,
+            refund_target: RefundTarget::Owner,
         };
 
         client.init(&factory, &admin, &creator, &config);
@@ -1293,21 +1736,24 @@ fn commit_reveal_entropy_is_mixed_from_all_tickets() {
         client.submit_commit(&2, &BytesN::from_array(&env, &commit_b));
         client.submit_commit(&3, &BytesN::from_array(&env, &commit_c));
 
+        // Commits feed the delayed draw instead of deciding it on the spot.
         client.finalize_raffle();
-
-        let fairness = client.get_fairness_data();
+        assert_eq!(client.get_raffle().status, RaffleStatus::Drawing);
 
         let mut combined = Bytes::new(&env);
         combined.extend_from_array(&commit_a);
         combined.extend_from_array(&commit_b);
         combined.extend_from_array(&commit_c);
-        let hash: BytesN<32> = env.crypto().sha256(&combined).into();
-        let arr = hash.to_array();
-        let expected_seed = u64::from_be_bytes([
-            arr[0], arr[1], arr[2], arr[3], arr[4], arr[5], arr[6], arr[7],
-        ]);
+        let pending = client.get_draw_commitment().unwrap();
+        assert_eq!(pending.commitment, expected_draw_commitment(&env, &client, combined));
+
+        env.ledger()
+            .with_mut(|l| l.sequence_number += RANDOMNESS_MIN_DELAY_LEDGERS);
+        client.execute_draw(&creator);
+        assert_eq!(client.get_raffle().status, RaffleStatus::Finalized);
 
-        assert_eq!(fairness.seed, expected_seed);
+        let fairness = client.get_fairness_data();
+        assert_eq!(fairness.version, FAIRNESS_VERSION_SEED256);
         fairness.seed
     }
 
@@ -1321,7 +1767,7 @@ fn commit_reveal_preserves_entropy_after_ticket_transfer() {
     let env = Env::default();
     env.mock_all_auths();
 
-    let factory = Address::generate(&env);
+    let factory = env.register(MockFactory, ());
     let admin = Address::generate(&env);
     let creator = Address::generate(&env);
     let buyer_a = Address::generate(&env);
@@ -1360,6 +1806,15 @@ fn commit_reveal_preserves_entropy_after_ticket_transfer() {
         metadata_hash: BytesN::from_array(&env, &[46; 32]),
         claim_lockup_seconds: 0,
         swap_deadline_seconds: 0,
+        early_bird_ticket_percentage: 0,
+        early_bird_discount_bp: 0,
+        prize_token: None,
+        nft_contract: None,
+        selection_algorithm: SelectionAlgorithm::Sha256CounterV2,
+        sale_gate: SaleGate::Open,
+        max_tickets_per_address: 0,
+        max_holding_bp: 0,
+        refund_target: RefundTarget::Owner,
     };
 
     client.init(&factory, &admin, &creator, &config);
@@ -1381,21 +1836,17 @@ fn commit_reveal_preserves_entropy_after_ticket_transfer() {
     });
 
     client.finalize_raffle();
-    let fairness = client.get_fairness_data();
 
     let mut combined = Bytes::new(&env);
     combined.extend_from_array(&commit);
-    let hash: BytesN<32> = env.crypto().sha256(&combined).into();
-    let arr = hash.to_array();
-    let expected_seed = u64::from_be_bytes([
-        arr[0], arr[1], arr[2], arr[3], arr[4], arr[5], arr[6], arr[7],
-    ]);
-
-    assert_eq!(fairness.seed, expected_seed);
-}
+    assert_eq!(
+        client.get_draw_commitment().unwrap().commitment,
+        expected_draw_commitment(&env, &client, combined)
+    );
+}
 
 #[test]
-fn commit_reveal_with_zero_commits_falls_back_to_prng() {
+fn commit_reveal_with_zero_commits_uses_delayed_draw() {
     let env = Env::default();
     env.mock_all_auths();
     env.ledger().set_timestamp(7_777);
@@ -1403,7 +1854,7 @@ fn commit_reveal_with_zero_commits_falls_back_to_prng() {
         l.sequence_number = 999;
     });
 
-    let factory = Address::generate(&env);
+    let factory = env.register(MockFactory, ());
     let admin = Address::generate(&env);
     let creator = Address::generate(&env);
     let buyer_a = Address::generate(&env);
@@ -1442,6 +1893,15 @@ fn commit_reveal_with_zero_commits_falls_back_to_prng() {
         metadata_hash: BytesN::from_array(&env, &[47; 32]),
         claim_lockup_seconds: 0,
         swap_deadline_seconds: 0,
+        early_bird_ticket_percentage: 0,
+        early_bird_discount_bp: 0,
+        prize_token: None,
+        nft_contract: None,
+        selection_algorithm: SelectionAlgorithm::Sha256CounterV2,
+        sale_gate: SaleGate::Open,
+        max_tickets_per_address: 0,
+        max_holding_bp: 0,
+        refund_target: RefundTarget::Owner,
     };
 
     client.init(&factory, &admin, &creator, &config);
@@ -1450,24 +1910,21 @@ fn commit_reveal_with_zero_commits_falls_back_to_prng() {
     client.buy_tickets(&buyer_b, &1);
     client.finalize_raffle();
 
+    // With no commits the draw still takes the delayed internal path.
+    assert_eq!(client.get_raffle().status, RaffleStatus::Drawing);
+    let pending = client.get_draw_commitment().unwrap();
+    assert_eq!(
+        pending.target_ledger,
+        pending.commit_ledger + RANDOMNESS_MIN_DELAY_LEDGERS
+    );
+
+    env.ledger()
+        .with_mut(|l| l.sequence_number += RANDOMNESS_MIN_DELAY_LEDGERS);
+    client.execute_draw(&creator);
+
     let raffle = client.get_raffle();
     assert_eq!(raffle.status, RaffleStatus::Finalized);
-
-    let fairness = client.get_fairness_data();
-    let expected_seed = env.as_contract(&contract_id, || {
-        let payload = (
-            env.ledger().timestamp(),
-            env.ledger().sequence(),
-            env.current_contract_address().to_xdr(&env),
-        )
-            .to_xdr(&env);
-        let hash: BytesN<32> = env.crypto().sha256(&payload).into();
-        let arr = hash.to_array();
-        u64::from_be_bytes([
-            arr[0], arr[1], arr[2], arr[3], arr[4], arr[5], arr[6], arr[7],
-        ])
-    });
-    assert_eq!(fairness.seed, expected_seed);
+    assert!(client.get_draw_commitment().is_none());
     assert_eq!(raffle.winners.len(), 2);
 }
 
@@ -1476,7 +1933,7 @@ fn drawing_lock_cleared_after_internal_finalize() {
     let env = Env::default();
     env.mock_all_auths();
 
-    let factory = Address::generate(&env);
+    let factory = env.register(MockFactory, ());
     let admin = Address::generate(&env);
     let creator = Address::generate(&env);
 
@@ -1511,13 +1968,21 @@ fn drawing_lock_cleared_after_internal_finalize() {
         metadata_hash: BytesN::from_array(&env, &[48; 32]),
         claim_lockup_seconds: 0,
         swap_deadline_seconds: 0,
-        bundles: soroban_sdk::vec![&env],
+        early_bird_ticket_percentage: 0,
+        early_bird_discount_bp: 0,
+        prize_token: None,
+        nft_contract: None,
+        selection_algorithm: SelectionAlgorithm::Sha256CounterV2,
+        sale_gate: SaleGate::Open,
+        max_tickets_per_address: 0,
+        max_holding_bp: 0,
+        refund_target: RefundTarget::Owner,
     };
 
     client.init(&factory, &admin, &creator, &config);
     client.deposit_prize();
     client.buy_tickets(&creator, &1);
-    client.finalize_raffle();
+    finalize_and_execute_draw(&env, &client, &creator);
 
     assert_drawing_lock_cleared(&env, &contract_id);
 }
@@ -1527,7 +1992,7 @@ fn drawing_lock_cleared_after_oracle_randomness() {
     let env = Env::default();
     env.mock_all_auths();
 
-    let factory = Address::generate(&env);
+    let factory = env.register(MockFactory, ());
     let admin = Address::generate(&env);
     let creator = Address::generate(&env);
     let oracle = Address::generate(&env);
@@ -1563,6 +2028,15 @@ fn drawing_lock_cleared_after_oracle_randomness() {
         metadata_hash: BytesN::from_array(&env, &[49; 32]),
         claim_lockup_seconds: 0,
         swap_deadline_seconds: 0,
+        early_bird_ticket_percentage: 0,
+        early_bird_discount_bp: 0,
+        prize_token: None,
+        nft_contract: None,
+        selection_algorithm: SelectionAlgorithm::Sha256CounterV2,
+        sale_gate: SaleGate::Open,
+        max_tickets_per_address: 0,
+        max_holding_bp: 0,
+        refund_target: RefundTarget::Owner,
     };
 
     client.init(&factory, &admin, &creator, &config);
@@ -1576,15 +2050,19 @@ fn drawing_lock_cleared_after_oracle_randomness() {
             .unwrap()
     });
 
+    let random_seed = BytesN::from_array(&env, &[0x42; 32]);
     let signing_key = SigningKey::from_bytes(&[5u8; 32]);
     let verifying = signing_key.verifying_key();
     let message = env.as_contract(&contract_id, || {
-        build_vrf_proof_message(&env, request_id, 424242)
+        build_vrf_proof_message(&env, request_id, &random_seed)
     });
-    let signature = signing_key.sign(message.as_slice());
+    let mut msg = [0u8; 256];
+    let msg_len = message.len() as usize;
+    message.copy_into_slice(&mut msg[..msg_len]);
+    let signature = signing_key.sign(&msg[..msg_len]);
 
     client.provide_randomness(
-        &424242,
+        &random_seed,
         &BytesN::from_array(&env, &verifying.to_bytes()),
         &BytesN::from_array(&env, &signature.to_bytes()),
         &request_id,
@@ -1598,7 +2076,7 @@ fn drawing_lock_cleared_after_fallback_refund() {
     let env = Env::default();
     env.mock_all_auths();
 
-    let factory = Address::generate(&env);
+    let factory = env.register(MockFactory, ());
     let admin = Address::generate(&env);
     let creator = Address::generate(&env);
     let oracle = Address::generate(&env);
@@ -1634,6 +2112,15 @@ fn drawing_lock_cleared_after_fallback_refund() {
         metadata_hash: BytesN::from_array(&env, &[50; 32]),
         claim_lockup_seconds: 0,
         swap_deadline_seconds: 0,
+        early_bird_ticket_percentage: 0,
+        early_bird_discount_bp: 0,
+        prize_token: None,
+        nft_contract: None,
+        selection_algorithm: SelectionAlgorithm::Sha256CounterV2,
+        sale_gate: SaleGate::Open,
+        max_tickets_per_address: 0,
+        max_holding_bp: 0,
+        refund_target: RefundTarget::Owner,
     };
 
     client.init(&factory, &admin, &creator, &config);
@@ -1653,7 +2140,7 @@ fn drawing_lock_cleared_after_fallback_no_refund() {
     let env = Env::default();
     env.mock_all_auths();
 
-    let factory = Address::generate(&env);
+    let factory = env.register(MockFactory, ());
     let admin = Address::generate(&env);
     let creator = Address::generate(&env);
     let oracle = Address::generate(&env);
@@ -1689,6 +2176,15 @@ fn drawing_lock_cleared_after_fallback_no_refund() {
         metadata_hash: BytesN::from_array(&env, &[51; 32]),
         claim_lockup_seconds: 0,
         swap_deadline_seconds: 0,
+        early_bird_ticket_percentage: 0,
+        early_bird_discount_bp: 0,
+        prize_token: None,
+        nft_contract: None,
+        selection_algorithm: SelectionAlgorithm::Sha256CounterV2,
+        sale_gate: SaleGate::Open,
+        max_tickets_per_address: 0,
+        max_holding_bp: 0,
+        refund_target: RefundTarget::Owner,
     };
 
     client.init(&factory, &admin, &creator, &config);
@@ -1708,7 +2204,7 @@ fn drawing_lock_cleared_after_cancel_in_drawing_state() {
     let env = Env::default();
     env.mock_all_auths();
 
-    let factory = Address::generate(&env);
+    let factory = env.register(MockFactory, ());
     let admin = Address::generate(&env);
     let creator = Address::generate(&env);
     let oracle = Address::generate(&env);
@@ -1744,6 +2240,15 @@ fn drawing_lock_cleared_after_cancel_in_drawing_state() {
         metadata_hash: BytesN::from_array(&env, &[52; 32]),
         claim_lockup_seconds: 0,
         swap_deadline_seconds: 0,
+        early_bird_ticket_percentage: 0,
+        early_bird_discount_bp: 0,
+        prize_token: None,
+        nft_contract: None,
+        selection_algorithm: SelectionAlgorithm::Sha256CounterV2,
+        sale_gate: SaleGate::Open,
+        max_tickets_per_address: 0,
+        max_holding_bp: 0,
+        refund_target: RefundTarget::Owner,
     };
 
     client.init(&factory, &admin, &creator, &config);
@@ -1754,66 +2259,2061 @@ fn drawing_lock_cleared_after_cancel_in_drawing_state() {
     assert_drawing_lock_cleared(&env, &contract_id);
 }
 
+fn make_token(env: &Env) -> (token::Client<'_>, Address) {
+    let admin = Address::generate(env);
+    let contract = env.register_stellar_asset_contract_v2(admin.clone());
+    (token::Client::new(env, &contract.address()), admin)
+}
+
+fn mint(env: &Env, _token_admin: &Address, token_addr: &Address, to: &Address, amount: i128) {
+    token::StellarAssetClient::new(env, token_addr).mint(to, &amount);
+}
+
+fn default_config(env: &Env, payment_token: Address) -> RaffleConfig {
+    let mut prizes = Vec::new(env);
+    prizes.push_back(10_000u32); // 100 % to single winner
+    RaffleConfig {
+        description: String::from_str(env, "Test raffle"),
+        end_time: 0,
+        no_deadline: true,
+        max_tickets: 1_000,
+        max_tickets_per_tx: 100,
+        min_tickets: 1,
+        allow_multiple: true,
+        ticket_price: 100_000i128,
+        payment_token,
+        prize_amount: 100_000i128,
+        prizes,
+        randomness_source: RandomnessSource::Internal,
+        oracle_address: None,
+        protocol_fee_bp: 0,
+        treasury_address: None,
+        swap_router: None,
+        tikka_token: None,
+        metadata_hash: BytesN::from_array(env, &[1u8; 32]),
+        claim_lockup_seconds: 0,
+        swap_deadline_seconds: 0,
+        early_bird_ticket_percentage: 0,
+        early_bird_discount_bp: 0,
+        prize_token: None,
+        nft_contract: None,
+        selection_algorithm: SelectionAlgorithm::Sha256CounterV2,
+        sale_gate: SaleGate::Open,
+        max_tickets_per_address: 0,
+        max_holding_bp: 0,
+        refund_target: RefundTarget::Owner,
+    }
+}
+
+/// Register a raffle-instance, call `init`, deposit prize, and return the
+/// client plus relevant addresses.
+fn setup_raffle(env: &Env) -> (ContractClient<'_>, Address, Address, token::Client<'_>) {
+    let factory = env.register(MockFactory, ());
+    let admin = Address::generate(env);
+    let creator = Address::generate(env);
+
+    let (token, token_admin) = make_token(env);
+    // Give creator enough to cover prize deposit.
+    mint(env, &token_admin, &token.address, &creator, 10_000_000);
+
+    let contract_id = env.register(Contract, ());
+    let client = ContractClient::new(env, &contract_id);
+
+    client.init(
+        &factory,
+        &admin,
+        &creator,
+        &default_config(env, token.address.clone()),
+    );
+    client.deposit_prize();
+
+    // Return token_admin as the 3rd element (the caller can mint with it).
+    (client, creator, token_admin, token)
+}
+
+// ── tests ─────────────────────────────────────────────────────────────────
+
+/// Core acceptance-criteria test:
+///   10 tickets purchased, 5 individually refunded first,
+///   batch_refund_tickets called with all 10 IDs →
+///   only the remaining 5 are processed.
 #[test]
-fn test_bundle_pricing_applies() {
+fn test_batch_refund_skips_already_refunded() {
     let env = Env::default();
     env.mock_all_auths();
 
-    let factory = Address::generate(&env);
-    let admin = Address::generate(&env);
-    let creator = Address::generate(&env);
+    let (client, _creator, token_admin, token) = setup_raffle(&env);
     let buyer = Address::generate(&env);
+    mint(&env, &token_admin, &token.address, &buyer, 100_000 * 10 * 2);
+
+    // Buy 10 tickets.
+    let before = client.get_raffle().tickets_sold;
+    client.buy_tickets(&buyer, &10u32);
+    let mut ticket_ids: Vec<u32> = Vec::new(&env);
+    for id in (before + 1)..=(before + 10) {
+        ticket_ids.push_back(id);
+    }
 
-    let token_admin = Address::generate(&env);
-    let payment_token = env
-        .register_stellar_asset_contract_v2(token_admin.clone())
-        .address();
-    let token_client = StellarAssetClient::new(&env, &payment_token);
-    token_client.mint(&creator, &100_000_000);
-    token_client.mint(&buyer, &100_000_000);
+    // Cancel.
+    client.cancel_raffle(&CancelReason::CreatorCancelled);
+
+    // Pre-refund tickets 0..5 (the first 5).
+    for i in 0..5u32 {
+        client.refund_ticket(&ticket_ids.get(i).unwrap());
+    }
+
+    let balance_before = token.balance(&buyer);
+
+    // Batch-refund all 10 — only the last 5 should go through.
+    let total = client.batch_refund_tickets(&buyer, &ticket_ids);
+
+    assert_eq!(
+        total,
+        5 * 100_000i128,
+        "Only 5 unrefunded tickets should be processed"
+    );
+    assert_eq!(
+        token.balance(&buyer) - balance_before,
+        5 * 100_000i128,
+        "Balance should increase by exactly 5 * ticket_price"
+    );
+}
+
+/// A caller who is not the ticket owner must be rejected with NotAuthorized.
+#[test]
+fn test_batch_refund_rejects_wrong_owner() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, _creator, token_admin, token) = setup_raffle(&env);
+    let buyer = Address::generate(&env);
+    let attacker = Address::generate(&env);
+
+    mint(&env, &token_admin, &token.address, &buyer, 100_000 * 3 * 2);
+    let before = client.get_raffle().tickets_sold;
+    client.buy_tickets(&buyer, &3u32);
+    let mut ids: Vec<u32> = Vec::new(&env);
+    for id in (before + 1)..=(before + 3) {
+        ids.push_back(id);
+    }
+
+    client.cancel_raffle(&CancelReason::CreatorCancelled);
+
+    // Attacker tries to claim buyer's refund — must fail.
+    let result = client.try_batch_refund_tickets(&attacker, &ids);
+    assert!(result.is_err(), "Should have rejected wrong owner");
+}
+
+/// Calling batch_refund_tickets while the raffle is Active must fail.
+#[test]
+fn test_batch_refund_rejects_active_raffle() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, _creator, token_admin, token) = setup_raffle(&env);
+    let buyer = Address::generate(&env);
+
+    mint(&env, &token_admin, &token.address, &buyer, 100_000 * 2 * 2);
+    let before = client.get_raffle().tickets_sold;
+    client.buy_tickets(&buyer, &2u32);
+    let mut ids: Vec<u32> = Vec::new(&env);
+    for id in (before + 1)..=(before + 2) {
+        ids.push_back(id);
+    }
+
+    // Do NOT cancel — raffle remains Active.
+    let result = client.try_batch_refund_tickets(&buyer, &ids);
+    assert!(
+        result.is_err(),
+        "Must reject when raffle is not cancelled/failed"
+    );
+}
+
+/// Passing more than 50 ticket IDs must return InvalidParameters.
+#[test]
+fn test_batch_refund_rejects_over_cap() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, _creator, _token_admin, _token) = setup_raffle(&env);
+    let buyer = Address::generate(&env);
+
+    client.cancel_raffle(&CancelReason::CreatorCancelled);
+
+    // Build a Vec of 51 dummy IDs.
+    let mut big_ids: Vec<u32> = Vec::new(&env);
+    for i in 1u32..=51 {
+        big_ids.push_back(i);
+    }
+
+    let result = client.try_batch_refund_tickets(&buyer, &big_ids);
+    assert!(result.is_err(), "Should reject more than 50 ticket IDs");
+}
+
+/// Calling batch_refund_tickets twice with the same IDs must be a no-op
+/// on the second call (total refund returns 0, no double-spend).
+#[test]
+fn test_batch_refund_idempotent() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, _creator, token_admin, token) = setup_raffle(&env);
+    let buyer = Address::generate(&env);
+
+    mint(&env, &token_admin, &token.address, &buyer, 100_000 * 5 * 2);
+    let before = client.get_raffle().tickets_sold;
+    client.buy_tickets(&buyer, &5u32);
+    let mut ids: Vec<u32> = Vec::new(&env);
+    for id in (before + 1)..=(before + 5) {
+        ids.push_back(id);
+    }
+
+    client.cancel_raffle(&CancelReason::CreatorCancelled);
+
+    // First call — all 5 processed.
+    let first = client.batch_refund_tickets(&buyer, &ids);
+    assert_eq!(first, 5 * 100_000i128);
+
+    // Second call — all already refunded, should return 0.
+    let second = client.batch_refund_tickets(&buyer, &ids);
+    assert_eq!(second, 0i128, "Second call must be a no-op");
+}
+
+/// #406: Admin cancel of a raffle with sold tickets must be timelocked.
+/// Scheduling, then executing within the window, must fail.
+#[test]
+fn admin_cancel_with_sold_tickets_requires_timelock() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1_000);
+
+    let (client, _admin, _creator, buyer, _factory, _token_mint) = setup_active_raffle(&env);
+    client.buy_tickets(&buyer, &3);
+    assert_eq!(client.get_raffle().tickets_sold, 3);
+
+    // No cancel is scheduled yet.
+    assert!(client.get_pending_cancel().is_none());
+
+    // Admin cancel only *schedules*: status and prize stay untouched, and
+    // exactly one event (CancelScheduled) is emitted.
+    let events_before = env.events().all().len();
+    client.cancel_raffle(&raffle_shared::CancelReason::AdminCancelled);
+    assert_eq!(env.events().all().len() - events_before, 1);
+
+    assert_eq!(client.get_raffle().status, RaffleStatus::Active);
+    assert!(client.get_raffle().prize_deposited);
+
+    let cancel_at = client.get_pending_cancel().expect("cancel scheduled");
+    assert_eq!(cancel_at, 1_000 + ADMIN_CANCEL_TIMELOCK_SECONDS);
+
+    // Executing immediately fails — the timelock is still active.
+    assert_eq!(
+        client.try_execute_admin_cancel(),
+        Err(Ok(CancelError::CancelTimelockActive))
+    );
+
+    // One second before the deadline still fails.
+    env.ledger().set_timestamp(cancel_at - 1);
+    assert_eq!(
+        client.try_execute_admin_cancel(),
+        Err(Ok(CancelError::CancelTimelockActive))
+    );
+
+    // At/after the deadline the cancel finally executes.
+    env.ledger().set_timestamp(cancel_at);
+    client.execute_admin_cancel();
+    assert_eq!(client.get_raffle().status, RaffleStatus::Cancelled);
+    assert!(client.get_pending_cancel().is_none());
+}
+
+/// #406: `execute_admin_cancel` with no scheduled cancel must fail.
+#[test]
+fn execute_admin_cancel_without_schedule_fails() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1_000);
+
+    let (client, _admin, _creator, buyer, _factory, _token_mint) = setup_active_raffle(&env);
+    client.buy_tickets(&buyer, &1);
+
+    assert_eq!(
+        client.try_execute_admin_cancel(),
+        Err(Ok(CancelError::CancelNotScheduled))
+    );
+}
+
+/// #406: An admin cancel of a raffle with *zero* tickets sold takes effect
+/// immediately — no timelock is needed because there are no buyers to rug.
+#[test]
+fn admin_cancel_with_no_tickets_is_immediate() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1_000);
+
+    let (client, _admin, _creator, _buyer, _factory, _token_mint) = setup_active_raffle(&env);
+
+    client.cancel_raffle(&raffle_shared::CancelReason::AdminCancelled);
+    assert_eq!(client.get_raffle().status, RaffleStatus::Cancelled);
+    assert!(client.get_pending_cancel().is_none());
+}
+
+/// #406: Ticket holders can refund immediately once a cancel is scheduled,
+/// without waiting for the timelock to execute.
+#[test]
+fn ticket_holders_refund_immediately_after_cancel_scheduled() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1_000);
+
+    let (client, _admin, _creator, buyer, _factory, _token_mint) = setup_active_raffle(&env);
+    client.buy_tickets(&buyer, &2);
+
+    // While the raffle is Active and no cancel is scheduled, refunds are
+    // rejected.
+    assert_eq!(
+        client.try_refund_ticket(&1),
+        Err(Ok(Error::InvalidStatus))
+    );
+
+    // Schedule the admin cancel. The raffle is still Active...
+    client.cancel_raffle(&raffle_shared::CancelReason::AdminCancelled);
+    assert_eq!(client.get_raffle().status, RaffleStatus::Active);
+
+    // ...yet ticket holders can refund right away, before the timelock.
+    assert_eq!(client.refund_ticket(&1), MIN_TICKET_PRICE);
+    assert_eq!(client.refund_ticket(&2), MIN_TICKET_PRICE);
+}
+
+#[test]
+fn emergency_withdraw_no_deadline_drawing_respects_delay() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1_000);
 
     let contract_id = env.register(Contract, ());
     let client = ContractClient::new(&env, &contract_id);
 
+    let factory = env.register(MockFactory, ());
+    let admin = Address::generate(&env);
+    let creator = Address::generate(&env);
+    let oracle = Address::generate(&env);
+
+    let token_admin = Address::generate(&env);
+    let (token_addr, token_mint) = create_token(&env, &token_admin);
+    token_mint.mint(&creator, &10_000_000);
+
     let config = RaffleConfig {
-        description: String::from_str(&env, "Bundle test"),
+        description: String::from_str(&env, "no-deadline drawing"),
         end_time: 0,
         no_deadline: true,
-        max_tickets: 50,
-        max_tickets_per_tx: 50,
+        max_tickets: 5,
+        max_tickets_per_tx: 5,
         min_tickets: 1,
         allow_multiple: true,
-        ticket_price: 100_000,
-        payment_token: payment_token.clone(),
-        prize_amount: 100_000 * 50,
-        prizes: soroban_sdk::vec![&env, 10000],
-        randomness_source: RandomnessSource::Internal,
-        oracle_address: None,
+        ticket_price: MIN_TICKET_PRICE,
+        payment_token: token_addr.clone(),
+        prize_amount: MIN_TICKET_PRICE * 5,
+        prizes: vec![&env, 10000u32],
+        randomness_source: RandomnessSource::External,
+        oracle_address: Some(oracle.clone()),
         protocol_fee_bp: 0,
         treasury_address: None,
         swap_router: None,
         tikka_token: None,
-        metadata_hash: BytesN::from_array(&env, &[9; 32]),
+        metadata_hash: BytesN::from_array(&env, &[3u8; 32]),
         claim_lockup_seconds: 0,
         swap_deadline_seconds: 0,
-        bundles: soroban_sdk::vec![
-            &env,
-            raffle_shared::TicketBundle { quantity: 5, price_per_ticket: 90_000 },
-            raffle_shared::TicketBundle { quantity: 10, price_per_ticket: 80_000 },
-            raffle_shared::TicketBundle { quantity: 20, price_per_ticket: 70_000 },
-        ],
+        prize_token: None,
+        nft_contract: None,
+        early_bird_ticket_percentage: 0,
+        early_bird_discount_bp: 0,
+        selection_algorithm: SelectionAlgorithm::Sha256CounterV2,
+        sale_gate: SaleGate::Open,
+        max_tickets_per_address: 0,
+        max_holding_bp: 0,
+        refund_target: RefundTarget::Owner,
     };
 
     client.init(&factory, &admin, &creator, &config);
-    env.as_contract(&contract_id, || {
-        env.storage().instance().remove(&DataKey::Factory);
+    client.deposit_prize();
+    client.buy_tickets(&creator, &5);
+
+    let raffle = client.get_raffle();
+    assert_eq!(raffle.status, RaffleStatus::Drawing);
+    assert!(raffle.no_deadline);
+
+    let too_early = client.try_emergency_withdraw(&creator);
+    assert_eq!(too_early.err(), Some(Ok(Error::EmergencyTooEarly)));
+
+    let ledgers_for_delay = (EMERGENCY_WITHDRAW_DELAY_SECONDS / 5) as u32 + 1;
+    env.ledger().with_mut(|l| {
+        l.sequence_number += ledgers_for_delay;
     });
 
-    client.deposit_prize();
+    client.emergency_withdraw(&creator);
+
+    let after = client.get_raffle();
+    assert_eq!(after.status, RaffleStatus::Cancelled);
+    assert!(!after.prize_deposited);
+}
 
-    let balance_before = token_client.balance(&buyer);
-    client.buy_tickets(&buyer, &11);
-    let balance_after = token_client.balance(&buyer);
+#[test]
+fn emergency_withdraw_deadline_drawing_respects_end_time_delay() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1_000);
 
-    assert_eq!(balance_before - balance_after, 11 * 80_000);
+    let contract_id = env.register(Contract, ());
+    let client = ContractClient::new(&env, &contract_id);
+
+    let factory = env.register(MockFactory, ());
+    let admin = Address::generate(&env);
+    let creator = Address::generate(&env);
+    let oracle = Address::generate(&env);
+
+    let token_admin = Address::generate(&env);
+    let (token_addr, token_mint) = create_token(&env, &token_admin);
+    token_mint.mint(&creator, &10_000_000);
+
+    let end_time = 5_000u64;
+    let config = RaffleConfig {
+        description: String::from_str(&env, "deadline drawing"),
+        end_time,
+        no_deadline: false,
+        max_tickets: 5,
+        max_tickets_per_tx: 5,
+        min_tickets: 1,
+        allow_multiple: true,
+        ticket_price: MIN_TICKET_PRICE,
+        payment_token: token_addr.clone(),
+        prize_amount: MIN_TICKET_PRICE * 5,
+        prizes: vec![&env, 10000u32],
+        randomness_source: RandomnessSource::External,
+        oracle_address: Some(oracle.clone()),
+        protocol_fee_bp: 0,
+        treasury_address: None,
+        swap_router: None,
+        tikka_token: None,
+        metadata_hash: BytesN::from_array(&env, &[4u8; 32]),
+        claim_lockup_seconds: 0,
+        swap_deadline_seconds: 0,
+        prize_token: None,
+        nft_contract: None,
+        early_bird_ticket_percentage: 0,
+        early_bird_discount_bp: 0,
+        selection_algorithm: SelectionAlgorithm::Sha256CounterV2,
+        sale_gate: SaleGate::Open,
+        max_tickets_per_address: 0,
+        max_holding_bp: 0,
+        refund_target: RefundTarget::Owner,
+    };
+
+    client.init(&factory, &admin, &creator, &config);
+    client.deposit_prize();
+    client.buy_tickets(&creator, &3);
+    env.ledger().set_timestamp(end_time);
+    client.finalize_raffle();
+
+    let raffle = client.get_raffle();
+    assert_eq!(raffle.status, RaffleStatus::Drawing);
+    assert!(!raffle.no_deadline);
+
+    let too_early = client.try_emergency_withdraw(&creator);
+    assert_eq!(too_early.err(), Some(Ok(Error::EmergencyTooEarly)));
+
+    env.ledger()
+        .set_timestamp(end_time + EMERGENCY_WITHDRAW_DELAY_SECONDS + 1);
+    client.emergency_withdraw(&creator);
+
+    let after = client.get_raffle();
+    assert_eq!(after.status, RaffleStatus::Cancelled);
+}
+
+fn setup_external_drawing_raffle(
+    env: &Env,
+) -> (Address, ContractClient<'_>, Address, Address, Address, u64) {
+    let contract_id = env.register(Contract, ());
+    let client = ContractClient::new(env, &contract_id);
+
+    let factory = env.register(MockFactory, ());
+    let admin = Address::generate(env);
+    let creator = Address::generate(env);
+    let oracle = Address::generate(env);
+
+    let token_admin = Address::generate(env);
+    let (token_addr, token_mint) = create_token(env, &token_admin);
+    token_mint.mint(&creator, &10_000_000);
+
+    let config = RaffleConfig {
+        description: String::from_str(env, "vrf proof test"),
+        end_time: 0,
+        no_deadline: true,
+        max_tickets: 3,
+        max_tickets_per_tx: 3,
+        min_tickets: 1,
+        allow_multiple: true,
+        ticket_price: MIN_TICKET_PRICE,
+        payment_token: token_addr,
+        prize_amount: MIN_TICKET_PRICE * 3,
+        prizes: vec![env, 10000u32],
+        randomness_source: RandomnessSource::External,
+        oracle_address: Some(oracle.clone()),
+        protocol_fee_bp: 0,
+        treasury_address: None,
+        swap_router: None,
+        tikka_token: None,
+        metadata_hash: BytesN::from_array(env, &[5u8; 32]),
+        claim_lockup_seconds: 0,
+        swap_deadline_seconds: 0,
+        prize_token: None,
+        nft_contract: None,
+        early_bird_ticket_percentage: 0,
+        early_bird_discount_bp: 0,
+        selection_algorithm: SelectionAlgorithm::Sha256CounterV2,
+        sale_gate: SaleGate::Open,
+        max_tickets_per_address: 0,
+        max_holding_bp: 0,
+        refund_target: RefundTarget::Owner,
+    };
+
+    client.init(&factory, &admin, &creator, &config);
+    client.deposit_prize();
+    client.buy_tickets(&creator, &3);
+
+    let request_id: u64 = env.as_contract(&contract_id, || {
+        env.storage()
+            .instance()
+            .get(&DataKey::RandomnessRequestId)
+            .unwrap()
+    });
+
+    (contract_id, client, creator, oracle, admin, request_id)
+}
+
+#[test]
+fn vrf_proof_valid_for_target_raffle_only() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1_000);
+
+    let signing_key = SigningKey::from_bytes(&[9u8; 32]);
+    let public_key = BytesN::from_array(&env, &signing_key.verifying_key().to_bytes());
+
+    let (contract_a, client_a, _creator_a, _oracle_a, _admin_a, request_id_a) =
+        setup_external_drawing_raffle(&env);
+    let (_contract_b, client_b, _creator_b, _oracle_b, _admin_b, request_id_b) =
+        setup_external_drawing_raffle(&env);
+
+    let random_seed = BytesN::from_array(&env, &[0xDE; 32]);
+
+    let message_a = env.as_contract(&contract_a, || {
+        build_vrf_proof_message(&env, request_id_a, &random_seed)
+    });
+    let mut msg_a = [0u8; 256];
+    let msg_len = message_a.len() as usize;
+    for (idx, byte) in message_a.iter().enumerate() {
+        msg_a[idx] = byte;
+    }
+    let proof_a = BytesN::from_array(&env, &signing_key.sign(&msg_a[..msg_len]).to_bytes());
+
+    client_a.provide_randomness(&random_seed, &public_key, &proof_a, &request_id_a);
+    assert_eq!(client_a.get_raffle().status, RaffleStatus::Finalized);
+
+    let replay =
+        client_b.try_provide_randomness(&random_seed, &public_key, &proof_a, &request_id_b);
+    assert!(replay.is_err());
+}
+/// Format regression guard for [`FairnessData`] returned by [`Contract::get_fairness_data`].
+///
+/// Off-chain verifiers and frontends depend on the exact field names, types, and values
+/// produced after a draw. If this test breaks, you are changing the public API — update
+/// the changelog before updating the expected snapshot constants below.
+#[test]
+fn fairness_data_format_regression() {
+    const EXPECTED_SEED: [u8; 32] = crate::randomness::widen_legacy_seed(12_345);
+    const EXPECTED_TS: u64 = 1_700_000_000;
+    const EXPECTED_SEQ: u32 = 42;
+    const EXPECTED_TICKET_IDS: [u32; 5] = [1, 2, 3, 4, 5];
+    const EXPECTED_WINNING_INDICES: [u32; 2] = [0, 3];
+
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(EXPECTED_TS);
+    env.ledger().with_mut(|l| l.sequence_number = EXPECTED_SEQ);
+
+    let contract_id = env.register(Contract, ());
+    let client = ContractClient::new(&env, &contract_id);
+
+    let factory = env.register(MockFactory, ());
+    let admin = Address::generate(&env);
+    let creator = Address::generate(&env);
+    let buyer = Address::generate(&env);
+
+    let token_admin = Address::generate(&env);
+    let (token_addr, token_mint) = create_token(&env, &token_admin);
+    token_mint.mint(&creator, &1_000_000);
+    token_mint.mint(&buyer, &1_000_000);
+
+    let config = RaffleConfig {
+        description: String::from_str(&env, "FairnessData format regression"),
+        end_time: 0,
+        no_deadline: true,
+        max_tickets: 5,
+        max_tickets_per_tx: 5,
+        min_tickets: 1,
+        allow_multiple: true,
+        ticket_price: MIN_TICKET_PRICE,
+        payment_token: token_addr,
+        prize_amount: MIN_TICKET_PRICE * 5,
+        prizes: vec![&env, 6000u32, 4000u32],
+        randomness_source: RandomnessSource::Internal,
+        oracle_address: None,
+        protocol_fee_bp: 0,
+        treasury_address: None,
+        swap_router: None,
+        tikka_token: None,
+        metadata_hash: BytesN::from_array(&env, &[0xFA; 32]),
+        claim_lockup_seconds: 0,
+        swap_deadline_seconds: 0,
+        early_bird_ticket_percentage: 0,
+        early_bird_discount_bp: 0,
+        prize_token: None,
+        nft_contract: None,
+        selection_algorithm: SelectionAlgorithm::Sha256CounterV2,
+        sale_gate: SaleGate::Open,
+        max_tickets_per_address: 0,
+        max_holding_bp: 0,
+        refund_target: RefundTarget::Owner,
+    };
+
+    client.init(&factory, &admin, &creator, &config);
+    client.deposit_prize();
+    client.buy_tickets(&buyer, &5);
+
+    // Raffles created before LcgRejectionV1 was retired still draw with it.
+    let mut raffle = client.get_raffle();
+    raffle.selection_algorithm = SelectionAlgorithm::LcgRejectionV1;
+    env.as_contract(&contract_id, || {
+        do_finalize_with_seed(
+            &env,
+            raffle,
+            BytesN::from_array(&env, &EXPECTED_SEED),
+            RandomnessType::Prng,
+        )
+    })
+    .unwrap();
+
+    let fairness = client.get_fairness_data();
+
+    assert_eq!(fairness.version, 2);
+    assert_eq!(fairness.seed.to_array(), EXPECTED_SEED);
+    assert_eq!(fairness.selection_algorithm, SelectionAlgorithm::LcgRejectionV1);
+    assert_eq!(fairness.randomness_source, RandomnessSource::Internal);
+    assert_eq!(fairness.ticket_ids.len(), 5);
+    assert_eq!(fairness.winning_ticket_indices.len(), 2);
+    assert_eq!(fairness.draw_timestamp, EXPECTED_TS);
+    assert_eq!(fairness.draw_sequence, EXPECTED_SEQ);
+
+    for (i, expected_id) in EXPECTED_TICKET_IDS.iter().enumerate() {
+        assert_eq!(fairness.ticket_ids.get(i as u32).unwrap(), *expected_id);
+    }
+    for (i, expected_idx) in EXPECTED_WINNING_INDICES.iter().enumerate() {
+        assert_eq!(
+            fairness.winning_ticket_indices.get(i as u32).unwrap(),
+            *expected_idx
+        );
+    }
+}
+
+#[test]
+fn internal_draw_waits_for_target_ledger() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1_000);
+    env.ledger().with_mut(|l| l.sequence_number = 500);
+
+    let contract_id = env.register(Contract, ());
+    let client = ContractClient::new(&env, &contract_id);
+
+    let factory = env.register(MockFactory, ());
+    let admin = Address::generate(&env);
+    let creator = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let late_buyer = Address::generate(&env);
+
+    let token_admin = Address::generate(&env);
+    let (token_addr, token_mint) = create_token(&env, &token_admin);
+    token_mint.mint(&creator, &1_000_000);
+    token_mint.mint(&buyer, &1_000_000);
+    token_mint.mint(&late_buyer, &1_000_000);
+
+    let config = RaffleConfig {
+        description: String::from_str(&env, "Delayed internal draw"),
+        end_time: 2_000,
+        no_deadline: false,
+        max_tickets: 10,
+        max_tickets_per_tx: 10,
+        min_tickets: 1,
+        allow_multiple: true,
+        ticket_price: MIN_TICKET_PRICE,
+        payment_token: token_addr,
+        prize_amount: MIN_TICKET_PRICE * 10,
+        prizes: vec![&env, 10000u32],
+        randomness_source: RandomnessSource::Internal,
+        oracle_address: None,
+        protocol_fee_bp: 0,
+        treasury_address: None,
+        swap_router: None,
+        tikka_token: None,
+        metadata_hash: BytesN::from_array(&env, &[26u8; 32]),
+        claim_lockup_seconds: 0,
+        swap_deadline_seconds: 0,
+        early_bird_ticket_percentage: 0,
+        early_bird_discount_bp: 0,
+        prize_token: None,
+        nft_contract: None,
+        selection_algorithm: SelectionAlgorithm::Sha256CounterV2,
+        sale_gate: SaleGate::Open,
+        max_tickets_per_address: 0,
+        max_holding_bp: 0,
+        refund_target: RefundTarget::Owner,
+    };
+
+    client.init(&factory, &admin, &creator, &config);
+    client.deposit_prize();
+    client.buy_tickets(&buyer, &3);
+
+    env.ledger().set_timestamp(2_000);
+    client.finalize_raffle();
+
+    // Commit step: sales are locked and no winner is chosen yet.
+    let raffle = client.get_raffle();
+    assert_eq!(raffle.status, RaffleStatus::Drawing);
+    assert_eq!(raffle.winners.len(), 0);
+    let pending = client.get_draw_commitment().unwrap();
+    assert_eq!(pending.commit_ledger, 500);
+    assert_eq!(pending.target_ledger, 500 + RANDOMNESS_MIN_DELAY_LEDGERS);
+    assert_eq!(
+        client.try_buy_tickets(&late_buyer, &1),
+        Err(Ok(Error::DrawingAlreadyInProgress))
+    );
+    assert_eq!(
+        client.try_finalize_raffle(),
+        Err(Ok(Error::DrawingAlreadyInProgress))
+    );
+
+    // Execute step refuses to run before the target ledger.
+    env.ledger()
+        .with_mut(|l| l.sequence_number = pending.target_ledger - 1);
+    assert_eq!(
+        client.try_execute_draw(&late_buyer),
+        Err(Ok(Error::RandomnessTooEarly))
+    );
+
+    // Anyone may execute once the target ledger is reached.
+    env.ledger()
+        .with_mut(|l| l.sequence_number = pending.target_ledger);
+    client.execute_draw(&late_buyer);
+
+    let raffle = client.get_raffle();
+    assert_eq!(raffle.status, RaffleStatus::Finalized);
+    assert_eq!(raffle.winners.get(0).unwrap(), buyer);
+    assert!(client.get_draw_commitment().is_none());
+    assert_drawing_lock_cleared(&env, &contract_id);
+    assert_eq!(
+        client.try_execute_draw(&late_buyer),
+        Err(Ok(Error::DrawingAlreadyComplete))
+    );
+}
+
+#[test]
+fn execute_draw_seed_mixes_execution_ledger_entropy() {
+    fn run(delay: u32, prng_seed: u8) -> BytesN<32> {
+        let env = Env::default();
+        env.mock_all_auths();
+        env.ledger().with_mut(|l| l.sequence_number = 500);
+
+        let (client, _admin, creator, buyer, _factory, _token_mint) = setup_active_raffle(&env);
+        client.buy_tickets(&buyer, &10);
+        client.finalize_raffle();
+
+        let pending = client.get_draw_commitment().unwrap();
+        env.ledger().with_mut(|l| {
+            l.sequence_number = pending.target_ledger + delay;
+            l.timestamp += delay as u64 * 5;
+        });
+        env.host().set_base_prng_seed([prng_seed; 32]).unwrap();
+        client.execute_draw(&creator);
+
+        client.get_fairness_data().seed
+    }
+
+    // Nothing stored at commit time fixes the seed: the executing ledger's
+    // PRNG output and position both move it.
+    let on_time = run(0, 1);
+    assert_ne!(run(0, 2), on_time);
+    assert_ne!(run(1, 1), on_time);
+    assert_eq!(run(0, 1), on_time);
+}
+
+#[test]
+fn execute_draw_recommits_after_execution_window() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|l| l.sequence_number = 500);
+
+    let (client, _admin, creator, buyer, _factory, _token_mint) = setup_active_raffle(&env);
+    client.buy_tickets(&buyer, &10);
+    client.finalize_raffle();
+    let pending = client.get_draw_commitment().unwrap();
+
+    let late = pending.target_ledger + DRAW_EXECUTION_WINDOW_LEDGERS + 1;
+    env.ledger().with_mut(|l| l.sequence_number = late);
+    client.execute_draw(&creator);
+
+    // No winners yet; the draw is re-committed from the old commitment alone.
+    assert_eq!(client.get_raffle().status, RaffleStatus::Drawing);
+    let recommitted = client.get_draw_commitment().unwrap();
+    let expected: BytesN<32> = env
+        .crypto()
+        .sha256(&(pending.commitment.clone(), pending.target_ledger).to_xdr(&env))
+        .into();
+    assert_eq!(recommitted.commitment, expected);
+    assert_eq!(recommitted.commit_ledger, late);
+    assert_eq!(recommitted.target_ledger, late + RANDOMNESS_MIN_DELAY_LEDGERS);
+    assert_eq!(
+        client.try_execute_draw(&creator),
+        Err(Ok(Error::RandomnessTooEarly))
+    );
+
+    env.ledger()
+        .with_mut(|l| l.sequence_number = recommitted.target_ledger);
+    client.execute_draw(&creator);
+    assert_eq!(client.get_raffle().status, RaffleStatus::Finalized);
+    assert!(client.get_draw_commitment().is_none());
+}
+
+#[test]
+fn cancel_clears_pending_internal_draw() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, _admin, creator, buyer, _factory, _token_mint) = setup_active_raffle(&env);
+    client.buy_tickets(&buyer, &10);
+    client.finalize_raffle();
+    assert!(client.get_draw_commitment().is_some());
+
+    client.cancel_raffle(&CancelReason::CreatorCancelled);
+
+    assert!(client.get_draw_commitment().is_none());
+    env.ledger()
+        .with_mut(|l| l.sequence_number += RANDOMNESS_MIN_DELAY_LEDGERS);
+    assert_eq!(
+        client.try_execute_draw(&creator),
+        Err(Ok(Error::DrawingAlreadyComplete))
+    );
+}
+
+#[test]
+fn every_randomness_source_selects_with_recorded_algorithm() {
+    #[derive(Clone, Copy)]
+    enum Path {
+        Internal,
+        CommitReveal,
+        Oracle,
+        Fallback,
+    }
+
+    for algorithm in [SelectionAlgorithm::Sha256CounterV2, SelectionAlgorithm::EnvPrng] {
+        for path in [Path::Internal, Path::CommitReveal, Path::Oracle, Path::Fallback] {
+            let env = Env::default();
+            env.mock_all_auths();
+
+            let contract_id = env.register(Contract, ());
+            let client = ContractClient::new(&env, &contract_id);
+            let factory = env.register(MockFactory, ());
+            let admin = Address::generate(&env);
+            let creator = Address::generate(&env);
+            let token_admin = Address::generate(&env);
+            let (token_addr, token_mint) = create_token(&env, &token_admin);
+            token_mint.mint(&creator, &1_000_000);
+
+            let mut config = gated_config(&env, &token_addr, SaleGate::Open);
+            config.max_tickets = 5;
+            config.max_tickets_per_tx = 5;
+            config.prizes = vec![&env, 5000, 3000, 2000];
+            config.selection_algorithm = algorithm;
+            match path {
+                Path::Internal => {}
+                Path::CommitReveal => config.randomness_source = RandomnessSource::CommitReveal,
+                Path::Oracle | Path::Fallback => {
+                    config.randomness_source = RandomnessSource::External;
+                    config.oracle_address = Some(Address::generate(&env));
+                }
+            }
+            client.init(&factory, &admin, &creator, &config);
+            client.deposit_prize();
+            for ticket_id in 1..=5 {
+                let buyer = Address::generate(&env);
+                token_mint.mint(&buyer, &1_000_000);
+                client.buy_tickets(&buyer, &1);
+                if matches!(path, Path::CommitReveal) && ticket_id == 2 {
+                    client.submit_commit(&2, &BytesN::from_array(&env, &[4u8; 32]));
+                }
+            }
+
+            // Selling out starts the draw; oracle raffles have requested randomness.
+            match path {
+                Path::Internal | Path::CommitReveal => {
+                    finalize_and_execute_draw(&env, &client, &creator)
+                }
+                Path::Oracle => {
+                    let request_id: u64 = env.as_contract(&contract_id, || {
+                        env.storage().instance().get(&DataKey::RandomnessRequestId).unwrap()
+                    });
+                    let random_seed = BytesN::from_array(&env, &[0x42; 32]);
+                    let signing_key = SigningKey::from_bytes(&[5u8; 32]);
+                    let message = env.as_contract(&contract_id, || {
+                        build_vrf_proof_message(&env, request_id, &random_seed)
+                    });
+                    let mut msg = [0u8; 256];
+                    let msg_len = message.len() as usize;
+                    message.copy_into_slice(&mut msg[..msg_len]);
+                    let signature = signing_key.sign(&msg[..msg_len]);
+                    client.provide_randomness(
+                        &random_seed,
+                        &BytesN::from_array(&env, &signing_key.verifying_key().to_bytes()),
+                        &BytesN::from_array(&env, &signature.to_bytes()),
+                        &request_id,
+                    );
+                }
+                Path::Fallback => {
+                    env.ledger()
+                        .with_mut(|l| l.sequence_number += ORACLE_TIMEOUT_LEDGERS + 1);
+                    client.trigger_randomness_fallback(&creator, &false);
+                }
+            }
+
+            assert_eq!(client.get_raffle().status, RaffleStatus::Finalized);
+            let fairness = client.get_fairness_data();
+            assert_eq!(fairness.selection_algorithm, algorithm);
+            let replayed = env.as_contract(&contract_id, || {
+                crate::randomness::select_winners_with(&env, &algorithm, &fairness.seed, 5, 3)
+            });
+            assert_eq!(fairness.winning_ticket_indices, replayed);
+        }
+    }
+}
+
+#[test]
+fn init_rejects_legacy_selection_algorithm() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (token_addr, _) = create_token(&env, &Address::generate(&env));
+    let mut config = gated_config(&env, &token_addr, SaleGate::Open);
+    config.selection_algorithm = SelectionAlgorithm::LcgRejectionV1;
+    let contract_id = env.register(Contract, ());
+    let client = ContractClient::new(&env, &contract_id);
+    assert_eq!(
+        client.try_init(
+            &env.register(MockFactory, ()),
+            &Address::generate(&env),
+            &Address::generate(&env),
+            &config,
+        ),
+        Err(Ok(Error::InvalidParameters))
+    );
+}
+
+#[test]
+fn legacy_fairness_record_still_decodes() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, _admin, _creator, buyer, _factory, _token_mint) = setup_active_raffle(&env);
+    client.buy_tickets(&buyer, &1);
+
+    // Raffles finalized before 256-bit seeds stored a bare 64-bit record.
+    env.as_contract(&client.address, || {
+        env.storage().persistent().set(
+            &DataKey::RandomnessSeed,
+            &LegacyFairnessMetadata {
+                seed: 0x0102_0304_0506_0708,
+                randomness_source: RandomnessSource::Internal,
+                winning_ticket_indices: vec![&env, 0u32],
+                draw_timestamp: 1_234,
+                draw_sequence: 56,
+            },
+        );
+    });
+
+    let fairness = client.get_fairness_data();
+    assert_eq!(fairness.version, FAIRNESS_VERSION_SEED64);
+    let mut widened = [0u8; 32];
+    widened[..8].copy_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+    assert_eq!(fairness.seed.to_array(), widened);
+    assert_eq!(fairness.selection_algorithm, SelectionAlgorithm::LcgRejectionV1);
+    assert_eq!(fairness.winning_ticket_indices, vec![&env, 0u32]);
+    assert_eq!(fairness.draw_timestamp, 1_234);
+    assert_eq!(fairness.draw_sequence, 56);
+}
+
+#[test]
+fn internal_draw_records_full_256_bit_seed() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let (client, _admin, _creator, buyer, _factory, _token_mint) = setup_active_raffle(&env);
+    client.buy_tickets(&buyer, &10);
+    finalize_and_execute_draw(&env, &client, &buyer);
+
+    let fairness = client.get_fairness_data();
+    assert_eq!(fairness.version, FAIRNESS_VERSION_SEED256);
+    // Bytes beyond the first eight carry entropy rather than zero padding.
+    assert_ne!(fairness.seed.to_array()[8..], [0u8; 24]);
+}
+
+#[test]
+fn cancel_rejected_once_raffle_failed() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let factory = env.register(MockFactory, ());
+    let admin = Address::generate(&env);
+    let creator = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (payment_token, token_mint) = create_token(&env, &token_admin);
+    token_mint.mint(&creator, &1_000_000);
+
+    let contract_id = env.register(Contract, ());
+    let client = ContractClient::new(&env, &contract_id);
+    let config = RaffleConfig {
+        description: String::from_str(&env, "Failed stays failed"),
+        end_time: 1_000,
+        no_deadline: false,
+        max_tickets: 5,
+        max_tickets_per_tx: 5,
+        min_tickets: 1,
+        allow_multiple: true,
+        ticket_price: MIN_TICKET_PRICE,
+        payment_token,
+        prize_amount: MIN_TICKET_PRICE * 10,
+        prizes: vec![&env, 10000u32],
+        randomness_source: RandomnessSource::Internal,
+        oracle_address: None,
+        protocol_fee_bp: 0,
+        treasury_address: None,
+        swap_router: None,
+        tikka_token: None,
+        metadata_hash: BytesN::from_array(&env, &[61u8; 32]),
+        claim_lockup_seconds: 0,
+        swap_deadline_seconds: 0,
+        early_bird_ticket_percentage: 0,
+        early_bird_discount_bp: 0,
+        prize_token: None,
+        nft_contract: None,
+        selection_algorithm: SelectionAlgorithm::Sha256CounterV2,
+        sale_gate: SaleGate::Open,
+        max_tickets_per_address: 0,
+        max_holding_bp: 0,
+        refund_target: RefundTarget::Owner,
+    };
+    client.init(&factory, &admin, &creator, &config);
+    client.deposit_prize();
+
+    env.ledger().with_mut(|l| l.timestamp = 1_000);
+    client.finalize_raffle();
+    assert_eq!(client.get_raffle().status, RaffleStatus::Failed);
+
+    // Failed is terminal; re-cancelling would rewrite history for indexers.
+    assert_eq!(
+        client.try_cancel_raffle(&CancelReason::CreatorCancelled),
+        Err(Ok(Error::InvalidStatus))
+    );
+    assert_eq!(client.get_raffle().status, RaffleStatus::Failed);
+}
+
+#[test]
+fn wipe_storage_batch_resumes_until_empty() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let factory = env.register(MockFactory, ());
+    let admin = Address::generate(&env);
+    let creator = Address::generate(&env);
+    let buyer_a = Address::generate(&env);
+    let buyer_b = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (payment_token, token_mint) = create_token(&env, &token_admin);
+    token_mint.mint(&creator, &1_000_000);
+    token_mint.mint(&buyer_a, &1_000_000);
+    token_mint.mint(&buyer_b, &1_000_000);
+
+    let contract_id = env.register(Contract, ());
+    let client = ContractClient::new(&env, &contract_id);
+    let config = RaffleConfig {
+        description: String::from_str(&env, "batched wipe"),
+        end_time: 0,
+        no_deadline: true,
+        max_tickets: 10,
+        max_tickets_per_tx: 10,
+        min_tickets: 1,
+        allow_multiple: true,
+        ticket_price: MIN_TICKET_PRICE,
+        payment_token,
+        prize_amount: MIN_TICKET_PRICE * 10,
+        prizes: vec![&env, 10000u32],
+        randomness_source: RandomnessSource::Internal,
+        oracle_address: None,
+        protocol_fee_bp: 0,
+        treasury_address: None,
+        swap_router: None,
+        tikka_token: None,
+        metadata_hash: BytesN::from_array(&env, &[62u8; 32]),
+        claim_lockup_seconds: 0,
+        swap_deadline_seconds: 0,
+        early_bird_ticket_percentage: 0,
+        early_bird_discount_bp: 0,
+        prize_token: None,
+        nft_contract: None,
+        selection_algorithm: SelectionAlgorithm::Sha256CounterV2,
+        sale_gate: SaleGate::Open,
+        max_tickets_per_address: 0,
+        max_holding_bp: 0,
+        refund_target: RefundTarget::Owner,
+    };
+    client.init(&factory, &admin, &creator, &config);
+    client.deposit_prize();
+    client.buy_tickets(&buyer_a, &3);
+    client.buy_tickets(&buyer_b, &2);
+    client.cancel_raffle(&CancelReason::CreatorCancelled);
+    assert_eq!(client.get_raffle().status, RaffleStatus::Cancelled);
+
+    assert_eq!(client.try_wipe_storage_batch(&0, &0), Err(Ok(Error::InvalidParameters)));
+
+    // 5 tickets + 2 buyers = 7 items.
+    let p1 = client.wipe_storage_batch(&0, &3);
+    assert_eq!((p1.next_cursor, p1.total_items, p1.done), (3, 7, false));
+    env.as_contract(&contract_id, || {
+        assert!(!env.storage().persistent().has(&DataKey::Ticket(3)));
+        assert!(env.storage().persistent().has(&DataKey::Ticket(4)));
+    });
+
+    let p2 = client.wipe_storage_batch(&p1.next_cursor, &3);
+    assert_eq!((p2.next_cursor, p2.done), (6, false));
+    env.as_contract(&contract_id, || {
+        assert!(!env.storage().persistent().has(&DataKey::OwnerTickets(buyer_a.clone())));
+        assert!(env.storage().persistent().has(&DataKey::OwnerTickets(buyer_b.clone())));
+        assert!(env.storage().instance().has(&DataKey::Raffle));
+    });
+
+    let p3 = client.wipe_storage_batch(&p2.next_cursor, &3);
+    assert_eq!((p3.next_cursor, p3.done), (7, true));
+    env.as_contract(&contract_id, || {
+        assert!(!env.storage().persistent().has(&DataKey::OwnerTickets(buyer_b.clone())));
+        assert!(!env.storage().persistent().has(&DataKey::TicketCount(buyer_b.clone())));
+        assert!(!env.storage().persistent().has(&DataKey::TicketBuyers));
+        assert!(!env.storage().instance().has(&DataKey::Raffle));
+        assert!(!env.storage().instance().has(&DataKey::Factory));
+    });
+}
+
+#[test]
+fn keeper_ttl_extension_outlives_default_ttls() {
+    use raffle_shared::constants::{
+        INSTANCE_TTL_EXTEND_TO, LEDGERS_PER_DAY, PERSISTENT_TTL_EXTEND_TO,
+    };
+    use soroban_sdk::testutils::storage::{Instance as _, Persistent as _};
+
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let factory = env.register(MockFactory, ());
+    let admin = Address::generate(&env);
+    let creator = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (payment_token, token_mint) = create_token(&env, &token_admin);
+    token_mint.mint(&creator, &1_000_000);
+    token_mint.mint(&buyer, &1_000_000);
+
+    let contract_id = env.register(Contract, ());
+    let client = ContractClient::new(&env, &contract_id);
+    let config = RaffleConfig {
+        description: String::from_str(&env, "long-lived raffle"),
+        end_time: 0,
+        no_deadline: true,
+        max_tickets: 10,
+        max_tickets_per_tx: 10,
+        min_tickets: 1,
+        allow_multiple: true,
+        ticket_price: MIN_TICKET_PRICE,
+        payment_token,
+        prize_amount: MIN_TICKET_PRICE * 10,
+        prizes: vec![&env, 10000u32],
+        randomness_source: RandomnessSource::Internal,
+        oracle_address: None,
+        protocol_fee_bp: 0,
+        treasury_address: None,
+        swap_router: None,
+        tikka_token: None,
+        metadata_hash: BytesN::from_array(&env, &[63u8; 32]),
+        claim_lockup_seconds: 0,
+        swap_deadline_seconds: 0,
+        early_bird_ticket_percentage: 0,
+        early_bird_discount_bp: 0,
+        selection_algorithm: SelectionAlgorithm::Sha256CounterV2,
+        prize_token: None,
+        nft_contract: None,
+        sale_gate: SaleGate::Open,
+        max_tickets_per_address: 0,
+        max_holding_bp: 0,
+        refund_target: RefundTarget::Owner,
+    };
+    client.init(&factory, &admin, &creator, &config);
+    client.deposit_prize();
+    client.buy_tickets(&buyer, &2);
+
+    env.as_contract(&contract_id, || {
+        assert_eq!(env.storage().instance().get_ttl(), INSTANCE_TTL_EXTEND_TO);
+        assert_eq!(
+            env.storage().persistent().get_ttl(&DataKey::Ticket(1)),
+            PERSISTENT_TTL_EXTEND_TO
+        );
+    });
+
+    assert_eq!(client.try_extend_raffle_ttl(&0, &0), Err(Ok(Error::InvalidParameters)));
+
+    // Ninety days with a keeper every six: far beyond both the host's
+    // default TTL and the 30/60-day extensions themselves.  Each visit
+    // lands inside the 7/14-day bump thresholds before anything lapses.
+    for _ in 0..15 {
+        env.ledger().with_mut(|l| l.sequence_number += 6 * LEDGERS_PER_DAY);
+        // 2 tickets + 1 buyer.
+        assert_eq!(client.extend_raffle_ttl(&0, &10), 3);
+    }
+
+    env.as_contract(&contract_id, || {
+        assert!(env.storage().instance().get_ttl() > LEDGERS_PER_DAY);
+        assert!(env.storage().persistent().get_ttl(&DataKey::Ticket(2)) > LEDGERS_PER_DAY);
+        assert!(env.storage().persistent().get_ttl(&DataKey::OwnerTickets(buyer.clone())) > LEDGERS_PER_DAY);
+    });
+
+    assert_eq!(client.get_raffle().tickets_sold, 2);
+    assert_eq!(client.get_my_tickets(&buyer).len(), 2);
+}
+
+/// Smallest Wasm module the host accepts for upload: empty apart from the
+/// `contractenvmetav0` section naming interface version 23.  Upgrading to it
+/// gives a real code swap; tests then re-register the native contract at the
+/// same address to play the new build.
+const STUB_WASM: &[u8] = b"\0asm\x01\0\0\0\0\x1e\x11contractenvmetav0\0\0\0\0\0\0\0\x17\0\0\0\0";
+
+fn stub_wasm_hash(env: &Env) -> BytesN<32> {
+    env.deployer()
+        .upload_contract_wasm(Bytes::from_slice(env, STUB_WASM))
+}
+
+#[test]
+fn upgrade_and_migrate_preserve_funds_and_tickets() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let factory = env.register(MockFactory, ());
+    let admin = Address::generate(&env);
+    let creator = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (payment_token, token_mint) = create_token(&env, &token_admin);
+    token_mint.mint(&creator, &1_000_000);
+    token_mint.mint(&buyer, &1_000_000);
+
+    let contract_id = env.register(Contract, ());
+    let client = ContractClient::new(&env, &contract_id);
+    let config = RaffleConfig {
+        description: String::from_str(&env, "upgradable"),
+        end_time: 0,
+        no_deadline: true,
+        max_tickets: 10,
+        max_tickets_per_tx: 10,
+        min_tickets: 1,
+        allow_multiple: true,
+        ticket_price: MIN_TICKET_PRICE,
+        payment_token: payment_token.clone(),
+        prize_amount: MIN_TICKET_PRICE * 10,
+        prizes: vec![&env, 10000u32],
+        randomness_source: RandomnessSource::Internal,
+        oracle_address: None,
+        protocol_fee_bp: 0,
+        treasury_address: None,
+        swap_router: None,
+        tikka_token: None,
+        metadata_hash: BytesN::from_array(&env, &[64u8; 32]),
+        claim_lockup_seconds: 0,
+        swap_deadline_seconds: 0,
+        early_bird_ticket_percentage: 0,
+        early_bird_discount_bp: 0,
+        selection_algorithm: SelectionAlgorithm::Sha256CounterV2,
+        prize_token: None,
+        nft_contract: None,
+        sale_gate: SaleGate::Open,
+        max_tickets_per_address: 0,
+        max_holding_bp: 0,
+        refund_target: RefundTarget::Owner,
+    };
+    client.init(&factory, &admin, &creator, &config);
+    client.deposit_prize();
+    client.buy_tickets(&buyer, &3);
+    assert_eq!(client.get_schema_version(), SCHEMA_VERSION);
+
+    let token = soroban_sdk::token::Client::new(&env, &payment_token);
+    let balance_before = token.balance(&contract_id);
+    let new_hash = stub_wasm_hash(&env);
+
+    env.as_contract(&contract_id, || {
+        env.storage().instance().set(&DataKey::DrawingLock, &true);
+    });
+    assert_eq!(client.try_upgrade(&new_hash), Err(Ok(Error::DrawingAlreadyInProgress)));
+    env.as_contract(&contract_id, || {
+        env.storage().instance().remove(&DataKey::DrawingLock);
+        // Pretend this instance predates the schema key.
+        env.storage().instance().remove(&DataKey::SchemaVersion);
+    });
+    assert_eq!(client.get_schema_version(), 0);
+
+    client.upgrade(&new_hash);
+    env.register_at(&contract_id, Contract, ());
+
+    assert_eq!(client.try_migrate(&1), Err(Ok(Error::InvalidParameters)));
+    client.migrate(&0);
+    assert_eq!(client.get_schema_version(), SCHEMA_VERSION);
+    assert_eq!(client.try_migrate(&0), Err(Ok(Error::InvalidParameters)));
+
+    assert_eq!(client.get_raffle().tickets_sold, 3);
+    assert_eq!(client.get_my_tickets(&buyer).len(), 3);
+    assert_eq!(token.balance(&contract_id), balance_before);
+    client.buy_tickets(&buyer, &1);
+    assert_eq!(client.get_raffle().tickets_sold, 4);
+}
+
+#[test]
+fn live_param_changes_wait_out_notice_window() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1_000);
+
+    let contract_id = env.register(Contract, ());
+    let client = ContractClient::new(&env, &contract_id);
+    let factory = env.register(MockFactory, ());
+    let admin = Address::generate(&env);
+    let creator = Address::generate(&env);
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    let fee_manager = Address::generate(&env);
+
+    let token_admin = Address::generate(&env);
+    let (token_addr, token_mint) = create_token(&env, &token_admin);
+    token_mint.mint(&creator, &1_000_000);
+    token_mint.mint(&alice, &1_000_000);
+    token_mint.mint(&bob, &1_000_000);
+
+    let config = RaffleConfig {
+        description: String::from_str(&env, "live parameter change"),
+        end_time: 0,
+        no_deadline: true,
+        max_tickets: 5,
+        max_tickets_per_tx: 5,
+        min_tickets: 1,
+        allow_multiple: true,
+        ticket_price: MIN_TICKET_PRICE,
+        payment_token: token_addr.clone(),
+        prize_amount: MIN_TICKET_PRICE * 10,
+        prizes: vec![&env, 10000u32],
+        randomness_source: RandomnessSource::Internal,
+        oracle_address: None,
+        protocol_fee_bp: 1_000,
+        treasury_address: None,
+        swap_router: None,
+        tikka_token: None,
+        metadata_hash: BytesN::from_array(&env, &[9u8; 32]),
+        claim_lockup_seconds: 0,
+        swap_deadline_seconds: 0,
+        early_bird_ticket_percentage: 0,
+        early_bird_discount_bp: 0,
+        selection_algorithm: SelectionAlgorithm::Sha256CounterV2,
+        prize_token: None,
+        nft_contract: None,
+        sale_gate: SaleGate::Open,
+        max_tickets_per_address: 0,
+        max_holding_bp: 0,
+        refund_target: RefundTarget::Owner,
+    };
+    client.init(&factory, &admin, &creator, &config);
+    client.deposit_prize();
+    set_role(&env, &client, Role::FeeManager, &fee_manager);
+
+    client.buy_tickets(&alice, &1);
+    client.buy_tickets(&bob, &2);
+
+    // A fee increase waits out the notice window like any other change.
+    client.set_protocol_fee_bp(&fee_manager, &1_500);
+    assert_eq!(client.get_raffle().protocol_fee_bp, 1_000);
+    assert_eq!(
+        client.get_pending_param_change().unwrap().change,
+        ParamChange::ProtocolFeeBp(1_500)
+    );
+    client.cancel_param_change(&fee_manager);
+
+    client.set_protocol_fee_bp(&fee_manager, &500);
+    assert_eq!(client.get_raffle().protocol_fee_bp, 1_000);
+    let pending = client.get_pending_param_change().unwrap();
+    assert_eq!(pending.change, ParamChange::ProtocolFeeBp(500));
+    assert_eq!(pending.effective_at, 1_000 + PARAM_CHANGE_NOTICE_SECONDS);
+    assert_eq!(
+        client.try_set_swap_deadline(&fee_manager, &60),
+        Err(Ok(ParamChangeError::ParamChangePending))
+    );
+
+    // Alice leaves during the notice window with everything she paid, fee
+    // included; Bob's last ticket takes her ID.
+    let token_client = token::Client::new(&env, &token_addr);
+    let before = token_client.balance(&alice);
+    let fees_before = client.get_accumulated_fees();
+    assert_eq!(client.refund_ticket(&1), MIN_TICKET_PRICE);
+    assert!(published(&env, &contract_id, "ticket_renumbered"));
+    assert_eq!(token_client.balance(&alice) - before, MIN_TICKET_PRICE);
+    assert_eq!(fees_before - client.get_accumulated_fees(), MIN_TICKET_PRICE / 10);
+    assert_eq!(client.get_outcome().gross_volume, 2 * MIN_TICKET_PRICE);
+    assert_eq!(client.get_raffle().tickets_sold, 2);
+    assert_eq!(client.get_my_tickets(&alice).len(), 0);
+    assert_eq!(client.get_my_tickets(&bob), vec![&env, 2u32, 1u32]);
+    assert_eq!(
+        client.get_portfolio_entry(&bob).refundable_tickets,
+        vec![&env, 2u32, 1u32]
+    );
+
+    assert_eq!(
+        client.try_execute_param_change(&alice),
+        Err(Ok(ParamChangeError::ParamChangeNoticeActive))
+    );
+    env.ledger().set_timestamp(1_000 + PARAM_CHANGE_NOTICE_SECONDS);
+    client.execute_param_change(&alice);
+    assert_eq!(client.get_raffle().protocol_fee_bp, 500);
+    assert!(client.get_pending_param_change().is_none());
+
+    // Buying again keeps ticket numbers unique after the swap.
+    client.buy_tickets(&alice, &1);
+    assert_eq!(client.get_raffle().tickets_sold, 3);
+
+    client.set_swap_deadline(&fee_manager, &60);
+    assert_eq!(
+        client.try_cancel_param_change(&alice),
+        Err(Ok(ParamChangeError::NotAuthorized))
+    );
+    client.cancel_param_change(&fee_manager);
+    assert!(client.get_pending_param_change().is_none());
+    assert_eq!(
+        client.try_execute_param_change(&alice),
+        Err(Ok(ParamChangeError::NoPendingParamChange))
+    );
+}
+
+#[test]
+fn notice_window_refund_returns_gross_paid_and_closes_with_window() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1_000);
+
+    let contract_id = env.register(Contract, ());
+    let client = ContractClient::new(&env, &contract_id);
+    let factory = env.register(MockFactory, ());
+    let admin = Address::generate(&env);
+    let creator = Address::generate(&env);
+    let payer = Address::generate(&env);
+    let recipient = Address::generate(&env);
+    let bob = Address::generate(&env);
+    let fee_manager = Address::generate(&env);
+
+    let (token_addr, token_mint) = create_token(&env, &Address::generate(&env));
+    token_mint.mint(&creator, &1_000_000);
+    token_mint.mint(&payer, &1_000_000);
+    token_mint.mint(&bob, &1_000_000);
+    let token_client = token::Client::new(&env, &token_addr);
+
+    let mut config = gated_config(&env, &token_addr, SaleGate::Open);
+    config.max_tickets = 5;
+    config.max_tickets_per_tx = 5;
+    config.protocol_fee_bp = 1_000;
+    config.refund_target = RefundTarget::Payer;
+    client.init(&factory, &admin, &creator, &config);
+    client.deposit_prize();
+    set_role(&env, &client, Role::FeeManager, &fee_manager);
+
+    client.buy_tickets_for(&payer, &recipient, &1, &None);
+    client.buy_tickets(&bob, &1);
+
+    // Live raffles only refund while a change is pending.
+    assert_eq!(client.try_refund_ticket(&1), Err(Ok(Error::InvalidStatus)));
+
+    client.set_swap_deadline(&fee_manager, &60);
+    let held = token_client.balance(&contract_id);
+
+    // The refund goes to whoever paid, not the holder, and Bob's ticket
+    // moves into ID 1.
+    let payer_before = token_client.balance(&payer);
+    assert_eq!(client.refund_ticket(&1), MIN_TICKET_PRICE);
+    assert!(published(&env, &contract_id, "ticket_renumbered"));
+    assert_eq!(token_client.balance(&payer) - payer_before, MIN_TICKET_PRICE);
+    assert_eq!(token_client.balance(&recipient), 0);
+    assert_eq!(client.get_my_tickets(&recipient).len(), 0);
+
+    // Its old ID is gone.
+    assert_eq!(client.get_my_tickets(&bob), vec![&env, 1u32]);
+    assert_eq!(client.try_refund_ticket(&2), Err(Ok(Error::TicketNotFound)));
+
+    // Bob had no separate payer, so his refund comes back to him.  Both
+    // sales are gone from the raffle's books.
+    let bob_before = token_client.balance(&bob);
+    assert_eq!(client.refund_ticket(&1), MIN_TICKET_PRICE);
+    assert!(!published(&env, &contract_id, "ticket_renumbered"));
+    assert_eq!(token_client.balance(&bob) - bob_before, MIN_TICKET_PRICE);
+    assert_eq!(client.get_raffle().tickets_sold, 0);
+    assert_eq!(held - token_client.balance(&contract_id), 2 * MIN_TICKET_PRICE);
+    assert_eq!(client.get_accumulated_fees(), 0);
+    assert_eq!(client.get_outcome().gross_volume, 0);
+
+    // Once the change applies the window closes.
+    env.ledger().set_timestamp(1_000 + PARAM_CHANGE_NOTICE_SECONDS);
+    client.execute_param_change(&fee_manager);
+    client.buy_tickets(&bob, &1);
+    assert_eq!(client.try_refund_ticket(&1), Err(Ok(Error::InvalidStatus)));
+}
+
+#[test]
+fn protocol_fee_increase_applies_to_sales_after_notice() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1_000);
+
+    let contract_id = env.register(Contract, ());
+    let client = ContractClient::new(&env, &contract_id);
+    let factory = env.register(MockFactory, ());
+    let admin = Address::generate(&env);
+    let creator = Address::generate(&env);
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    let fee_manager = Address::generate(&env);
+
+    let (token_addr, token_mint) = create_token(&env, &Address::generate(&env));
+    token_mint.mint(&creator, &1_000_000);
+    token_mint.mint(&alice, &1_000_000);
+    token_mint.mint(&bob, &1_000_000);
+
+    let mut config = gated_config(&env, &token_addr, SaleGate::Open);
+    config.max_tickets = 5;
+    config.max_tickets_per_tx = 5;
+    config.protocol_fee_bp = 1_000;
+    client.init(&factory, &admin, &creator, &config);
+    client.deposit_prize();
+    set_role(&env, &client, Role::FeeManager, &fee_manager);
+    let ticket_payment = |ticket_id: u32| -> TicketPayment {
+        env.as_contract(&contract_id, || {
+            env.storage().persistent().get(&DataKey::TicketPaid(ticket_id)).unwrap()
+        })
+    };
+
+    // Before any sale the fee moves at once.
+    client.set_protocol_fee_bp(&fee_manager, &1_500);
+    assert_eq!(client.get_raffle().protocol_fee_bp, 1_500);
+    client.set_protocol_fee_bp(&fee_manager, &1_000);
+
+    // After a sale an increase is only scheduled; sales during the notice
+    // window still pay the old fee.
+    client.buy_tickets(&alice, &1);
+    client.set_protocol_fee_bp(&fee_manager, &1_500);
+    assert_eq!(client.get_raffle().protocol_fee_bp, 1_000);
+    client.buy_tickets(&bob, &1);
+    assert_eq!(
+        ticket_payment(2),
+        TicketPayment { gross: MIN_TICKET_PRICE, fee: MIN_TICKET_PRICE / 10 }
+    );
+
+    env.ledger().set_timestamp(1_000 + PARAM_CHANGE_NOTICE_SECONDS);
+    client.execute_param_change(&fee_manager);
+    assert_eq!(client.get_raffle().protocol_fee_bp, 1_500);
+    client.buy_tickets(&alice, &1);
+    assert_eq!(
+        ticket_payment(3),
+        TicketPayment { gross: MIN_TICKET_PRICE, fee: MIN_TICKET_PRICE * 15 / 100 }
+    );
+    assert_eq!(
+        client.get_accumulated_fees(),
+        2 * (MIN_TICKET_PRICE / 10) + MIN_TICKET_PRICE * 15 / 100
+    );
+}
+
+#[test]
+fn notice_window_refunds_reverse_the_exact_purchase_fee() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1_000);
+
+    let contract_id = env.register(Contract, ());
+    let client = ContractClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    let creator = Address::generate(&env);
+    let alice = Address::generate(&env);
+    let fee_manager = Address::generate(&env);
+
+    let (token_addr, token_mint) = create_token(&env, &Address::generate(&env));
+    token_mint.mint(&creator, &1_000_000);
+    token_mint.mint(&alice, &1_000_000);
+
+    // 3 tickets at 10_005 with a 10% fee: 3_001 in fees, not a multiple of 3.
+    let mut config = gated_config(&env, &token_addr, SaleGate::Open);
+    config.ticket_price = 10_005;
+    config.protocol_fee_bp = 1_000;
+    client.init(&env.register(MockFactory, ()), &admin, &creator, &config);
+    client.deposit_prize();
+    set_role(&env, &client, Role::FeeManager, &fee_manager);
+
+    client.buy_tickets(&alice, &3);
+    assert_eq!(client.get_accumulated_fees(), 3_001);
+    let first: TicketPayment = env.as_contract(&contract_id, || {
+        env.storage().persistent().get(&DataKey::TicketPaid(1)).unwrap()
+    });
+    assert_eq!(first, TicketPayment { gross: 10_005, fee: 1_001 });
+
+    client.set_swap_deadline(&fee_manager, &60);
+    for ticket_id in [3u32, 2, 1] {
+        assert_eq!(client.refund_ticket(&ticket_id), 10_005);
+    }
+    assert_eq!(client.get_accumulated_fees(), 0);
+    assert_eq!(token::Client::new(&env, &token_addr).balance(&alice), 1_000_000);
+}
+
+#[test]
+fn cancelled_refunds_return_the_early_bird_price_paid() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(Contract, ());
+    let client = ContractClient::new(&env, &contract_id);
+    let creator = Address::generate(&env);
+    let early = Address::generate(&env);
+    let late = Address::generate(&env);
+
+    let (token_addr, token_mint) = create_token(&env, &Address::generate(&env));
+    token_mint.mint(&creator, &1_000_000);
+    token_mint.mint(&early, &1_000_000);
+    token_mint.mint(&late, &1_000_000);
+    let token_client = token::Client::new(&env, &token_addr);
+
+    // The first half of the tickets sell at half price.
+    let mut config = gated_config(&env, &token_addr, SaleGate::Open);
+    config.early_bird_ticket_percentage = 50;
+    config.early_bird_discount_bp = 5_000;
+    client.init(&env.register(MockFactory, ()), &Address::generate(&env), &creator, &config);
+    client.deposit_prize();
+
+    client.buy_tickets(&early, &5);
+    client.buy_tickets(&late, &2);
+    let held = token_client.balance(&contract_id) - MIN_TICKET_PRICE * 10;
+    assert_eq!(held, 5 * MIN_TICKET_PRICE / 2 + 2 * MIN_TICKET_PRICE);
+
+    client.cancel_raffle(&CancelReason::CreatorCancelled);
+    assert_eq!(client.refund_ticket(&1), MIN_TICKET_PRICE / 2);
+    assert_eq!(
+        client.batch_refund_tickets(&early, &vec![&env, 2u32, 3, 4, 5]),
+        4 * MIN_TICKET_PRICE / 2
+    );
+    assert_eq!(
+        client.batch_refund_tickets(&late, &vec![&env, 6u32, 7]),
+        2 * MIN_TICKET_PRICE
+    );
+    assert_eq!(token_client.balance(&early), 1_000_000);
+    assert_eq!(token_client.balance(&late), 1_000_000);
+    assert_eq!(token_client.balance(&contract_id), MIN_TICKET_PRICE * 10);
+}
+
+/// How many events named `name` the last invocation made `contract` publish.
+fn published_count(env: &Env, contract: &Address, name: &str) -> usize {
+    let topic = Symbol::new(env, name);
+    env.events()
+        .all()
+        .iter()
+        .filter(|(address, topics, _)| {
+            address == contract
+                && topics
+                    .get(0)
+                    .and_then(|t| Symbol::try_from_val(env, &t).ok())
+                    .is_some_and(|t| t == topic)
+        })
+        .count()
+}
+
+/// Whether the last invocation made `contract` publish an event named `name`.
+fn published(env: &Env, contract: &Address, name: &str) -> bool {
+    published_count(env, contract, name) > 0
+}
+
+/// Records each receipt `mint` under `(raffle_id, ticket_id)`.
+#[contract]
+pub struct MockNft;
+
+#[contractimpl]
+impl MockNft {
+    pub fn mint(env: Env, recipient: Address, ticket_id: u32, raffle_id: Address) {
+        env.storage().persistent().set(&(raffle_id, ticket_id), &recipient);
+    }
+    pub fn owner_of(env: Env, raffle_id: Address, ticket_id: u32) -> Option<Address> {
+        env.storage().persistent().get(&(raffle_id, ticket_id))
+    }
+}
+
+#[test]
+fn buy_tickets_mints_an_nft_receipt_per_ticket() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(Contract, ());
+    let client = ContractClient::new(&env, &contract_id);
+    let nft = MockNftClient::new(&env, &env.register(MockNft, ()));
+    let creator = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let (token_addr, token_mint) = create_token(&env, &Address::generate(&env));
+    token_mint.mint(&creator, &1_000_000);
+    token_mint.mint(&buyer, &1_000_000);
+
+    let mut config = gated_config(&env, &token_addr, SaleGate::Open);
+    config.nft_contract = Some(nft.address.clone());
+    client.init(&env.register(MockFactory, ()), &Address::generate(&env), &creator, &config);
+    client.deposit_prize();
+    assert_eq!(client.get_raffle().nft_contract, Some(nft.address.clone()));
+
+    client.buy_tickets(&buyer, &3);
+    assert_eq!(published_count(&env, &contract_id, "ticket_nft_minted"), 3);
+    for ticket_id in 1..=3u32 {
+        assert_eq!(nft.owner_of(&contract_id, &ticket_id), Some(buyer.clone()));
+    }
+    assert_eq!(nft.owner_of(&contract_id, &4), None);
+}
+
+#[test]
+fn gifted_tickets_mint_their_nft_receipts_to_the_recipient() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(Contract, ());
+    let client = ContractClient::new(&env, &contract_id);
+    let nft = MockNftClient::new(&env, &env.register(MockNft, ()));
+    let creator = Address::generate(&env);
+    let payer = Address::generate(&env);
+    let recipient = Address::generate(&env);
+    let (token_addr, token_mint) = create_token(&env, &Address::generate(&env));
+    token_mint.mint(&creator, &1_000_000);
+    token_mint.mint(&payer, &1_000_000);
+
+    let mut config = gated_config(&env, &token_addr, SaleGate::Open);
+    config.nft_contract = Some(nft.address.clone());
+    client.init(&env.register(MockFactory, ()), &Address::generate(&env), &creator, &config);
+    client.deposit_prize();
+
+    client.buy_tickets_for(&payer, &recipient, &2, &None);
+    for ticket_id in 1..=2u32 {
+        assert_eq!(nft.owner_of(&contract_id, &ticket_id), Some(recipient.clone()));
+    }
+}
+
+
+/// Allowlist leaf: `sha256(address XDR || allocation)`.
+fn allowlist_leaf(env: &Env, buyer: &Address, allocation: u32) -> BytesN<32> {
+    let mut data = buyer.clone().to_xdr(env);
+    data.extend_from_array(&allocation.to_be_bytes());
+    env.crypto().sha256(&data).into()
+}
+
+/// Allowlist parent node, hashing its children in ascending order.
+fn allowlist_parent(env: &Env, a: &BytesN<32>, b: &BytesN<32>) -> BytesN<32> {
+    let (first, second) = if a.to_array() <= b.to_array() { (a, b) } else { (b, a) };
+    let mut data = Bytes::from_array(env, &first.to_array());
+    data.extend_from_array(&second.to_array());
+    env.crypto().sha256(&data).into()
+}
+
+fn gated_config(env: &Env, payment_token: &Address, sale_gate: SaleGate) -> RaffleConfig {
+    RaffleConfig {
+        description: String::from_str(env, "gated sale"),
+        end_time: 0,
+        no_deadline: true,
+        max_tickets: 10,
+        max_tickets_per_tx: 10,
+        min_tickets: 1,
+        allow_multiple: true,
+        ticket_price: MIN_TICKET_PRICE,
+        payment_token: payment_token.clone(),
+        prize_amount: MIN_TICKET_PRICE * 10,
+        prizes: vec![env, 10000u32],
+        randomness_source: RandomnessSource::Internal,
+        oracle_address: None,
+        protocol_fee_bp: 0,
+        treasury_address: None,
+        swap_router: None,
+        tikka_token: None,
+        metadata_hash: BytesN::from_array(env, &[7u8; 32]),
+        claim_lockup_seconds: 0,
+        swap_deadline_seconds: 0,
+        early_bird_ticket_percentage: 0,
+        early_bird_discount_bp: 0,
+        prize_token: None,
+        nft_contract: None,
+        selection_algorithm: SelectionAlgorithm::Sha256CounterV2,
+        sale_gate,
+        max_tickets_per_address: 0,
+        max_holding_bp: 0,
+        refund_target: RefundTarget::Owner,
+    }
+}
+
+#[test]
+fn allowlist_presale_caps_allocations_then_opens_to_public() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1_000);
+
+    let contract_id = env.register(Contract, ());
+    let client = ContractClient::new(&env, &contract_id);
+    let factory = env.register(MockFactory, ());
+    let admin = Address::generate(&env);
+    let creator = Address::generate(&env);
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    let carol = Address::generate(&env);
+
+    let token_admin = Address::generate(&env);
+    let (token_addr, token_mint) = create_token(&env, &token_admin);
+    for account in [&creator, &alice, &bob, &carol] {
+        token_mint.mint(account, &1_000_000);
+    }
+
+    let alice_leaf = allowlist_leaf(&env, &alice, 2);
+    let bob_leaf = allowlist_leaf(&env, &bob, 1);
+    let root = allowlist_parent(&env, &alice_leaf, &bob_leaf);
+    let alice_proof = AllowlistProof { allocation: 2, proof: vec![&env, bob_leaf] };
+    let bob_proof = AllowlistProof { allocation: 1, proof: vec![&env, alice_leaf] };
+
+    let gate = SaleGate::Presale(GateRule::MerkleAllowlist(root), 2_000);
+    client.init(&factory, &admin, &creator, &gated_config(&env, &token_addr, gate.clone()));
+    client.deposit_prize();
+    assert_eq!(client.get_sale_gate(), gate);
+
+    // Presale: only allowlisted buyers, up to their allocation.
+    assert_eq!(client.try_buy_tickets(&alice, &1), Err(Ok(Error::NotEligible)));
+    assert_eq!(
+        client.check_eligibility(&alice, &Some(alice_proof.clone())),
+        Eligibility { eligible: true, gated: true, remaining_allocation: Some(2) }
+    );
+    client.buy_tickets_with_proof(&alice, &2, &alice_proof);
+    assert_eq!(
+        client.try_buy_tickets_with_proof(&alice, &1, &alice_proof),
+        Err(Ok(Error::NotEligible))
+    );
+    assert!(!client.check_eligibility(&alice, &Some(alice_proof.clone())).eligible);
+    let mut inflated = bob_proof.clone();
+    inflated.allocation = 5;
+    assert_eq!(
+        client.try_buy_tickets_with_proof(&bob, &2, &inflated),
+        Err(Ok(Error::NotEligible))
+    );
+    client.buy_tickets_with_proof(&bob, &1, &bob_proof);
+    assert_eq!(
+        client.try_buy_tickets_with_proof(&carol, &1, &alice_proof),
+        Err(Ok(Error::NotEligible))
+    );
+    assert!(!client.check_eligibility(&carol, &None).eligible);
+
+    // Public sale: anyone, without allocations.
+    env.ledger().set_timestamp(2_000);
+    assert_eq!(
+        client.check_eligibility(&carol, &None),
+        Eligibility { eligible: true, gated: false, remaining_allocation: None }
+    );
+    client.buy_tickets(&carol, &3);
+    client.buy_tickets(&alice, &1);
+    assert_eq!(client.get_raffle().tickets_sold, 7);
+}
+
+#[test]
+fn token_gated_sale_requires_minimum_balance() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1_000);
+
+    let contract_id = env.register(Contract, ());
+    let client = ContractClient::new(&env, &contract_id);
+    let factory = env.register(MockFactory, ());
+    let admin = Address::generate(&env);
+    let creator = Address::generate(&env);
+    let holder = Address::generate(&env);
+    let outsider = Address::generate(&env);
+
+    let token_admin = Address::generate(&env);
+    let (token_addr, token_mint) = create_token(&env, &token_admin);
+    let (tikka_addr, tikka_mint) = create_token(&env, &token_admin);
+    for account in [&creator, &holder, &outsider] {
+        token_mint.mint(account, &1_000_000);
+    }
+    tikka_mint.mint(&holder, &500);
+    tikka_mint.mint(&outsider, &499);
+
+    let gate = SaleGate::Gated(GateRule::MinBalance(tikka_addr.clone(), 0));
+    assert_eq!(
+        client.try_init(&factory, &admin, &creator, &gated_config(&env, &token_addr, gate)),
+        Err(Ok(Error::InvalidParameters))
+    );
+    let gate = SaleGate::Gated(GateRule::MinBalance(tikka_addr, 500));
+    client.init(&factory, &admin, &creator, &gated_config(&env, &token_addr, gate));
+    client.deposit_prize();
+
+    assert_eq!(client.try_buy_tickets(&outsider, &1), Err(Ok(Error::NotEligible)));
+    assert_eq!(
+        client.check_eligibility(&holder, &None),
+        Eligibility { eligible: true, gated: true, remaining_allocation: None }
+    );
+    client.buy_tickets(&holder, &4);
+    assert_eq!(client.get_raffle().tickets_sold, 4);
+}
+
+#[test]
+fn per_address_cap_spans_purchases_and_tightens_to_holding_share() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1_000);
+
+    let contract_id = env.register(Contract, ());
+    let client = ContractClient::new(&env, &contract_id);
+    let factory = env.register(MockFactory, ());
+    let admin = Address::generate(&env);
+    let creator = Address::generate(&env);
+    let whale = Address::generate(&env);
+    let other = Address::generate(&env);
+
+    let token_admin = Address::generate(&env);
+    let (token_addr, token_mint) = create_token(&env, &token_admin);
+    for account in [&creator, &whale, &other] {
+        token_mint.mint(account, &1_000_000);
+    }
+
+    let mut config = gated_config(&env, &token_addr, SaleGate::Open);
+    config.max_holding_bp = 500;
+    assert_eq!(
+        client.try_init(&factory, &admin, &creator, &config),
+        Err(Ok(Error::InvalidParameters))
+    );
+    // 4 per address, tightened to 30% of 10 tickets.
+    config.max_tickets_per_address = 4;
+    config.max_holding_bp = 3_000;
+    client.init(&factory, &admin, &creator, &config);
+    client.deposit_prize();
+
+    assert_eq!(client.get_remaining_allowance(&whale), 3);
+    client.buy_tickets(&whale, &2);
+    assert_eq!(client.get_remaining_allowance(&whale), 1);
+    assert_eq!(client.try_buy_tickets(&whale, &2), Err(Ok(Error::TicketLimitExceeded)));
+    client.buy_tickets(&whale, &1);
+    assert_eq!(client.get_remaining_allowance(&whale), 0);
+    assert_eq!(client.try_buy_tickets(&whale, &1), Err(Ok(Error::TicketLimitExceeded)));
+
+    client.buy_tickets(&other, &3);
+    assert_eq!(client.get_remaining_allowance(&other), 0);
+    assert_eq!(client.get_raffle().tickets_sold, 6);
+}
+
+#[test]
+fn gifted_tickets_belong_to_recipient_and_refund_payer() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1_000);
+
+    let contract_id = env.register(Contract, ());
+    let client = ContractClient::new(&env, &contract_id);
+    let factory = env.register(MockFactory, ());
+    let admin = Address::generate(&env);
+    let creator = Address::generate(&env);
+    let payer = Address::generate(&env);
+    let recipient = Address::generate(&env);
+
+    let token_admin = Address::generate(&env);
+    let (token_addr, token_mint) = create_token(&env, &token_admin);
+    token_mint.mint(&creator, &1_000_000);
+    token_mint.mint(&payer, &1_000_000);
+    let token = soroban_sdk::token::Client::new(&env, &token_addr);
+
+    let mut config = gated_config(&env, &token_addr, SaleGate::Open);
+    config.max_tickets_per_address = 2;
+    config.refund_target = RefundTarget::Payer;
+    client.init(&factory, &admin, &creator, &config);
+    client.deposit_prize();
+
+    client.buy_tickets_for(&payer, &recipient, &2, &None);
+    assert_eq!(token.balance(&payer), 1_000_000 - MIN_TICKET_PRICE * 2);
+    assert_eq!(token.balance(&recipient), 0);
+    assert_eq!(client.get_my_tickets(&recipient), vec![&env, 1u32, 2u32]);
+    assert_eq!(client.get_my_tickets(&payer).len(), 0);
+    // The cap counts the recipient's tickets, not the payer's.
+    assert_eq!(
+        client.try_buy_tickets_for(&payer, &recipient, &1, &None),
+        Err(Ok(Error::TicketLimitExceeded))
+    );
+    assert_eq!(client.get_remaining_allowance(&payer), 2);
+
+    client.cancel_raffle(&CancelReason::CreatorCancelled);
+    assert_eq!(client.refund_ticket(&1), MIN_TICKET_PRICE);
+    assert_eq!(token.balance(&payer), 1_000_000 - MIN_TICKET_PRICE);
+    assert_eq!(token.balance(&recipient), 0);
+}
+
+#[test]
+fn gifts_into_allowlisted_raffle_take_the_recipients_proof() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().set_timestamp(1_000);
+
+    let contract_id = env.register(Contract, ());
+    let client = ContractClient::new(&env, &contract_id);
+    let factory = env.register(MockFactory, ());
+    let admin = Address::generate(&env);
+    let creator = Address::generate(&env);
+    let payer = Address::generate(&env);
+    let listed = Address::generate(&env);
+    let other = Address::generate(&env);
+
+    let token_admin = Address::generate(&env);
+    let (token_addr, token_mint) = create_token(&env, &token_admin);
+    token_mint.mint(&creator, &1_000_000);
+    token_mint.mint(&payer, &1_000_000);
+    let token = soroban_sdk::token::Client::new(&env, &token_addr);
+
+    let listed_leaf = allowlist_leaf(&env, &listed, 2);
+    let other_leaf = allowlist_leaf(&env, &other, 1);
+    let root = allowlist_parent(&env, &listed_leaf, &other_leaf);
+    let listed_proof = AllowlistProof { allocation: 2, proof: vec![&env, other_leaf] };
+
+    let gate = SaleGate::Gated(GateRule::MerkleAllowlist(root));
+    client.init(&factory, &admin, &creator, &gated_config(&env, &token_addr, gate));
+    client.deposit_prize();
+
+    assert_eq!(
+        client.try_buy_tickets_for(&payer, &listed, &1, &None),
+        Err(Ok(Error::NotEligible))
+    );
+    // The proof must be the recipient's, not the payer's.
+    assert_eq!(
+        client.try_buy_tickets_for(&payer, &other, &1, &Some(listed_proof.clone())),
+        Err(Ok(Error::NotEligible))
+    );
+    client.buy_tickets_for(&payer, &listed, &2, &Some(listed_proof));
+    assert_eq!(client.get_my_tickets(&listed), vec![&env, 1u32, 2u32]);
+    assert_eq!(token.balance(&listed), 0);
 }
@@ -1,18 +1,72 @@
-use soroban_sdk::{
-    token,
-    auth::{ContractContext, InvokerContractAuthEntry, SubContractInvocation},
-    Address, BytesN, Env, IntoVal, Symbol, Val, Vec,
-};
+use soroban_sdk::{token, Address, BytesN, Env, IntoVal, Vec};
 
-use raffle_shared::{RandomnessSource, Ticket};
+use raffle_shared::{AllowlistProof, NftTicketClient, RaffleConfig, RandomnessSource, Ticket};
 
-use crate::events::{DrawTriggered, RandomnessRequested, TicketPurchased};
+use crate::events::{DrawTriggered, RandomnessRequested, TicketNftMinted, TicketPurchased, TicketsGifted};
 use crate::{
-    request_randomness, require_not_paused, transition_to_drawing,
-    CommitRevealEntry, DataKey, Error, RaffleStatus,
+    bump_persistent, notify_factory, report_volume, request_randomness, require_not_paused,
+    require_token_enabled, transition_to_drawing, CommitRevealEntry, DataKey, Error, Raffle,
+    RaffleStatus, TicketPayment,
 };
 
-pub(crate) fn buy_tickets(env: Env, buyer: Address, quantity: u32) -> Result<u32, Error> {
+/// Resolves the per-address cap from `max_tickets_per_address` and
+/// `max_holding_bp`, the tighter of the two, `None` when neither is set.
+pub(crate) fn address_cap(config: &RaffleConfig) -> Result<Option<u32>, Error> {
+    if config.max_holding_bp > 10000 { return Err(Error::InvalidParameters); }
+    let per_address = (config.max_tickets_per_address > 0).then_some(config.max_tickets_per_address);
+    let holding = (config.max_holding_bp > 0).then(|| (config.max_tickets as u64 * config.max_holding_bp as u64 / 10000) as u32);
+    let cap = per_address.into_iter().chain(holding).min();
+    // A share that rounds down to no tickets would make the raffle unbuyable.
+    if cap == Some(0) { return Err(Error::InvalidParameters); }
+    Ok(cap)
+}
+
+fn read_cap(env: &Env) -> Option<u32> {
+    env.storage().instance().get(&DataKey::AddressTicketCap)
+}
+
+/// Rejects a purchase taking an owner already holding `owned` tickets past
+/// the per-address cap.  Counts follow the ticket owner, not the payer.
+pub(crate) fn require_within_cap(env: &Env, owned: u32, quantity: u32) -> Result<(), Error> {
+    match read_cap(env) {
+        Some(cap) if owned.saturating_add(quantity) > cap => Err(Error::TicketLimitExceeded),
+        _ => Ok(()),
+    }
+}
+
+pub(crate) fn remaining_allowance(env: &Env, owner: &Address) -> Result<u32, Error> {
+    let raffle = crate::read_raffle(env)?;
+    if raffle.status != RaffleStatus::Active { return Ok(0); }
+    let owned: u32 = env.storage().persistent().get(&DataKey::TicketCount(owner.clone())).unwrap_or(0);
+    let mut remaining = raffle.max_tickets.saturating_sub(raffle.tickets_sold);
+    if !raffle.allow_multiple { remaining = remaining.min(if owned > 0 { 0 } else { 1 }); }
+    if let Some(cap) = read_cap(env) { remaining = remaining.min(cap.saturating_sub(owned)); }
+    Ok(remaining)
+}
+
+pub(crate) fn buy_tickets(env: Env, buyer: Address, quantity: u32, proof: Option<AllowlistProof>) -> Result<u32, Error> {
+    purchase(env, buyer.clone(), buyer, quantity, proof)
+}
+
+pub(crate) fn buy_tickets_for(
+    env: Env,
+    payer: Address,
+    recipient: Address,
+    quantity: u32,
+    proof: Option<AllowlistProof>,
+) -> Result<u32, Error> {
+    purchase(env, payer, recipient, quantity, proof)
+}
+
+/// Where a ticket's refund goes: the payer recorded for it under
+/// `RefundTarget::Payer`, otherwise its owner.
+pub(crate) fn refund_recipient(env: &Env, ticket: &Ticket) -> Address {
+    env.storage().persistent().get(&DataKey::TicketPayer(ticket.id)).unwrap_or_else(|| ticket.owner.clone())
+}
+
+/// Sells `quantity` tickets paid by `payer` to `recipient`, which the sale
+/// gate, per-address limits, ticket records and NFT receipts all follow.
+fn purchase(env: Env, payer: Address, recipient: Address, quantity: u32, proof: Option<AllowlistProof>) -> Result<u32, Error> {
     let drawing_lock: bool = env.storage().instance().get(&crate::DataKey::DrawingLock).unwrap_or(false);
     if drawing_lock {
         return Err(Error::DrawingAlreadyInProgress);
@@ -24,7 +78,7 @@ pub(crate) fn buy_tickets(env: Env, buyer: Address, quantity: u32) -> Result<u32
     if quantity > raffle.max_tickets_per_tx {
         return Err(Error::ExceedsMaxTicketsPerTx);
     }
-    buyer.require_auth();
+    payer.require_auth();
     require_not_paused(&env)?;
 
     if raffle.status != RaffleStatus::Active {
@@ -39,9 +93,10 @@ pub(crate) fn buy_tickets(env: Env, buyer: Address, quantity: u32) -> Result<u32
     if !raffle.no_deadline && env.ledger().timestamp() > raffle.end_time {
         return Err(Error::RaffleExpired);
     }
+    require_token_enabled(&env, &raffle.payment_token)?;
 
     let snapshot_sold = raffle.tickets_sold;
-    let current_count: u32 = env.storage().persistent().get(&DataKey::TicketCount(buyer.clone())).unwrap_or(0);
+    let current_count: u32 = env.storage().persistent().get(&DataKey::TicketCount(recipient.clone())).unwrap_or(0);
 
     if snapshot_sold + quantity > raffle.max_tickets {
         return Err(Error::TicketsSoldOut);
@@ -49,14 +104,17 @@ pub(crate) fn buy_tickets(env: Env, buyer: Address, quantity: u32) -> Result<u32
     if !raffle.allow_multiple && (current_count > 0 || quantity > 1) {
         return Err(Error::MultipleTicketsNotAllowed);
     }
+    crate::gate::require_eligible(&env, &recipient, current_count, quantity, proof.as_ref())?;
+    require_within_cap(&env, current_count, quantity)?;
 
     let timestamp = env.ledger().timestamp();
-    let total_price = raffle.ticket_price.checked_mul(quantity as i128).ok_or(Error::InvalidParameters)?;
+    let effective_price = effective_ticket_price(&raffle)?;
+    let total_price = effective_price.checked_mul(quantity as i128).ok_or(Error::InvalidParameters)?;
     let protocol_fee = total_price.checked_mul(raffle.protocol_fee_bp as i128).ok_or(Error::ArithmeticOverflow)? / 10000;
 
     let persisted = crate::read_raffle(&env)?;
     let persisted_sold = persisted.tickets_sold;
-    let persisted_count: u32 = env.storage().persistent().get(&DataKey::TicketCount(buyer.clone())).unwrap_or(0);
+    let persisted_count: u32 = env.storage().persistent().get(&DataKey::TicketCount(recipient.clone())).unwrap_or(0);
     if persisted_sold != snapshot_sold || persisted_count != current_count {
         return Err(Error::InvalidStateTransition);
     }
@@ -64,29 +122,55 @@ pub(crate) fn buy_tickets(env: Env, buyer: Address, quantity: u32) -> Result<u32
         return Err(Error::TicketsSoldOut);
     }
 
-    if current_count == 0 {
+    // An owner whose tickets were all refunded keeps a zero count and is
+    // already listed.
+    if !env.storage().persistent().has(&DataKey::TicketCount(recipient.clone())) {
         let mut buyers: Vec<Address> = env.storage().persistent().get(&DataKey::TicketBuyers)
             .unwrap_or_else(|| Vec::new(&env));
-        buyers.push_back(buyer.clone());
+        buyers.push_back(recipient.clone());
         env.storage().persistent().set(&DataKey::TicketBuyers, &buyers);
+        bump_persistent(&env, &DataKey::TicketBuyers);
     }
 
+    let issued: u32 = env.storage().instance().get(&DataKey::TicketsIssued).unwrap_or(snapshot_sold);
+    // The fee is taken on the whole purchase; the first ticket carries the
+    // rounding remainder so the tickets' fees add up to exactly that.
+    let fee_per_ticket = protocol_fee / quantity as i128;
+    let fee_remainder = protocol_fee % quantity as i128;
     let mut ticket_ids = Vec::new(&env);
+    let record_payer = payer != recipient && env.storage().instance().has(&DataKey::RefundTarget);
     for i in 0..quantity {
         let ticket_id = snapshot_sold + i + 1;
-        let ticket = Ticket { id: ticket_id, owner: buyer.clone(), purchase_time: timestamp, ticket_number: ticket_id };
+        let ticket = Ticket { id: ticket_id, owner: recipient.clone(), purchase_time: timestamp, ticket_number: issued + i + 1 };
         env.storage().persistent().set(&DataKey::Ticket(ticket_id), &ticket);
+        bump_persistent(&env, &DataKey::Ticket(ticket_id));
+        let fee = if i == 0 { fee_per_ticket + fee_remainder } else { fee_per_ticket };
+        env.storage().persistent().set(&DataKey::TicketPaid(ticket_id), &TicketPayment { gross: effective_price, fee });
+        bump_persistent(&env, &DataKey::TicketPaid(ticket_id));
+        if record_payer {
+            env.storage().persistent().set(&DataKey::TicketPayer(ticket_id), &payer);
+            bump_persistent(&env, &DataKey::TicketPayer(ticket_id));
+        }
         ticket_ids.push_back(ticket_id);
     }
+    env.storage().instance().set(&DataKey::TicketsIssued, &(issued + quantity));
 
-    env.storage().persistent().set(&DataKey::TicketCount(buyer.clone()), &(current_count + quantity));
+    // Maintain the per-owner ticket ID index so get_my_tickets is O(1).
+    let mut owner_tickets: Vec<u32> = env.storage().persistent().get(&DataKey::OwnerTickets(recipient.clone()))
+        .unwrap_or_else(|| Vec::new(&env));
+    for tid in ticket_ids.iter() { owner_tickets.push_back(tid); }
+    env.storage().persistent().set(&DataKey::OwnerTickets(recipient.clone()), &owner_tickets);
+    bump_persistent(&env, &DataKey::OwnerTickets(recipient.clone()));
+
+    env.storage().persistent().set(&DataKey::TicketCount(recipient.clone()), &(current_count + quantity));
+    bump_persistent(&env, &DataKey::TicketCount(recipient.clone()));
     raffle.tickets_sold = snapshot_sold + quantity;
 
     if raffle.tickets_sold >= raffle.max_tickets {
         transition_to_drawing(&env, &mut raffle, timestamp)?;
         if raffle.randomness_source == RandomnessSource::External {
             let request_id = request_randomness(&env)?;
-            DrawTriggered { caller: buyer.clone(), total_tickets_sold: raffle.tickets_sold, timestamp }.publish(&env);
+            DrawTriggered { caller: payer.clone(), total_tickets_sold: raffle.tickets_sold, timestamp }.publish(&env);
             RandomnessRequested {
                 oracle: raffle.oracle_address.clone().unwrap_or(env.current_contract_address()),
                 request_id, timestamp,
@@ -96,38 +180,61 @@ pub(crate) fn buy_tickets(env: Env, buyer: Address, quantity: u32) -> Result<u32
 
     crate::write_raffle(&env, &raffle);
 
-    if let Some(factory_address) = env.storage().instance().get::<_, Address>(&DataKey::Factory) {
-        let args: Vec<Val> = (raffle.payment_token.clone(), total_price).into_val(&env);
-        env.authorize_as_current_contract(Vec::from_array(&env, [
-            InvokerContractAuthEntry::Contract(SubContractInvocation {
-                context: ContractContext {
-                    contract: factory_address.clone(),
-                    fn_name: Symbol::new(&env, "record_volume"),
-                    args: args.clone(),
-                },
-                sub_invocations: Vec::new(&env),
-            }),
-        ]));
-        env.invoke_contract::<()>(&factory_address, &Symbol::new(&env, "record_volume"), args);
-        env.invoke_contract::<()>(&factory_address, &Symbol::new(&env, "track_participant"), (buyer.clone(),).into_val(&env));
-    }
+    report_volume(&env, "record_volume", &raffle.payment_token, total_price);
+    // Reported by the raffle itself: a gift recipient never signs.
+    notify_factory(&env, "record_participation", (env.current_contract_address(), recipient.clone()).into_val(&env));
 
     let token_client = token::Client::new(&env, &raffle.payment_token);
-    let _ = token_client.try_transfer(&buyer, env.current_contract_address(), &total_price)
+    let _ = token_client.try_transfer(&payer, env.current_contract_address(), &total_price)
         .map_err(|_| Error::TokenTransferFailed)?;
 
+    // Fees stay in the raffle until it can no longer refund tickets; the
+    // factory then sweeps them to its treasury.
     if protocol_fee > 0 {
-        if let Some(treasury) = &raffle.treasury_address {
-            token_client.transfer(&env.current_contract_address(), treasury, &protocol_fee);
-        }
         let prev: i128 = env.storage().instance().get(&DataKey::AccumulatedFees).unwrap_or(0);
         env.storage().instance().set(&DataKey::AccumulatedFees, &(prev + protocol_fee));
     }
+    let gross: i128 = env.storage().instance().get(&DataKey::GrossVolume).unwrap_or(0);
+    env.storage().instance().set(&DataKey::GrossVolume, &(gross + total_price));
+
+    if payer != recipient {
+        TicketsGifted { payer, recipient: recipient.clone(), ticket_ids: ticket_ids.clone(), timestamp }.publish(&env);
+    }
+    TicketPurchased { buyer: recipient.clone(), ticket_ids: ticket_ids.clone(), quantity, ticket_price: raffle.ticket_price, effective_ticket_price: effective_price, total_paid: total_price, protocol_fee, timestamp }.publish(&env);
+    mint_receipts(&env, &raffle, &recipient, &ticket_ids, timestamp);
 
-    TicketPurchased { buyer, ticket_ids, quantity, ticket_price: raffle.ticket_price, total_paid: total_price, protocol_fee, timestamp }.publish(&env);
     Ok(raffle.tickets_sold)
 }
 
+/// Mints an NFT receipt for each ticket to its owner when the raffle has an
+/// `nft_contract`.  A failing NFT contract fails the purchase, so the NFT
+/// contract is assumed to be trusted and correctly implemented.
+fn mint_receipts(env: &Env, raffle: &Raffle, owner: &Address, ticket_ids: &Vec<u32>, timestamp: u64) {
+    let Some(nft_contract) = raffle.nft_contract.clone() else { return };
+    let nft_client = NftTicketClient::new(env, &nft_contract);
+    let raffle_id = env.current_contract_address();
+    for ticket_id in ticket_ids.iter() {
+        nft_client.mint(owner, &ticket_id, &raffle_id);
+        TicketNftMinted { recipient: owner.clone(), ticket_id, raffle_id: raffle_id.clone(), nft_contract: nft_contract.clone(), timestamp }.publish(env);
+    }
+}
+
+/// Ticket price for the next purchase, discounted while sales are within
+/// `early_bird_ticket_percentage` of `max_tickets`.
+fn effective_ticket_price(raffle: &Raffle) -> Result<i128, Error> {
+    if raffle.early_bird_ticket_percentage == 0 {
+        return Ok(raffle.ticket_price);
+    }
+    let early_bird_cap = raffle.max_tickets * raffle.early_bird_ticket_percentage / 100;
+    if raffle.tickets_sold >= early_bird_cap {
+        return Ok(raffle.ticket_price);
+    }
+    Ok(raffle.ticket_price
+        .checked_mul((10000 - raffle.early_bird_discount_bp) as i128)
+        .ok_or(Error::ArithmeticOverflow)?
+        / 10000)
+}
+
 pub(crate) fn submit_commit(env: Env, ticket_id: u32, hash: BytesN<32>) -> Result<(), Error> {
     let raffle = crate::read_raffle(&env)?;
 
@@ -146,6 +253,7 @@ pub(crate) fn submit_commit(env: Env, ticket_id: u32, hash: BytesN<32>) -> Resul
         committer: ticket.owner,
         hash,
     });
+    bump_persistent(&env, &DataKey::CommitEntry(ticket_id));
 
     Ok(())
 }
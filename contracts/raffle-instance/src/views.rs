@@ -1,28 +1,138 @@
-use soroban_sdk::{Env, Vec};
+use soroban_sdk::{Address, BytesN, Env, Vec};
 
-use raffle_shared::FairnessData;
+use raffle_shared::constants::{FAIRNESS_VERSION_SEED256, FAIRNESS_VERSION_SEED64};
+use raffle_shared::{
+    FairnessData, FeeQuote, PortfolioEntry, RaffleOutcome, RaffleStatus, RaffleSummary, SelectionAlgorithm,
+};
 
-use crate::{read_raffle, DataKey, Error, FairnessMetadata};
+use crate::randomness::widen_legacy_seed;
+use crate::{
+    calculate_tier_prize, get_ticket_owner, read_fairness_record, read_raffle, DataKey, Error,
+    FairnessRecord, PendingParamChange,
+};
 
 pub(crate) fn get_raffle(env: Env) -> Result<crate::Raffle, Error> {
     read_raffle(&env)
 }
 
+pub(crate) fn get_summary(env: Env) -> Result<RaffleSummary, Error> {
+    let raffle = read_raffle(&env)?;
+    Ok(RaffleSummary {
+        raffle: env.current_contract_address(),
+        status: raffle.status,
+        end_time: raffle.end_time,
+        no_deadline: raffle.no_deadline,
+        ticket_price: raffle.ticket_price,
+        tickets_sold: raffle.tickets_sold,
+        max_tickets: raffle.max_tickets,
+        payment_token: raffle.payment_token,
+    })
+}
+
+pub(crate) fn get_outcome(env: Env) -> Result<RaffleOutcome, Error> {
+    let raffle = read_raffle(&env)?;
+    // Raffles created before volume tracking fall back to list price.
+    let gross_volume: i128 = env.storage().instance().get(&DataKey::GrossVolume)
+        .unwrap_or(raffle.ticket_price * raffle.tickets_sold as i128);
+    let seed = read_fairness_record(&env).map(|record| match record {
+        FairnessRecord::V1(meta) => BytesN::from_array(&env, &widen_legacy_seed(meta.seed)),
+        FairnessRecord::V2(meta) => meta.seed,
+    });
+    Ok(RaffleOutcome {
+        creator: raffle.creator,
+        payment_token: raffle.payment_token,
+        prize_amount: raffle.prize_amount,
+        tickets_sold: raffle.tickets_sold,
+        gross_volume,
+        winners: raffle.winners,
+        seed,
+        randomness_source: raffle.randomness_source,
+        selection_algorithm: raffle.selection_algorithm,
+        final_status: raffle.status,
+        created_at: env.storage().instance().get(&DataKey::CreatedAt).unwrap_or(0),
+        finalized_at: raffle.finalized_at,
+    })
+}
+
+pub(crate) fn get_portfolio_entry(env: Env, participant: Address) -> Result<PortfolioEntry, Error> {
+    let raffle = read_raffle(&env)?;
+    let ticket_count: u32 = env.storage().persistent().get(&DataKey::TicketCount(participant.clone())).unwrap_or(0);
+
+    let mut winning_tiers = Vec::new(&env);
+    let mut claimable_amounts = Vec::new(&env);
+    for (tier, winner) in raffle.winners.iter().enumerate() {
+        if winner != participant { continue; }
+        let tier = tier as u32;
+        let claimed = raffle.claimed_winners.get(tier).unwrap_or(true);
+        let amount = if claimed || raffle.status != RaffleStatus::Finalized { 0 } else { calculate_tier_prize(&raffle, tier)? };
+        winning_tiers.push_back(tier);
+        claimable_amounts.push_back(amount);
+    }
+
+    // Mirrors the gate in `refund_ticket`, including a scheduled admin cancel
+    // and the notice window of a pending parameter change.
+    let refunds_open = raffle.status == RaffleStatus::Cancelled
+        || raffle.status == RaffleStatus::Failed
+        || env.storage().instance().has(&DataKey::PendingAdminCancel)
+        || (raffle.status == RaffleStatus::Active && env.storage().instance().has(&DataKey::PendingParamChange));
+    let mut refundable_tickets = Vec::new(&env);
+    if refunds_open {
+        let owned: Vec<u32> = env.storage().persistent().get(&DataKey::OwnerTickets(participant.clone())).unwrap_or_else(|| Vec::new(&env));
+        for id in owned.iter() {
+            let still_owned = get_ticket_owner(&env, id).is_some_and(|owner| owner == participant);
+            if still_owned && !env.storage().persistent().has(&DataKey::TicketRefunded(id)) {
+                refundable_tickets.push_back(id);
+            }
+        }
+    }
+
+    Ok(PortfolioEntry {
+        raffle: env.current_contract_address(),
+        status: raffle.status,
+        ticket_count,
+        winning_tiers,
+        claimable_amounts,
+        refundable_tickets,
+    })
+}
+
 pub(crate) fn get_fairness_data(env: Env) -> Result<FairnessData, Error> {
-    let meta: FairnessMetadata = env.storage().persistent().get(&DataKey::RandomnessSeed).ok_or(Error::InvalidStatus)?;
+    let record = read_fairness_record(&env).ok_or(Error::InvalidStatus)?;
     let raffle = read_raffle(&env)?;
     let mut ticket_ids = Vec::new(&env);
     for i in 1..=raffle.tickets_sold { ticket_ids.push_back(i); }
-    Ok(FairnessData {
-        seed: meta.seed,
-        randomness_source: meta.randomness_source,
-        ticket_ids,
-        winning_ticket_indices: meta.winning_ticket_indices,
-        draw_timestamp: meta.draw_timestamp,
-        draw_sequence: meta.draw_sequence,
+    Ok(match record {
+        FairnessRecord::V1(meta) => FairnessData {
+            version: FAIRNESS_VERSION_SEED64,
+            seed: BytesN::from_array(&env, &widen_legacy_seed(meta.seed)),
+            randomness_source: meta.randomness_source,
+            selection_algorithm: SelectionAlgorithm::LcgRejectionV1,
+            ticket_ids,
+            winning_ticket_indices: meta.winning_ticket_indices,
+            draw_timestamp: meta.draw_timestamp,
+            draw_sequence: meta.draw_sequence,
+        },
+        FairnessRecord::V2(meta) => FairnessData {
+            version: FAIRNESS_VERSION_SEED256,
+            seed: meta.seed,
+            randomness_source: meta.randomness_source,
+            selection_algorithm: meta.selection_algorithm,
+            ticket_ids,
+            winning_ticket_indices: meta.winning_ticket_indices,
+            draw_timestamp: meta.draw_timestamp,
+            draw_sequence: meta.draw_sequence,
+        },
     })
 }
 
+pub(crate) fn get_pending_param_change(env: Env) -> Option<PendingParamChange> {
+    env.storage().instance().get(&DataKey::PendingParamChange)
+}
+
+pub(crate) fn get_fee_quote(env: Env) -> Option<FeeQuote> {
+    env.storage().instance().get(&DataKey::FeeQuote)
+}
+
 pub(crate) fn is_paused(env: Env) -> bool {
     env.storage().instance().get(&DataKey::Paused).unwrap_or(false)
 }
@@ -34,3 +144,12 @@ pub(crate) fn is_ticket_sales_paused(env: Env) -> bool {
 pub(crate) fn get_accumulated_fees(env: Env) -> i128 {
     env.storage().instance().get(&DataKey::AccumulatedFees).unwrap_or(0)
 }
+
+/// Return all ticket IDs owned by `owner`.
+///
+/// Uses the `OwnerTickets` index maintained during `buy_tickets` for an
+/// O(1) read.  Falls back to an empty Vec when the address has never
+/// purchased a ticket.
+pub(crate) fn get_my_tickets(env: Env, owner: Address) -> Vec<u32> {
+    env.storage().persistent().get(&DataKey::OwnerTickets(owner)).unwrap_or_else(|| Vec::new(&env))
+}
@@ -21,13 +21,24 @@ pub const MAX_TICKETS_LIMIT: u32 = 100_000;
 /// Hard cap on the number of prize tiers per raffle.
 pub const MAX_PRIZES: u32 = 100;
 
-/// Minimum ticket price in the payment token's base unit (stroops / smallest
-/// denomination).  Prevents dust-amount raffles that would be uneconomical.
+/// Reference ticket price floor for a 7-decimal token, in its base unit.
+/// The factory enforces each token's `SupportedToken::min_ticket_price`
+/// instead.
 pub const MIN_TICKET_PRICE: i128 = 10_000;
 
-/// Maximum allowed prize pool.  Prevents i128 overflow in prize calculations.
+/// Maximum allowed prize pool.  Prevents i128 overflow in prize calculations
+/// and caps every token's `SupportedToken::max_prize_amount`.
 pub const MAX_PRIZE_AMOUNT: i128 = 1_000_000_000_000_000_000_000; // 1e21
 
+/// Maximum number of payment tokens the factory registry may list.
+pub const MAX_SUPPORTED_TOKENS: u32 = 50;
+
+/// Maximum number of volume fee tiers per payment token.
+pub const MAX_FEE_TIERS: u32 = 10;
+
+/// Maximum number of promotional fee holidays the factory keeps.
+pub const MAX_FEE_HOLIDAYS: u32 = 10;
+
 // --- Timing constants -------------------------------------------------------
 
 /// Default delay (seconds) between raffle finalization and when winners may
@@ -48,18 +59,72 @@ pub const MAX_SWAP_DEADLINE_SECONDS: u64 = 3_600;
 /// emergency withdrawal is permitted.  Equals 90 days (7 776 000 s).
 pub const EMERGENCY_WITHDRAW_DELAY_SECONDS: u64 = 90 * 24 * 3_600; // 7_776_000
 
+// --- Fairness record versions ----------------------------------------------
+
+/// `FairnessData::version` for draws recorded with a 64-bit seed.  The seed is
+/// reported widened (big-endian, zero-padded to 32 bytes).
+pub const FAIRNESS_VERSION_SEED64: u32 = 1;
+
+/// `FairnessData::version` for draws recorded with a full 256-bit seed.
+pub const FAIRNESS_VERSION_SEED256: u32 = 2;
+
 // --- Factory constants ------------------------------------------------------
 
 /// Timelock delay (seconds) before a proposed admin operation may be executed.
 /// Equals 48 hours, giving users time to react to protocol changes.
 pub const TIMELOCK_DELAY_SECONDS: u64 = 172_800;
 
+/// Upper bound for a per-kind op delay set through `SetOpDelay` (30 days).
+pub const MAX_OP_DELAY_SECONDS: u64 = 2_592_000;
+
+/// Lower bound for a per-kind op delay set through `SetOpDelay` (1 day),
+/// except for the pause switches, which may run at once.
+pub const MIN_OP_DELAY_SECONDS: u64 = 86_400;
+
+/// Seconds after its effective time that a queued op stays executable
+/// before it goes stale, unless changed through `SetOpExpiry` (14 days).
+pub const DEFAULT_OP_EXPIRY_SECONDS: u64 = 1_209_600;
+
+/// Bounds for the op expiry window (1 to 90 days).
+pub const MIN_OP_EXPIRY_SECONDS: u64 = 86_400;
+pub const MAX_OP_EXPIRY_SECONDS: u64 = 7_776_000;
+
+/// Maximum number of ops one governance proposal may bundle.
+pub const MAX_OPS_PER_PROPOSAL: u32 = 10;
+
 /// Factory creates a state checkpoint every `CHECKPOINT_INTERVAL` raffles.
 pub const CHECKPOINT_INTERVAL: u32 = 1_000;
 
 /// Maximum protocol fee in basis points (20 %).
 pub const MAX_PROTOCOL_FEE_BP: u32 = 2_000;
 
+/// Maximum number of raffle instances one `upgrade_instances` or
+/// `migrate_instances` call may touch.
+pub const MAX_INSTANCE_UPGRADE_BATCH: u32 = 25;
+
+/// Maximum number of raffle instances one factory `sweep_fees` call may
+/// touch.
+pub const MAX_FEE_SWEEP_BATCH: u32 = 50;
+
+// --- Storage TTL policy -----------------------------------------------------
+
+/// Ledgers closed per day at ~5-second close times.
+pub const LEDGERS_PER_DAY: u32 = 17_280;
+
+/// Instance storage (raffle state, factory config) is bumped on access once
+/// its remaining TTL falls below this many ledgers (7 days).
+pub const INSTANCE_TTL_THRESHOLD: u32 = 7 * LEDGERS_PER_DAY;
+
+/// TTL, in ledgers, instance storage is extended to when bumped (30 days).
+pub const INSTANCE_TTL_EXTEND_TO: u32 = 30 * LEDGERS_PER_DAY;
+
+/// Persistent entries (tickets, owner indexes, registry slots) are bumped on
+/// access once their remaining TTL falls below this many ledgers (14 days).
+pub const PERSISTENT_TTL_THRESHOLD: u32 = 14 * LEDGERS_PER_DAY;
+
+/// TTL, in ledgers, persistent entries are extended to when bumped (60 days).
+pub const PERSISTENT_TTL_EXTEND_TO: u32 = 60 * LEDGERS_PER_DAY;
+
 // --- Pagination defaults ----------------------------------------------------
 
 /// Default number of items returned by paginated queries.
@@ -2,6 +2,7 @@
 #![cfg_attr(not(test), deny(clippy::unwrap_used))]
 
 pub mod constants;
+pub mod roles;
 
 use soroban_sdk::{contracttype, Address, BytesN, String, Vec};
 
@@ -67,6 +68,23 @@ pub enum RandomnessSource {
     CommitReveal = 2,
 }
 
+/// Algorithm that maps a draw seed to winning ticket indices.
+///
+/// Fixed at raffle creation and recorded with the draw so outcomes can be
+/// replayed exactly.  Discriminants are frozen: never change the behaviour of
+/// an existing id, add a new variant instead.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[contracttype]
+pub enum SelectionAlgorithm {
+    /// 64-bit LCG with rejection sampling (original on-chain algorithm).
+    /// Kept only to replay legacy records; new raffles cannot select it.
+    LcgRejectionV1 = 1,
+    /// SHA-256 counter-mode stream with rejection sampling.
+    Sha256CounterV2 = 2,
+    /// Soroban host PRNG reseeded from the draw seed.
+    EnvPrng = 3,
+}
+
 /// Type/classification of randomness mechanism requested or received.
 #[derive(Clone, PartialEq, Eq, Debug)]
 #[contracttype]
@@ -116,7 +134,8 @@ pub struct RaffleConfig {
     /// Charged at two points: ticket purchase and prize claim.
     /// See docs/FEE_MODEL.md for full fee model details.
     pub protocol_fee_bp: u32,
-    /// Optional treasury recipient address for protocol fees.
+    /// Factory treasury at creation, kept for reference.  Accumulated fees
+    /// are swept to the factory's current treasury.
     pub treasury_address: Option<Address>,
     /// Optional router contract used when swap-based flows are enabled.
     pub swap_router: Option<Address>,
@@ -134,6 +153,24 @@ pub struct RaffleConfig {
     pub early_bird_ticket_percentage: u32,
     /// The discount amount specified in basis points.
     pub early_bird_discount_bp: u32,
+    /// Optional token the prize is deposited and paid in.
+    /// Defaults to `payment_token` when `None`.
+    pub prize_token: Option<Address>,
+    /// Optional NFT contract that mints a receipt for every ticket sold.
+    pub nft_contract: Option<Address>,
+    /// Winner-selection algorithm used for the draw; cannot change after creation.
+    pub selection_algorithm: SelectionAlgorithm,
+    /// Allowlist or token-holding requirement for buyers, `SaleGate::Open`
+    /// for none.
+    pub sale_gate: SaleGate,
+    /// Most tickets one address may hold across purchases (0 for no cap).
+    pub max_tickets_per_address: u32,
+    /// Most of `max_tickets` one address may hold, in basis points (0 for
+    /// no cap).
+    pub max_holding_bp: u32,
+    /// Who is refunded for tickets bought for someone else with
+    /// `buy_tickets_for`.
+    pub refund_target: RefundTarget,
 }
 
 impl RaffleConfig {
@@ -148,6 +185,67 @@ impl RaffleConfig {
     }
 }
 
+/// Who receives the refund of a ticket bought for another address.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+#[repr(u32)]
+pub enum RefundTarget {
+    /// The ticket owner, as for tickets bought for oneself.
+    Owner = 0,
+    /// The address that paid for the ticket.
+    Payer = 1,
+}
+
+/// Requirement a buyer must meet while a raffle's sale is gated.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum GateRule {
+    /// Merkle root over `sha256(buyer XDR || allocation as big-endian u32)`
+    /// leaves, with sorted-pair hashing.  The allocation caps the tickets a
+    /// buyer may hold while gated.
+    MerkleAllowlist(BytesN<32>),
+    /// Minimum balance of a token, for example `tikka_token`, checked on
+    /// each purchase.  A soft gate: the balance is read at purchase time
+    /// only, so one balance can be moved between addresses to qualify each
+    /// in turn, and tickets are kept if it later drops.
+    MinBalance(Address, i128),
+}
+
+/// Sale gate of a raffle, fixed at creation.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum SaleGate {
+    /// Anyone may buy.
+    Open,
+    /// Only eligible buyers may buy, for the whole sale.
+    Gated(GateRule),
+    /// Only eligible buyers may buy until the timestamp, after which the
+    /// sale opens to everyone.
+    Presale(GateRule, u64),
+}
+
+/// A buyer's allowlist entry with its Merkle proof.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct AllowlistProof {
+    pub allocation: u32,
+    /// Sibling hashes from the leaf up to the root.
+    pub proof: Vec<BytesN<32>>,
+}
+
+/// Whether an address passes a raffle's sale gate right now.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct Eligibility {
+    pub eligible: bool,
+    /// Whether the gate applies now; false once the presale has ended or
+    /// when the raffle has no gate.
+    pub gated: bool,
+    /// Tickets the address may still buy under its allowlist allocation.
+    /// `None` when the gate does not cap it.
+    pub remaining_allocation: Option<u32>,
+}
+
 #[derive(Clone)]
 #[contracttype]
 pub struct Ticket {
@@ -165,10 +263,14 @@ pub struct Ticket {
 #[derive(Clone)]
 #[contracttype]
 pub struct FairnessData {
-    /// Seed value used to derive final winner indices.
-    pub seed: u64,
+    /// Record layout version (see `constants::FAIRNESS_VERSION_*`).
+    pub version: u32,
+    /// 256-bit seed used to derive final winner indices.
+    pub seed: BytesN<32>,
     /// Source used to generate the randomness seed.
     pub randomness_source: RandomnessSource,
+    /// Algorithm that mapped the seed to winning indices.
+    pub selection_algorithm: SelectionAlgorithm,
     /// Ordered ticket identifiers considered in the draw.
     pub ticket_ids: Vec<u32>,
     /// Computed winning indices into `ticket_ids`.
@@ -201,6 +303,117 @@ pub struct PageResultRaffles {
     pub has_more: bool,
 }
 
+/// Lobby-card view of a raffle, served by the factory's status index.
+#[derive(Clone)]
+#[contracttype]
+pub struct RaffleSummary {
+    /// Raffle instance contract address.
+    pub raffle: Address,
+    /// Current lifecycle status.
+    pub status: RaffleStatus,
+    /// Unix timestamp when ticket sales close (ignored when `no_deadline` is true).
+    pub end_time: u64,
+    /// True when the raffle has no hard end timestamp.
+    pub no_deadline: bool,
+    /// Price per ticket in the payment token's base units.
+    pub ticket_price: i128,
+    /// Tickets sold so far.
+    pub tickets_sold: u32,
+    /// Maximum number of tickets that can ever be sold.
+    pub max_tickets: u32,
+    /// Token used to buy tickets.
+    pub payment_token: Address,
+}
+
+/// Paginated raffle summary query result.
+#[derive(Clone)]
+#[contracttype]
+pub struct PageResultRaffleSummaries {
+    /// Returned raffle summaries for the current page.
+    pub items: Vec<RaffleSummary>,
+    /// Total number of raffles matching the query.
+    pub total: u32,
+    /// True when more records are available after this page.
+    pub has_more: bool,
+}
+
+/// Final outcome of a raffle, read by the factory just before its storage is
+/// wiped so history and audit links survive cleanup.
+#[derive(Clone)]
+#[contracttype]
+pub struct RaffleOutcome {
+    /// Raffle creator.
+    pub creator: Address,
+    /// Token used for tickets and prizes.
+    pub payment_token: Address,
+    /// Total prize pool.
+    pub prize_amount: i128,
+    /// Tickets sold over the raffle's lifetime.
+    pub tickets_sold: u32,
+    /// Sum paid by buyers across all purchases, before refunds.
+    pub gross_volume: i128,
+    /// Winner per prize tier; empty when no draw happened.
+    pub winners: Vec<Address>,
+    /// Draw seed, widened to 256 bits for legacy records; `None` without a draw.
+    pub seed: Option<BytesN<32>>,
+    /// Randomness source used for the draw.
+    pub randomness_source: RandomnessSource,
+    /// Winner-selection algorithm used for the draw.
+    pub selection_algorithm: SelectionAlgorithm,
+    /// Status at cleanup time (`Claimed`, `Cancelled` or `Failed`).
+    pub final_status: RaffleStatus,
+    /// Unix timestamp of instance initialization (0 for raffles that predate it).
+    pub created_at: u64,
+    /// Unix timestamp of finalization, if the raffle was drawn.
+    pub finalized_at: Option<u64>,
+}
+
+/// One raffle in a participant's portfolio, as reported by the instance.
+#[derive(Clone)]
+#[contracttype]
+pub struct PortfolioEntry {
+    /// Raffle instance contract address.
+    pub raffle: Address,
+    /// Current lifecycle status.
+    pub status: RaffleStatus,
+    /// Tickets the participant bought in this raffle.
+    pub ticket_count: u32,
+    /// Prize tiers the participant won.
+    pub winning_tiers: Vec<u32>,
+    /// Unclaimed prize per entry of `winning_tiers` (0 once claimed).
+    /// Subject to the raffle's claim lockup.
+    pub claimable_amounts: Vec<i128>,
+    /// Ticket IDs the participant may refund right now.
+    pub refundable_tickets: Vec<u32>,
+}
+
+/// Paginated participant portfolio query result.
+#[derive(Clone)]
+#[contracttype]
+pub struct PageResultPortfolio {
+    /// Portfolio entries for the current page.
+    pub items: Vec<PortfolioEntry>,
+    /// Total number of raffles the participant has entered.
+    pub total: u32,
+    /// True when more records are available after this page.
+    pub has_more: bool,
+}
+
+/// Progress of a resumable `wipe_storage_batch` run on a raffle instance.
+///
+/// Items are counted as one per sold ticket followed by one per distinct
+/// buyer; instance-level keys are removed together with the last item.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct WipeProgress {
+    /// Cursor to pass to the next batch.
+    pub next_cursor: u32,
+    /// Total number of items the wipe walks through.
+    pub total_items: u32,
+    /// True once the instance holds no raffle state anymore.
+    pub done: bool,
+}
+
 /// Paginated ticket query result.
 #[derive(Clone)]
 #[contracttype]
@@ -213,14 +426,394 @@ pub struct PageResultTickets {
     pub has_more: bool,
 }
 
-/// Administrative operations that can be timelocked or proposed.
+/// Factory registry entry for a payment token raffles may be priced in.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct SupportedToken {
+    /// Lowest `ticket_price` accepted by `create_raffle`, in base units.
+    pub min_ticket_price: i128,
+    /// Highest `prize_amount` accepted by `create_raffle`, in base units.
+    pub max_prize_amount: i128,
+    /// Decimals reported by the token contract.
+    pub decimals: u32,
+    /// Disabled tokens cannot back new raffles or new ticket sales.
+    pub enabled: bool,
+}
+
+/// Which volume a `FeeTier` threshold is measured against.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+#[repr(u32)]
+pub enum VolumeBasis {
+    /// Ticket volume across every raffle in the asset.
+    Protocol = 0,
+    /// Ticket volume across the creator's raffles in the asset.
+    Creator = 1,
+}
+
+/// Fee discount unlocked once a volume threshold is reached.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct FeeTier {
+    pub basis: VolumeBasis,
+    /// Volume, in the asset's base units, at which the tier applies.
+    pub min_volume: i128,
+    pub fee_bp: u32,
+}
+
+/// Promotional fee applied to raffles created in `[start_time, end_time)`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct FeeHoliday {
+    pub start_time: u64,
+    pub end_time: u64,
+    pub fee_bp: u32,
+}
+
+/// Rule that set a raffle's protocol fee.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+#[repr(u32)]
+pub enum FeeSource {
+    /// The factory-wide `ProtocolFeeBP`.
+    Default = 0,
+    /// The creator's partner fee schedule.
+    Partner = 1,
+    /// A volume tier of the payment token.
+    VolumeTier = 2,
+    /// A promotional fee holiday.
+    Holiday = 3,
+    /// The reputation fee of a creator in good standing.
+    Reputation = 4,
+}
+
+/// How the factory resolved a raffle's protocol fee at creation.  Stored
+/// on the instance so the fee can be audited later.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct FeeQuote {
+    /// Factory-wide fee at creation.
+    pub base_fee_bp: u32,
+    /// The creator's partner fee, if one was set.
+    pub partner_fee_bp: Option<u32>,
+    /// Fee written to the raffle's `protocol_fee_bp`.
+    pub fee_bp: u32,
+    pub source: FeeSource,
+    /// Protocol volume in the payment token at creation.
+    pub protocol_volume: i128,
+    /// Creator volume in the payment token at creation.
+    pub creator_volume: i128,
+    pub resolved_at: u64,
+}
+
+/// Where a slashed creator bond goes.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+#[repr(u32)]
+pub enum BondSlashTarget {
+    /// Split evenly over the tickets sold, paid out with each ticket refund.
+    Holders = 0,
+    /// Sent to the factory treasury.
+    Treasury = 1,
+}
+
+/// Factory policy sizing the bond a creator posts with the prize.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct BondPolicy {
+    /// Bond as basis points of `prize_amount`.  Zero with a zero `min_bond`
+    /// disables bonds.
+    pub bond_bp: u32,
+    /// Floor applied to non-partner bonds.
+    pub min_bond: i128,
+    /// Bond rate for whitelisted partners, at most `bond_bp`.  Zero exempts
+    /// them; no floor applies.
+    pub partner_bond_bp: u32,
+    pub slash_target: BondSlashTarget,
+}
+
+/// Lifecycle of a raffle's creator bond.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[contracttype]
+#[repr(u32)]
+pub enum BondStatus {
+    /// Required, to be posted with the prize.
+    Pending = 0,
+    /// Held by the raffle.
+    Posted = 1,
+    /// Forfeited by a creator cancellation after sales.
+    Slashed = 2,
+    /// Paid back to the creator.
+    Returned = 3,
+}
+
+/// Creator bond of one raffle, in its payment token.  Set by the factory at
+/// creation and tracked by the instance.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct CreatorBond {
+    pub amount: i128,
+    pub slash_target: BondSlashTarget,
+    /// Factory treasury at creation; receives treasury slashes and dust.
+    pub treasury: Address,
+    pub status: BondStatus,
+}
+
+/// Track record the factory keeps for each creator, fed by instance
+/// callbacks.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[contracttype]
+pub struct CreatorProfile {
+    pub raffles_created: u32,
+    /// Raffles that reached `Finalized`.
+    pub completed: u32,
+    pub cancelled_by_creator: u32,
+    /// Raffles that failed or were cancelled for missing `min_tickets`.
+    pub failed_min_tickets: u32,
+    /// Distinct buyers other than the creator, summed over completed
+    /// raffles.
+    pub completed_buyers: u64,
+    /// Ticket volume summed across payment tokens, in raw token units.
+    pub total_volume: i128,
+    /// Prize amounts claimed by winners, in raw token units.
+    pub prizes_paid: i128,
+    /// Creation-to-finalization seconds summed over completed raffles.
+    pub total_finalize_seconds: u64,
+    pub avg_finalize_seconds: u64,
+}
+
+/// Outcome an instance reports to its factory for the creator's profile.
+/// Completions and failures arrive through status changes instead.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub enum CreatorOutcome {
+    Cancelled(CancelReason),
+    PrizePaid(i128),
+}
+
+/// Factory policy granting creators in good standing a waived creation
+/// rate limit, a smaller bond and a reputation fee.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[contracttype]
+pub struct ReputationPolicy {
+    /// Completed raffles needed for good standing.  Zero disables the
+    /// policy.
+    pub min_completed: u32,
+    /// Buyers other than the creator, summed over completed raffles, also
+    /// needed for good standing, so self-bought raffles do not count.
+    pub min_buyers: u32,
+    /// Highest share, in basis points of ended raffles, that may have been
+    /// cancelled by the creator or failed for `min_tickets`.
+    pub max_failure_bp: u32,
+    pub waive_rate_limit: bool,
+    /// Cut off the bond, in basis points of it.
+    pub bond_discount_bp: u32,
+    /// Fee applied when lower than the otherwise resolved fee.
+    pub fee_bp: u32,
+}
+
+/// Administrative operations queued behind the factory timelock.
 #[derive(Clone)]
 #[contracttype]
 pub enum AdminOp {
-    /// Update protocol configuration entry `u32` with a new address value.
+    /// Set the protocol fee (basis points) and treasury for new raffles.
     SetConfig(u32, Address),
     /// Rotate target contract WASM hash for upgrades.
     UpdateWasmHash(BytesN<32>),
+    /// Upgrade the live raffle instances with these stable IDs to a WASM hash.
+    UpgradeInstances(Vec<u32>, BytesN<32>),
+    /// Upgrade the factory's own code to a WASM hash.
+    UpgradeFactory(BytesN<32>),
+    /// Grant a timelocked role (see `Role::grant_is_timelocked`).
+    GrantRole(roles::Role, Address),
+    /// Revoke a role whose removal is timelocked (Guardian).
+    RevokeRole(roles::Role, Address),
+    /// Number of guardian vetoes needed to drop a queued op.
+    SetGuardianThreshold(u32),
+    /// Minimum seconds between raffles from one non-whitelisted creator.
+    SetCreationDelay(u64),
+    /// Exempt (or stop exempting) a partner from the creation rate limit.
+    SetWhitelistStatus(Address, bool),
+    /// Stop raffle creation on the factory.
+    PauseFactory,
+    /// Resume raffle creation on the factory.
+    UnpauseFactory,
+    /// Pause the raffle instance at this address.
+    PauseInstance(Address),
+    /// Unpause the raffle instance at this address.
+    UnpauseInstance(Address),
+    /// Sweep `i128` units of token `Address` from the factory to a recipient.
+    RescueTokens(Address, Address, i128),
+    /// Change the delay, in seconds, applied to ops of one kind.
+    SetOpDelay(OpKind, u64),
+    /// Change how long an op stays executable after its delay elapses.
+    SetOpExpiry(u64),
+    /// List a payment token, or replace its limits.
+    SetSupportedToken(Address, SupportedToken),
+    /// Enable or disable a listed payment token.
+    SetTokenEnabled(Address, bool),
+    /// Set (`Some`) or clear (`None`) a creator's partner fee in basis points.
+    SetPartnerFee(Address, Option<u32>),
+    /// Replace the volume fee tiers of a payment token.
+    SetFeeTiers(Address, Vec<FeeTier>),
+    /// Replace the list of promotional fee holidays.
+    SetFeeHolidays(Vec<FeeHoliday>),
+    /// Replace the creator bond policy for new raffles.
+    SetBondPolicy(BondPolicy),
+    /// Replace the reputation policy for new raffles.
+    SetReputationPolicy(ReputationPolicy),
+}
+
+/// Discriminant of an `AdminOp`, used to configure per-kind delays and to
+/// summarise queued ops for review tooling.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[contracttype]
+#[repr(u32)]
+pub enum OpKind {
+    SetConfig = 0,
+    UpdateWasmHash = 1,
+    UpgradeInstances = 2,
+    UpgradeFactory = 3,
+    GrantRole = 4,
+    RevokeRole = 5,
+    SetGuardianThreshold = 6,
+    SetCreationDelay = 7,
+    SetWhitelistStatus = 8,
+    PauseFactory = 9,
+    UnpauseFactory = 10,
+    PauseInstance = 11,
+    UnpauseInstance = 12,
+    RescueTokens = 13,
+    SetOpDelay = 14,
+    SetOpExpiry = 15,
+    SetSupportedToken = 16,
+    SetTokenEnabled = 17,
+    SetPartnerFee = 18,
+    SetFeeTiers = 19,
+    SetFeeHolidays = 20,
+    SetBondPolicy = 21,
+    SetReputationPolicy = 22,
+}
+
+/// Every op kind, in discriminant order.
+pub const ALL_OP_KINDS: [OpKind; 23] = [
+    OpKind::SetConfig,
+    OpKind::UpdateWasmHash,
+    OpKind::UpgradeInstances,
+    OpKind::UpgradeFactory,
+    OpKind::GrantRole,
+    OpKind::RevokeRole,
+    OpKind::SetGuardianThreshold,
+    OpKind::SetCreationDelay,
+    OpKind::SetWhitelistStatus,
+    OpKind::PauseFactory,
+    OpKind::UnpauseFactory,
+    OpKind::PauseInstance,
+    OpKind::UnpauseInstance,
+    OpKind::RescueTokens,
+    OpKind::SetOpDelay,
+    OpKind::SetOpExpiry,
+    OpKind::SetSupportedToken,
+    OpKind::SetTokenEnabled,
+    OpKind::SetPartnerFee,
+    OpKind::SetFeeTiers,
+    OpKind::SetFeeHolidays,
+    OpKind::SetBondPolicy,
+    OpKind::SetReputationPolicy,
+];
+
+impl OpKind {
+    /// Delay applied until `SetOpDelay` overrides it.  Pausing, unpausing
+    /// and toggling a token are incident response and run at once;
+    /// everything else waits out `TIMELOCK_DELAY_SECONDS`.
+    pub fn default_delay(&self) -> u64 {
+        match self {
+            OpKind::PauseFactory
+            | OpKind::UnpauseFactory
+            | OpKind::PauseInstance
+            | OpKind::UnpauseInstance
+            | OpKind::SetTokenEnabled => 0,
+            _ => constants::TIMELOCK_DELAY_SECONDS,
+        }
+    }
+
+    /// Whether the delay of this kind is pinned to its default.  The
+    /// queue's own settings cannot be loosened faster than the timelock.
+    pub fn delay_is_fixed(&self) -> bool {
+        matches!(self, OpKind::SetOpDelay | OpKind::SetOpExpiry)
+    }
+
+    /// Shortest delay `SetOpDelay` may give this kind.  Only the pause
+    /// switches (factory, instance and payment token) may run at once, so
+    /// a compromised admin cannot strip the timelock from anything else.
+    pub fn min_delay(&self) -> u64 {
+        if self.default_delay() == 0 {
+            0
+        } else {
+            constants::MIN_OP_DELAY_SECONDS
+        }
+    }
+}
+
+impl AdminOp {
+    /// Returns the kind of this op.
+    pub fn kind(&self) -> OpKind {
+        match self {
+            AdminOp::SetConfig(..) => OpKind::SetConfig,
+            AdminOp::UpdateWasmHash(..) => OpKind::UpdateWasmHash,
+            AdminOp::UpgradeInstances(..) => OpKind::UpgradeInstances,
+            AdminOp::UpgradeFactory(..) => OpKind::UpgradeFactory,
+            AdminOp::GrantRole(..) => OpKind::GrantRole,
+            AdminOp::RevokeRole(..) => OpKind::RevokeRole,
+            AdminOp::SetGuardianThreshold(..) => OpKind::SetGuardianThreshold,
+            AdminOp::SetCreationDelay(..) => OpKind::SetCreationDelay,
+            AdminOp::SetWhitelistStatus(..) => OpKind::SetWhitelistStatus,
+            AdminOp::PauseFactory => OpKind::PauseFactory,
+            AdminOp::UnpauseFactory => OpKind::UnpauseFactory,
+            AdminOp::PauseInstance(..) => OpKind::PauseInstance,
+            AdminOp::UnpauseInstance(..) => OpKind::UnpauseInstance,
+            AdminOp::RescueTokens(..) => OpKind::RescueTokens,
+            AdminOp::SetOpDelay(..) => OpKind::SetOpDelay,
+            AdminOp::SetOpExpiry(..) => OpKind::SetOpExpiry,
+            AdminOp::SetSupportedToken(..) => OpKind::SetSupportedToken,
+            AdminOp::SetTokenEnabled(..) => OpKind::SetTokenEnabled,
+            AdminOp::SetPartnerFee(..) => OpKind::SetPartnerFee,
+            AdminOp::SetFeeTiers(..) => OpKind::SetFeeTiers,
+            AdminOp::SetFeeHolidays(..) => OpKind::SetFeeHolidays,
+            AdminOp::SetBondPolicy(..) => OpKind::SetBondPolicy,
+            AdminOp::SetReputationPolicy(..) => OpKind::SetReputationPolicy,
+        }
+    }
+
+    /// Role allowed to propose this op; `None` means admin only.
+    pub fn proposer_role(&self) -> Option<roles::Role> {
+        match self {
+            AdminOp::SetConfig(..)
+            | AdminOp::SetSupportedToken(..)
+            | AdminOp::SetPartnerFee(..)
+            | AdminOp::SetFeeTiers(..)
+            | AdminOp::SetFeeHolidays(..) => Some(roles::Role::FeeManager),
+            AdminOp::UpgradeInstances(..) | AdminOp::UpgradeFactory(..) => {
+                Some(roles::Role::Upgrader)
+            }
+            AdminOp::SetCreationDelay(..)
+            | AdminOp::SetWhitelistStatus(..)
+            | AdminOp::SetBondPolicy(..)
+            | AdminOp::SetReputationPolicy(..) => Some(roles::Role::Moderator),
+            AdminOp::PauseFactory
+            | AdminOp::UnpauseFactory
+            | AdminOp::PauseInstance(..)
+            | AdminOp::UnpauseInstance(..)
+            | AdminOp::SetTokenEnabled(..) => Some(roles::Role::Pauser),
+            AdminOp::RescueTokens(..) => Some(roles::Role::RescueOperator),
+            AdminOp::UpdateWasmHash(..)
+            | AdminOp::GrantRole(..)
+            | AdminOp::RevokeRole(..)
+            | AdminOp::SetGuardianThreshold(..)
+            | AdminOp::SetOpDelay(..)
+            | AdminOp::SetOpExpiry(..) => None,
+        }
+    }
 }
 
 /// Default page size when callers request zero items.
@@ -264,7 +857,7 @@ pub trait RandomnessOracleTrait {
 #[soroban_sdk::contractclient(name = "RandomnessReceiverClient")]
 pub trait RandomnessReceiverTrait {
     /// Delivers a randomness response to the callback contract.
-    fn receive_randomness(env: soroban_sdk::Env, request_id: u64, random_seed: u64);
+    fn receive_randomness(env: soroban_sdk::Env, request_id: u64, random_seed: BytesN<32>);
 }
 
 /// Cross-contract interface for an NFT ticket contract.
@@ -280,7 +873,7 @@ pub trait RandomnessReceiverTrait {
 /// * `recipient`  – the address that receives the NFT (the ticket buyer).
 /// * `ticket_id`  – the unique ticket ID within this raffle (1-indexed, u32).
 /// * `raffle_id`  – the raffle instance contract address, used as a namespace
-///                  so a single NFT contract can serve multiple raffles.
+///   so a single NFT contract can serve multiple raffles.
 #[soroban_sdk::contractclient(name = "NftTicketClient")]
 pub trait NftTicketTrait {
     fn mint(
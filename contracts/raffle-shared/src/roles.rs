@@ -0,0 +1,141 @@
+//! Role-based access control shared by the factory and raffle instances.
+//!
+//! The factory holds the authoritative role table and pushes each role's
+//! member list to its instances, so both sides answer `has_role` from their
+//! own storage.
+
+use soroban_sdk::{contracttype, Address, Env, Vec};
+
+use crate::constants::{PERSISTENT_TTL_EXTEND_TO, PERSISTENT_TTL_THRESHOLD};
+
+/// Privileged capabilities that can be granted independently of the admin.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[contracttype]
+#[repr(u32)]
+pub enum Role {
+    /// Pauses and unpauses the factory, instances, ticket sales and payment
+    /// tokens.
+    Pauser = 0,
+    /// Changes protocol fees, treasury, swap settings and payment-token
+    /// limits, and withdraws fees.
+    FeeManager = 1,
+    /// Proposes factory and instance upgrades and runs their migrations.
+    Upgrader = 2,
+    /// Cancels raffles, manages partner whitelisting, creator bonds and
+    /// reputation policy, and cleans up storage.
+    Moderator = 3,
+    /// Runs emergency actions that bypass the timelock.
+    Guardian = 4,
+    /// Sweeps tokens sent to a contract by mistake.
+    RescueOperator = 5,
+}
+
+/// Every role, in discriminant order.
+pub const ALL_ROLES: [Role; 6] = [
+    Role::Pauser,
+    Role::FeeManager,
+    Role::Upgrader,
+    Role::Moderator,
+    Role::Guardian,
+    Role::RescueOperator,
+];
+
+impl Role {
+    /// Whether granting this role must wait out the admin timelock.
+    /// Pauser and Moderator are operational and take effect immediately;
+    /// the rest move funds or code.
+    pub fn grant_is_timelocked(&self) -> bool {
+        !matches!(self, Role::Pauser | Role::Moderator)
+    }
+}
+
+/// Maximum number of addresses that may hold a single role.
+pub const MAX_ROLE_MEMBERS: u32 = 10;
+
+/// Storage key for role membership.
+#[derive(Clone)]
+#[contracttype]
+pub enum RoleKey {
+    /// Addresses currently holding the role.
+    Members(Role),
+}
+
+/// Returns the addresses holding `role`.
+pub fn members(env: &Env, role: Role) -> Vec<Address> {
+    env.storage()
+        .persistent()
+        .get(&RoleKey::Members(role))
+        .unwrap_or_else(|| Vec::new(env))
+}
+
+/// Whether `account` currently holds `role`.
+pub fn has_role(env: &Env, role: Role, account: &Address) -> bool {
+    members(env, role).contains(account)
+}
+
+/// Replaces the member list of `role`; an empty list removes the entry.
+pub fn set_members(env: &Env, role: Role, accounts: &Vec<Address>) {
+    let key = RoleKey::Members(role);
+    if accounts.is_empty() {
+        env.storage().persistent().remove(&key);
+        return;
+    }
+    env.storage().persistent().set(&key, accounts);
+    env.storage()
+        .persistent()
+        .extend_ttl(&key, PERSISTENT_TTL_THRESHOLD, PERSISTENT_TTL_EXTEND_TO);
+}
+
+/// Adds `account` to `role`.  Returns `false` if it already held the role
+/// or the role is full.
+pub fn grant(env: &Env, role: Role, account: &Address) -> bool {
+    let mut current = members(env, role);
+    if current.contains(account) || current.len() >= MAX_ROLE_MEMBERS {
+        return false;
+    }
+    current.push_back(account.clone());
+    set_members(env, role, &current);
+    true
+}
+
+/// Removes `account` from `role`.  Returns `false` if it did not hold it.
+pub fn revoke(env: &Env, role: Role, account: &Address) -> bool {
+    let mut current = members(env, role);
+    let Some(index) = current.first_index_of(account) else {
+        return false;
+    };
+    current.remove(index);
+    set_members(env, role, &current);
+    true
+}
+
+/// Extends the TTL of every non-empty role entry.
+pub fn bump_roles(env: &Env) {
+    for role in ALL_ROLES {
+        let key = RoleKey::Members(role);
+        if env.storage().persistent().has(&key) {
+            env.storage()
+                .persistent()
+                .extend_ttl(&key, PERSISTENT_TTL_THRESHOLD, PERSISTENT_TTL_EXTEND_TO);
+        }
+    }
+}
+
+/// Defines `fn require_role(env, role, caller) -> Result<(), $err>` for a
+/// contract: `caller` must authorize the call and hold `role`.
+#[macro_export]
+macro_rules! impl_require_role {
+    ($err:ty, $unauthorized:expr) => {
+        fn require_role(
+            env: &soroban_sdk::Env,
+            role: $crate::roles::Role,
+            caller: &soroban_sdk::Address,
+        ) -> Result<(), $err> {
+            caller.require_auth();
+            if !$crate::roles::has_role(env, role, caller) {
+                return Err($unauthorized);
+            }
+            Ok(())
+        }
+    };
+}
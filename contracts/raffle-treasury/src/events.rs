@@ -0,0 +1,42 @@
+use soroban_sdk::{contractevent, Address, Vec};
+
+use crate::Share;
+
+/// Emitted when the revenue split is configured or replaced.
+#[derive(Clone)]
+#[contractevent]
+pub struct SharesUpdated {
+    pub shares: Vec<Share>,
+    pub updated_by: Address,
+    pub timestamp: u64,
+}
+
+/// Emitted when newly received fees are credited to the recipients.
+#[derive(Clone)]
+#[contractevent]
+pub struct RevenueDistributed {
+    #[topic]
+    pub token: Address,
+    pub amount: i128,
+    pub timestamp: u64,
+}
+
+/// Emitted when a recipient pulls its credited revenue.
+#[derive(Clone)]
+#[contractevent]
+pub struct RevenueWithdrawn {
+    #[topic]
+    pub recipient: Address,
+    pub token: Address,
+    pub amount: i128,
+    pub timestamp: u64,
+}
+
+/// Emitted when the treasury admin is replaced.
+#[derive(Clone)]
+#[contractevent]
+pub struct TreasuryAdminChanged {
+    pub old_admin: Address,
+    pub new_admin: Address,
+    pub timestamp: u64,
+}
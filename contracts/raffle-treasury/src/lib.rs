@@ -0,0 +1,285 @@
+#![no_std]
+#![cfg_attr(not(test), deny(clippy::unwrap_used))]
+
+//! Protocol treasury.  Receives raffle protocol fees, credits them to the
+//! revenue buckets by configured share, and lets each recipient pull what it
+//! has been credited.
+
+use soroban_sdk::{contract, contracterror, contractimpl, contracttype, token, Address, Env, Vec};
+
+use raffle_shared::constants::{
+    INSTANCE_TTL_EXTEND_TO, INSTANCE_TTL_THRESHOLD, PERSISTENT_TTL_EXTEND_TO,
+    PERSISTENT_TTL_THRESHOLD,
+};
+
+mod events;
+
+use events::{RevenueDistributed, RevenueWithdrawn, SharesUpdated, TreasuryAdminChanged};
+
+/// Basis-point total every split must add up to.
+pub const TOTAL_SHARE_BP: u32 = 10_000;
+
+/// Purpose a slice of protocol revenue is set aside for.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(u32)]
+pub enum Bucket {
+    Ops = 0,
+    Stakers = 1,
+    Buyback = 2,
+    Charity = 3,
+}
+
+/// One bucket's cut of incoming revenue and the address that may withdraw it.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Share {
+    pub bucket: Bucket,
+    pub recipient: Address,
+    pub share_bp: u32,
+}
+
+#[contracttype]
+#[derive(Clone)]
+pub enum DataKey {
+    Admin,
+    /// Current `Vec<Share>`.
+    Shares,
+    /// Credited but not yet withdrawn revenue of a token, summed over all
+    /// recipients.  The rest of the balance is still to be distributed.
+    Reserved(Address),
+    /// (recipient, token) → revenue credited to the recipient.
+    Owed(Address, Address),
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    AlreadyInitialized = 1,
+    NotInitialized = 2,
+    InvalidShares = 3,
+    NothingToWithdraw = 4,
+    ArithmeticOverflow = 5,
+}
+
+#[contract]
+pub struct RaffleTreasury;
+
+fn bump_instance(env: &Env) {
+    env.storage()
+        .instance()
+        .extend_ttl(INSTANCE_TTL_THRESHOLD, INSTANCE_TTL_EXTEND_TO);
+}
+
+fn bump_persistent(env: &Env, key: &DataKey) {
+    if env.storage().persistent().has(key) {
+        env.storage().persistent().extend_ttl(
+            key,
+            PERSISTENT_TTL_THRESHOLD,
+            PERSISTENT_TTL_EXTEND_TO,
+        );
+    }
+}
+
+fn read_admin(env: &Env) -> Result<Address, Error> {
+    env.storage()
+        .instance()
+        .get(&DataKey::Admin)
+        .ok_or(Error::NotInitialized)
+}
+
+/// Each bucket appears at most once and the shares add up to
+/// `TOTAL_SHARE_BP`.
+fn validate_shares(shares: &Vec<Share>) -> Result<(), Error> {
+    let mut total: u32 = 0;
+    for (i, share) in shares.iter().enumerate() {
+        for other in shares.iter().skip(i + 1) {
+            if other.bucket == share.bucket {
+                return Err(Error::InvalidShares);
+            }
+        }
+        total = total
+            .checked_add(share.share_bp)
+            .ok_or(Error::InvalidShares)?;
+    }
+    if total != TOTAL_SHARE_BP {
+        return Err(Error::InvalidShares);
+    }
+    Ok(())
+}
+
+fn write_shares(env: &Env, shares: Vec<Share>, updated_by: Address) {
+    env.storage().instance().set(&DataKey::Shares, &shares);
+    SharesUpdated {
+        shares,
+        updated_by,
+        timestamp: env.ledger().timestamp(),
+    }
+    .publish(env);
+}
+
+fn reserved(env: &Env, token: &Address) -> i128 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Reserved(token.clone()))
+        .unwrap_or(0)
+}
+
+fn owed(env: &Env, recipient: &Address, token: &Address) -> i128 {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Owed(recipient.clone(), token.clone()))
+        .unwrap_or(0)
+}
+
+#[contractimpl]
+impl RaffleTreasury {
+    pub fn init(env: Env, admin: Address, shares: Vec<Share>) -> Result<(), Error> {
+        if env.storage().instance().has(&DataKey::Admin) {
+            return Err(Error::AlreadyInitialized);
+        }
+        admin.require_auth();
+        validate_shares(&shares)?;
+        env.storage().instance().set(&DataKey::Admin, &admin);
+        write_shares(&env, shares, admin);
+        bump_instance(&env);
+        Ok(())
+    }
+
+    /// Replaces the revenue split.  Only fees distributed afterwards follow
+    /// the new shares, so call `distribute` for each token first to settle
+    /// what arrived under the old split.
+    pub fn set_shares(env: Env, shares: Vec<Share>) -> Result<(), Error> {
+        let admin = read_admin(&env)?;
+        admin.require_auth();
+        validate_shares(&shares)?;
+        write_shares(&env, shares, admin);
+        bump_instance(&env);
+        Ok(())
+    }
+
+    pub fn set_admin(env: Env, new_admin: Address) -> Result<(), Error> {
+        let old_admin = read_admin(&env)?;
+        old_admin.require_auth();
+        new_admin.require_auth();
+        env.storage().instance().set(&DataKey::Admin, &new_admin);
+        TreasuryAdminChanged {
+            old_admin,
+            new_admin,
+            timestamp: env.ledger().timestamp(),
+        }
+        .publish(&env);
+        Ok(())
+    }
+
+    /// Credits the token balance that arrived since the last call to the
+    /// recipients by share.  Permissionless.  Rounding dust stays
+    /// undistributed and is picked up by a later call.  Returns the amount
+    /// credited.
+    pub fn distribute(env: Env, token: Address) -> Result<i128, Error> {
+        let shares: Vec<Share> = env
+            .storage()
+            .instance()
+            .get(&DataKey::Shares)
+            .ok_or(Error::NotInitialized)?;
+        bump_instance(&env);
+
+        let reserved_before = reserved(&env, &token);
+        let balance = token::Client::new(&env, &token).balance(&env.current_contract_address());
+        let incoming = balance - reserved_before;
+        if incoming <= 0 {
+            return Ok(0);
+        }
+
+        let mut credited: i128 = 0;
+        for share in shares.iter() {
+            let amount = incoming
+                .checked_mul(share.share_bp as i128)
+                .ok_or(Error::ArithmeticOverflow)?
+                / TOTAL_SHARE_BP as i128;
+            if amount == 0 {
+                continue;
+            }
+            let key = DataKey::Owed(share.recipient.clone(), token.clone());
+            let owed_after = owed(&env, &share.recipient, &token)
+                .checked_add(amount)
+                .ok_or(Error::ArithmeticOverflow)?;
+            env.storage().persistent().set(&key, &owed_after);
+            bump_persistent(&env, &key);
+            credited += amount;
+        }
+        if credited == 0 {
+            return Ok(0);
+        }
+
+        let key = DataKey::Reserved(token.clone());
+        env.storage()
+            .persistent()
+            .set(&key, &(reserved_before + credited));
+        bump_persistent(&env, &key);
+        RevenueDistributed {
+            token,
+            amount: credited,
+            timestamp: env.ledger().timestamp(),
+        }
+        .publish(&env);
+        Ok(credited)
+    }
+
+    /// Pays `recipient` everything it has been credited in `token`.
+    pub fn withdraw(env: Env, recipient: Address, token: Address) -> Result<i128, Error> {
+        recipient.require_auth();
+        let amount = owed(&env, &recipient, &token);
+        if amount <= 0 {
+            return Err(Error::NothingToWithdraw);
+        }
+
+        env.storage()
+            .persistent()
+            .remove(&DataKey::Owed(recipient.clone(), token.clone()));
+        let key = DataKey::Reserved(token.clone());
+        env.storage()
+            .persistent()
+            .set(&key, &(reserved(&env, &token) - amount));
+        bump_persistent(&env, &key);
+        token::Client::new(&env, &token).transfer(
+            &env.current_contract_address(),
+            &recipient,
+            &amount,
+        );
+
+        RevenueWithdrawn {
+            recipient,
+            token,
+            amount,
+            timestamp: env.ledger().timestamp(),
+        }
+        .publish(&env);
+        Ok(amount)
+    }
+
+    pub fn get_admin(env: Env) -> Result<Address, Error> {
+        read_admin(&env)
+    }
+
+    pub fn get_shares(env: Env) -> Vec<Share> {
+        env.storage()
+            .instance()
+            .get(&DataKey::Shares)
+            .unwrap_or_else(|| Vec::new(&env))
+    }
+
+    pub fn get_owed(env: Env, recipient: Address, token: Address) -> i128 {
+        owed(&env, &recipient, &token)
+    }
+
+    /// Balance of `token` not yet credited to any recipient.
+    pub fn get_undistributed(env: Env, token: Address) -> i128 {
+        let balance = token::Client::new(&env, &token).balance(&env.current_contract_address());
+        balance - reserved(&env, &token)
+    }
+}
+
+#[cfg(test)]
+mod test;
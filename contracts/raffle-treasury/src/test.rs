@@ -0,0 +1,133 @@
+use super::*;
+use soroban_sdk::testutils::Address as _;
+use soroban_sdk::{token, vec, Env};
+
+struct Setup<'a> {
+    client: RaffleTreasuryClient<'a>,
+    token: Address,
+    minter: token::StellarAssetClient<'a>,
+    ops: Address,
+    stakers: Address,
+    buyback: Address,
+    charity: Address,
+}
+
+fn share(bucket: Bucket, recipient: &Address, share_bp: u32) -> Share {
+    Share {
+        bucket,
+        recipient: recipient.clone(),
+        share_bp,
+    }
+}
+
+fn setup(env: &Env) -> Setup<'_> {
+    env.mock_all_auths();
+    let admin = Address::generate(env);
+    let ops = Address::generate(env);
+    let stakers = Address::generate(env);
+    let buyback = Address::generate(env);
+    let charity = Address::generate(env);
+    let token = env
+        .register_stellar_asset_contract_v2(Address::generate(env))
+        .address();
+
+    let client = RaffleTreasuryClient::new(env, &env.register(RaffleTreasury, ()));
+    client.init(
+        &admin,
+        &vec![
+            env,
+            share(Bucket::Ops, &ops, 4_000),
+            share(Bucket::Stakers, &stakers, 3_000),
+            share(Bucket::Buyback, &buyback, 2_000),
+            share(Bucket::Charity, &charity, 1_000),
+        ],
+    );
+
+    Setup {
+        client,
+        minter: token::StellarAssetClient::new(env, &token),
+        token,
+        ops,
+        stakers,
+        buyback,
+        charity,
+    }
+}
+
+#[test]
+fn distributes_by_share_and_pays_on_withdraw() {
+    let env = Env::default();
+    let s = setup(&env);
+
+    s.minter.mint(&s.client.address, &10_000);
+    assert_eq!(s.client.distribute(&s.token), 10_000);
+    assert_eq!(s.client.get_owed(&s.ops, &s.token), 4_000);
+    assert_eq!(s.client.get_owed(&s.stakers, &s.token), 3_000);
+    assert_eq!(s.client.get_owed(&s.buyback, &s.token), 2_000);
+    assert_eq!(s.client.get_owed(&s.charity, &s.token), 1_000);
+    assert_eq!(s.client.get_undistributed(&s.token), 0);
+    assert_eq!(s.client.distribute(&s.token), 0);
+
+    assert_eq!(s.client.withdraw(&s.ops, &s.token), 4_000);
+    assert_eq!(token::Client::new(&env, &s.token).balance(&s.ops), 4_000);
+    assert_eq!(
+        s.client.try_withdraw(&s.ops, &s.token),
+        Err(Ok(Error::NothingToWithdraw))
+    );
+
+    // Withdrawals do not free credited revenue for redistribution, and
+    // rounding dust waits for a later call.
+    s.minter.mint(&s.client.address, &9);
+    assert_eq!(s.client.distribute(&s.token), 6);
+    assert_eq!(s.client.get_owed(&s.ops, &s.token), 3);
+    assert_eq!(s.client.get_owed(&s.stakers, &s.token), 3_002);
+    assert_eq!(s.client.get_undistributed(&s.token), 3);
+}
+
+#[test]
+fn share_changes_apply_to_later_distributions() {
+    let env = Env::default();
+    let s = setup(&env);
+
+    s.minter.mint(&s.client.address, &1_000);
+    s.client.distribute(&s.token);
+
+    let uneven = vec![
+        &env,
+        share(Bucket::Ops, &s.ops, 5_000),
+        share(Bucket::Charity, &s.charity, 4_000),
+    ];
+    assert_eq!(
+        s.client.try_set_shares(&uneven),
+        Err(Ok(Error::InvalidShares))
+    );
+    let duplicate = vec![
+        &env,
+        share(Bucket::Ops, &s.ops, 5_000),
+        share(Bucket::Ops, &s.charity, 5_000),
+    ];
+    assert_eq!(
+        s.client.try_set_shares(&duplicate),
+        Err(Ok(Error::InvalidShares))
+    );
+
+    let charity_only = vec![&env, share(Bucket::Charity, &s.charity, TOTAL_SHARE_BP)];
+    s.client.set_shares(&charity_only);
+    assert_eq!(s.client.get_shares(), charity_only);
+
+    s.minter.mint(&s.client.address, &1_000);
+    s.client.distribute(&s.token);
+    assert_eq!(s.client.get_owed(&s.ops, &s.token), 400);
+    assert_eq!(s.client.get_owed(&s.charity, &s.token), 1_100);
+}
+
+#[test]
+fn init_rejects_second_call() {
+    let env = Env::default();
+    let s = setup(&env);
+    let admin = Address::generate(&env);
+    assert_eq!(
+        s.client.try_init(&admin, &s.client.get_shares()),
+        Err(Ok(Error::AlreadyInitialized))
+    );
+}
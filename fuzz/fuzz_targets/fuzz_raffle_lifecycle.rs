@@ -0,0 +1,19 @@
+//! Fuzz target: raffle lifecycle against the real contracts
+//!
+//! Decodes an arbitrary sequence of create/deposit/buy/finalize/draw/claim/
+//! refund/cancel steps and replays it through `raffle_fuzz::Harness`, which
+//! registers the real factory and instance contracts and asserts token
+//! conservation, status-machine legality and no double payout after every
+//! step.
+//!
+//! Run for 30 minutes (Linux/WSL with nightly):
+//!   cargo fuzz run fuzz_raffle_lifecycle -- -max_total_time=1800
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use raffle_fuzz::{Harness, Step};
+
+fuzz_target!(|steps: Vec<Step>| {
+    Harness::run(&steps);
+});
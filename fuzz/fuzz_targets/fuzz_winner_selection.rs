@@ -2,23 +2,38 @@
 
 use arbitrary::Arbitrary;
 use libfuzzer_sys::fuzz_target;
-use tikka_raffle_instance::randomness::OracleSeedWinnerSelection;
+use raffle_instance::randomness::select_winners_with;
+use raffle_shared::SelectionAlgorithm;
+use soroban_sdk::{BytesN, Env};
 
 #[derive(Debug, Arbitrary)]
 struct WinnerSelectionInput {
-    seed: u64,
+    seed: [u8; 32],
     total_tickets: u8,   // 1..=255 to stay fast
     winner_count: u8,    // 1..=total_tickets
+    algorithm: u8,       // every `SelectionAlgorithm`
 }
 
 fuzz_target!(|input: WinnerSelectionInput| {
     let n = (input.total_tickets as u32).max(1);
     let w = ((input.winner_count as u32) % n).max(1);
-    let selector = OracleSeedWinnerSelection::new(input.seed);
-    
+    let algorithm = match input.algorithm % 3 {
+        0 => SelectionAlgorithm::Sha256CounterV2,
+        1 => SelectionAlgorithm::EnvPrng,
+        _ => SelectionAlgorithm::LcgRejectionV1,
+    };
+
+    // `EnvPrng` reseeds the host PRNG, which needs a contract context.
+    let env = Env::default();
+    let contract = env.register(raffle_instance::Contract, ());
+
     // This must always terminate
-    let indices = selector.select_winner_indices_pure(n, w);
-    
+    let indices: std::vec::Vec<u32> = env.as_contract(&contract, || {
+        select_winners_with(&env, &algorithm, &BytesN::from_array(&env, &input.seed), n, w)
+            .iter()
+            .collect()
+    });
+
     // INVARIANT: correct count
     assert_eq!(indices.len(), w as usize);
     // INVARIANT: all within range
@@ -26,4 +41,4 @@ fuzz_target!(|input: WinnerSelectionInput| {
     // INVARIANT: all unique
     let unique: std::collections::HashSet<_> = indices.iter().collect();
     assert_eq!(unique.len(), indices.len());
-});
\ No newline at end of file
+});
@@ -0,0 +1,602 @@
+//! Stateful harness shared by the fuzz targets and the smoke tests.
+//!
+//! Nothing here mirrors contract logic. Every step calls the real
+//! `raffle-factory` and `raffle-instance` contracts registered in a testutils
+//! `Env`; the harness only remembers what the contracts reported so the
+//! invariants below can be checked after each step:
+//!
+//! * **Token conservation** — the payment token's balances across every
+//!   account and contract the harness knows about always sum to what was
+//!   minted.
+//! * **Status-machine legality** — every observed `RaffleStatus` change is an
+//!   edge of the documented state machine (see `docs/ARCHITECTURE.md`).
+//! * **No double payout** — a prize tier, a ticket refund or a prize refund
+//!   never succeeds twice, and paid-out prizes never exceed `prize_amount`.
+//!
+//! The factory is built exactly as shipped: it checks that role addresses
+//! exist and deploys instances from its stored WASM hash.  The harness uses
+//! [`Vault`] contract accounts for the admin and treasury, and
+//! [`NativeInstanceShim`] lets those deployments run the natively compiled
+//! instance.
+
+use std::collections::BTreeSet;
+
+use arbitrary::Arbitrary;
+use raffle_factory::{RaffleFactory, RaffleFactoryClient};
+use raffle_instance::ContractClient as RaffleClient;
+use raffle_shared::constants::{MAX_PRIZE_AMOUNT, MIN_TICKET_PRICE};
+use raffle_shared::{
+    AdminOp, CancelReason, RaffleConfig, RaffleStatus, RandomnessSource, RefundTarget, SaleGate,
+    SelectionAlgorithm, SupportedToken,
+};
+use soroban_sdk::{
+    contract, contractimpl,
+    testutils::{Address as _, Ledger},
+    token, vec, xdr::ToXdr, Address, Bytes, BytesN, Env, Error, String,
+};
+
+/// Upper bound on steps per input so a single run stays fast.
+pub const MAX_STEPS: usize = 64;
+
+const CREATORS: usize = 2;
+const BUYERS: usize = 4;
+const MAX_RAFFLES: usize = 4;
+const STARTING_BALANCE: i128 = 1_000_000_000;
+const PROTOCOL_FEE_BP: u32 = 250;
+const RAFFLE_DURATION: u64 = 3_600;
+/// One per raffle, so no two deployments share a salt.
+const DESCRIPTIONS: [&str; MAX_RAFFLES] = ["fuzz raffle 0", "fuzz raffle 1", "fuzz raffle 2", "fuzz raffle 3"];
+
+/// One call against the factory, a raffle instance or the ledger clock.
+///
+/// Indices are reduced modulo the number of known actors/raffles, so every
+/// byte string decodes to a meaningful step.
+#[derive(Arbitrary, Debug, Clone)]
+pub enum Step {
+    Create {
+        creator: u8,
+        max_tickets: u8,
+        min_tickets: u8,
+        price_steps: u8,
+        two_tiers: bool,
+        algorithm: u8,
+        charge_fee: bool,
+    },
+    DepositPrize { raffle: u8 },
+    BuyTickets { raffle: u8, buyer: u8, quantity: u8 },
+    Finalize { raffle: u8 },
+    ExecuteDraw { raffle: u8, caller: u8 },
+    Advance { ledgers: u8, seconds: u16 },
+    Claim { raffle: u8, tier: u8 },
+    RefundTicket { raffle: u8, ticket: u8 },
+    RefundPrize { raffle: u8 },
+    Cancel { raffle: u8, by_admin: bool },
+}
+
+/// Makes a factory deployment at a known address dispatch to the natively
+/// compiled `raffle_instance::Contract`.
+///
+/// A testutils `Env` runs a contract natively when its executable is the
+/// empty WASM and a native implementation is registered for its address.
+/// `preload` registers one and then fails, so the host rolls back the
+/// instance entry but keeps the registration; the factory's own `deploy_v2`
+/// from the empty WASM hash then creates the instance.
+#[contract]
+pub struct NativeInstanceShim;
+
+#[contractimpl]
+impl NativeInstanceShim {
+    pub fn preload(env: Env, at: Address) -> Result<(), Error> {
+        env.register_at(&at, raffle_instance::Contract, ());
+        Err(Error::from_contract_error(0))
+    }
+}
+
+/// Contract account standing in for the factory admin and treasury.
+#[contract]
+pub struct Vault;
+
+#[contractimpl]
+impl Vault {}
+
+/// What the harness has observed about one deployed raffle.
+struct TrackedRaffle {
+    address: Address,
+    status: RaffleStatus,
+    prize_amount: i128,
+    prizes_paid: i128,
+    claimed_tiers: BTreeSet<u32>,
+    refunded_tickets: BTreeSet<u32>,
+    prize_refunded: bool,
+}
+
+pub struct Harness {
+    env: Env,
+    factory: Address,
+    shim: NativeInstanceShimClient<'static>,
+    token: Address,
+    admin: Address,
+    treasury: Address,
+    creators: std::vec::Vec<Address>,
+    buyers: std::vec::Vec<Address>,
+    raffles: std::vec::Vec<TrackedRaffle>,
+    minted: i128,
+}
+
+impl Harness {
+    pub fn new() -> Self {
+        let env = Env::default();
+        env.mock_all_auths();
+        env.ledger().with_mut(|l| {
+            l.timestamp = 1_000_000;
+            l.sequence_number = 100;
+        });
+
+        // Role addresses must exist on the ledger.  Contract accounts do,
+        // and hold the payment token without a trustline.
+        let admin = env.register(Vault, ());
+        let treasury = env.register(Vault, ());
+        let factory = env.register(RaffleFactory, ());
+        let shim = NativeInstanceShimClient::new(&env, &env.register(NativeInstanceShim, ()));
+        // Registering native contracts uploaded the empty WASM that marks
+        // them; instances deployed from it run natively once preloaded.
+        let native_wasm: BytesN<32> = env.crypto().sha256(&Bytes::new(&env)).into();
+        let factory_client = RaffleFactoryClient::new(&env, &factory);
+        factory_client.init_factory(&admin, &native_wasm, &PROTOCOL_FEE_BP, &treasury);
+
+        let token_admin = Address::generate(&env);
+        let token = env
+            .register_stellar_asset_contract_v2(token_admin)
+            .address();
+        let mint = token::StellarAssetClient::new(&env, &token);
+
+        // List the token and lift the creation rate limit through the
+        // governance queue, as an operator would.
+        let setup = vec![
+            &env,
+            AdminOp::SetCreationDelay(0),
+            AdminOp::SetSupportedToken(
+                token.clone(),
+                SupportedToken {
+                    min_ticket_price: MIN_TICKET_PRICE,
+                    max_prize_amount: MAX_PRIZE_AMOUNT,
+                    decimals: 7,
+                    enabled: true,
+                },
+            ),
+        ];
+        let op_id = factory_client.propose_batch(&admin, &setup);
+        let effective_at = factory_client
+            .get_pending_op(&op_id)
+            .expect("setup proposal queued")
+            .effective_timestamp;
+        env.ledger().with_mut(|l| l.timestamp = effective_at);
+        factory_client.execute_config_change(&op_id);
+
+        let creators: std::vec::Vec<Address> =
+            (0..CREATORS).map(|_| Address::generate(&env)).collect();
+        let buyers: std::vec::Vec<Address> =
+            (0..BUYERS).map(|_| Address::generate(&env)).collect();
+        let mut minted = 0;
+        for account in creators.iter().chain(buyers.iter()) {
+            mint.mint(account, &STARTING_BALANCE);
+            minted += STARTING_BALANCE;
+        }
+
+        Self {
+            env,
+            factory,
+            shim,
+            token,
+            admin,
+            treasury,
+            creators,
+            buyers,
+            raffles: std::vec::Vec::new(),
+            minted,
+        }
+    }
+
+    /// Runs `steps` in order, checking every invariant after each one.
+    pub fn run(steps: &[Step]) {
+        let mut harness = Self::new();
+        for step in steps.iter().take(MAX_STEPS) {
+            harness.apply(step);
+            harness.check_invariants();
+        }
+    }
+
+    fn raffle(&self, index: u8) -> Option<usize> {
+        if self.raffles.is_empty() {
+            None
+        } else {
+            Some(index as usize % self.raffles.len())
+        }
+    }
+
+    fn client(&self, index: usize) -> RaffleClient<'_> {
+        RaffleClient::new(&self.env, &self.raffles[index].address)
+    }
+
+    pub fn apply(&mut self, step: &Step) {
+        match *step {
+            Step::Create {
+                creator,
+                max_tickets,
+                min_tickets,
+                price_steps,
+                two_tiers,
+                algorithm,
+                charge_fee,
+            } => self.create(
+                creator,
+                max_tickets,
+                min_tickets,
+                price_steps,
+                two_tiers,
+                algorithm,
+                charge_fee,
+            ),
+            Step::DepositPrize { raffle } => {
+                if let Some(i) = self.raffle(raffle) {
+                    let _ = self.client(i).try_deposit_prize();
+                }
+            }
+            Step::BuyTickets {
+                raffle,
+                buyer,
+                quantity,
+            } => {
+                if let Some(i) = self.raffle(raffle) {
+                    let buyer = self.buyers[buyer as usize % BUYERS].clone();
+                    let quantity = u32::from(quantity % 8) + 1;
+                    let _ = self.client(i).try_buy_tickets(&buyer, &quantity);
+                }
+            }
+            Step::Finalize { raffle } => {
+                if let Some(i) = self.raffle(raffle) {
+                    let _ = self.client(i).try_finalize_raffle();
+                }
+            }
+            Step::ExecuteDraw { raffle, caller } => {
+                if let Some(i) = self.raffle(raffle) {
+                    let caller = self.buyers[caller as usize % BUYERS].clone();
+                    let _ = self.client(i).try_execute_draw(&caller);
+                }
+            }
+            Step::Advance { ledgers, seconds } => {
+                // Wide enough to cross the instance's draw delay in one step.
+                let ledgers = u32::from(ledgers % 32);
+                self.env.ledger().with_mut(|l| {
+                    l.sequence_number += ledgers;
+                    l.timestamp += u64::from(seconds);
+                });
+            }
+            Step::Claim { raffle, tier } => {
+                if let Some(i) = self.raffle(raffle) {
+                    self.claim(i, tier);
+                }
+            }
+            Step::RefundTicket { raffle, ticket } => {
+                if let Some(i) = self.raffle(raffle) {
+                    self.refund_ticket(i, ticket);
+                }
+            }
+            Step::RefundPrize { raffle } => {
+                if let Some(i) = self.raffle(raffle) {
+                    if self.client(i).try_refund_prize().is_ok() {
+                        let tracked = &mut self.raffles[i];
+                        assert!(!tracked.prize_refunded, "prize refunded twice");
+                        assert_eq!(tracked.prizes_paid, 0, "prize refunded after a payout");
+                        tracked.prize_refunded = true;
+                    }
+                }
+            }
+            Step::Cancel { raffle, by_admin } => {
+                if let Some(i) = self.raffle(raffle) {
+                    let reason = if by_admin {
+                        CancelReason::AdminCancelled
+                    } else {
+                        CancelReason::CreatorCancelled
+                    };
+                    let _ = self.client(i).try_cancel_raffle(&reason);
+                }
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn create(
+        &mut self,
+        creator: u8,
+        max_tickets: u8,
+        min_tickets: u8,
+        price_steps: u8,
+        two_tiers: bool,
+        algorithm: u8,
+        charge_fee: bool,
+    ) {
+        if self.raffles.len() >= MAX_RAFFLES {
+            return;
+        }
+        let env = &self.env;
+        let creator = self.creators[creator as usize % CREATORS].clone();
+        let max_tickets = u32::from(max_tickets % 16) + 1;
+        let ticket_price = MIN_TICKET_PRICE * (i128::from(price_steps % 4) + 1);
+        let prizes = if two_tiers && max_tickets > 1 {
+            vec![env, 7_000u32, 3_000u32]
+        } else {
+            vec![env, 10_000u32]
+        };
+        let selection_algorithm = if algorithm.is_multiple_of(2) {
+            SelectionAlgorithm::Sha256CounterV2
+        } else {
+            SelectionAlgorithm::EnvPrng
+        };
+        let config = RaffleConfig {
+            description: String::from_str(env, DESCRIPTIONS[self.raffles.len()]),
+            end_time: env.ledger().timestamp() + RAFFLE_DURATION,
+            no_deadline: false,
+            max_tickets,
+            max_tickets_per_tx: max_tickets,
+            min_tickets: u32::from(min_tickets) % max_tickets + 1,
+            allow_multiple: true,
+            ticket_price,
+            payment_token: self.token.clone(),
+            prize_amount: ticket_price * 10,
+            prizes,
+            randomness_source: RandomnessSource::Internal,
+            oracle_address: None,
+            protocol_fee_bp: if charge_fee { PROTOCOL_FEE_BP } else { 0 },
+            treasury_address: Some(self.treasury.clone()),
+            swap_router: None,
+            tikka_token: None,
+            metadata_hash: BytesN::from_array(env, &[self.raffles.len() as u8 + 1; 32]),
+            claim_lockup_seconds: 0,
+            swap_deadline_seconds: 0,
+            early_bird_ticket_percentage: 0,
+            early_bird_discount_bp: 0,
+            prize_token: None,
+            nft_contract: None,
+            selection_algorithm,
+            sale_gate: SaleGate::Open,
+            max_tickets_per_address: 0,
+            max_holding_bp: 0,
+            refund_target: RefundTarget::Owner,
+        };
+
+        // Every generated config is valid, so a rejection is a contract bug
+        // rather than an input to skip.
+        // The factory salts deployments with the creator and description.
+        let salt = env
+            .crypto()
+            .sha256(&(creator.clone(), config.description.clone()).to_xdr(env));
+        let at = env
+            .deployer()
+            .with_address(self.factory.clone(), salt)
+            .deployed_address();
+        // Fails by design; only the native registration survives.
+        let _ = self.shim.try_preload(&at);
+
+        let factory = RaffleFactoryClient::new(env, &self.factory);
+        let created = factory.try_create_raffle(&creator, &config);
+        let Ok(Ok(address)) = created else {
+            panic!("valid config rejected: {created:?}");
+        };
+        let raffle = RaffleClient::new(env, &address).get_raffle();
+        self.raffles.push(TrackedRaffle {
+            address,
+            status: raffle.status,
+            prize_amount: raffle.prize_amount,
+            prizes_paid: 0,
+            claimed_tiers: BTreeSet::new(),
+            refunded_tickets: BTreeSet::new(),
+            prize_refunded: false,
+        });
+    }
+
+    fn claim(&mut self, index: usize, tier: u8) {
+        let raffle = self.client(index).get_raffle();
+        if raffle.winners.is_empty() {
+            return;
+        }
+        let tier = u32::from(tier) % raffle.winners.len();
+        let winner = raffle.winners.get(tier).unwrap();
+        let claimed = self.client(index).try_claim_prize(&winner, &tier);
+        if let Ok(Ok(amount)) = claimed {
+            let tracked = &mut self.raffles[index];
+            assert!(tracked.claimed_tiers.insert(tier), "tier {tier} paid twice");
+            assert!(!tracked.prize_refunded, "prize claimed after refund");
+            tracked.prizes_paid += amount;
+        }
+    }
+
+    fn refund_ticket(&mut self, index: usize, ticket: u8) {
+        let sold = self.client(index).get_raffle().tickets_sold;
+        if sold == 0 {
+            return;
+        }
+        let ticket_id = u32::from(ticket) % sold + 1;
+        if self.client(index).try_refund_ticket(&ticket_id).is_ok() {
+            assert!(
+                self.raffles[index].refunded_tickets.insert(ticket_id),
+                "ticket {ticket_id} refunded twice"
+            );
+        }
+    }
+
+    pub fn check_invariants(&mut self) {
+        self.check_token_conservation();
+        for index in 0..self.raffles.len() {
+            let raffle = self.client(index).get_raffle();
+            let tracked = &mut self.raffles[index];
+
+            assert!(
+                is_legal_transition(&tracked.status, &raffle.status),
+                "illegal status transition {:?} -> {:?}",
+                tracked.status,
+                raffle.status
+            );
+            tracked.status = raffle.status;
+
+            assert!(
+                tracked.prizes_paid <= tracked.prize_amount,
+                "paid {} out of a {} prize pool",
+                tracked.prizes_paid,
+                tracked.prize_amount
+            );
+            assert!(tracked.refunded_tickets.len() as u32 <= raffle.tickets_sold);
+        }
+    }
+
+    fn check_token_conservation(&self) {
+        let token = token::Client::new(&self.env, &self.token);
+        let mut holders = vec![
+            &self.env,
+            self.admin.clone(),
+            self.treasury.clone(),
+            self.factory.clone(),
+        ];
+        for account in self.creators.iter().chain(self.buyers.iter()) {
+            holders.push_back(account.clone());
+        }
+        for raffle in self.raffles.iter() {
+            holders.push_back(raffle.address.clone());
+        }
+
+        let total: i128 = holders.iter().map(|holder| token.balance(&holder)).sum();
+        assert_eq!(total, self.minted, "payment token created or destroyed");
+    }
+}
+
+impl Default for Harness {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Edges of the `RaffleStatus` state machine, plus staying put.
+pub fn is_legal_transition(from: &RaffleStatus, to: &RaffleStatus) -> bool {
+    use RaffleStatus::*;
+    from == to
+        || matches!(
+            (from, to),
+            (PendingPrize, Active)
+                | (PendingPrize, Cancelled)
+                | (Active, Drawing)
+                | (Active, Cancelled)
+                | (Active, Failed)
+                | (Drawing, Finalized)
+                | (Drawing, Cancelled)
+                | (Finalized, Claimed)
+                | (Finalized, Cancelled)
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn full_lifecycle_pays_every_tier_once() {
+        let create = Step::Create {
+            creator: 0,
+            max_tickets: 3,
+            min_tickets: 0,
+            price_steps: 0,
+            two_tiers: true,
+            algorithm: 1,
+            charge_fee: true,
+        };
+        let mut steps = std::vec![
+            create,
+            Step::DepositPrize { raffle: 0 },
+            Step::BuyTickets { raffle: 0, buyer: 0, quantity: 1 },
+            Step::BuyTickets { raffle: 0, buyer: 1, quantity: 1 },
+            Step::Advance { ledgers: 0, seconds: 3_600 },
+            Step::Finalize { raffle: 0 },
+            Step::Advance { ledgers: 10, seconds: 0 },
+            Step::ExecuteDraw { raffle: 0, caller: 2 },
+            Step::Advance { ledgers: 1, seconds: 3_600 },
+        ];
+        for _ in 0..2 {
+            steps.push(Step::Claim { raffle: 0, tier: 0 });
+            steps.push(Step::Claim { raffle: 0, tier: 1 });
+        }
+
+        let mut harness = Harness::new();
+        for step in &steps {
+            harness.apply(step);
+            harness.check_invariants();
+        }
+        assert_eq!(harness.raffles[0].status, RaffleStatus::Claimed);
+        assert_eq!(harness.raffles[0].claimed_tiers.len(), 2);
+    }
+
+    #[test]
+    fn cancelled_raffle_refunds_each_ticket_once() {
+        let mut steps = std::vec![
+            Step::Create {
+                creator: 1,
+                max_tickets: 5,
+                min_tickets: 0,
+                price_steps: 2,
+                two_tiers: false,
+                algorithm: 0,
+                charge_fee: false,
+            },
+            Step::DepositPrize { raffle: 0 },
+            Step::BuyTickets { raffle: 0, buyer: 3, quantity: 2 },
+            Step::Cancel { raffle: 0, by_admin: false },
+            Step::RefundPrize { raffle: 0 },
+            Step::RefundPrize { raffle: 0 },
+        ];
+        for ticket in [0, 1, 0, 1] {
+            steps.push(Step::RefundTicket { raffle: 0, ticket });
+        }
+
+        let mut harness = Harness::new();
+        for step in &steps {
+            harness.apply(step);
+            harness.check_invariants();
+        }
+        assert_eq!(harness.raffles[0].status, RaffleStatus::Cancelled);
+        assert_eq!(harness.raffles[0].refunded_tickets.len(), 2);
+        assert!(harness.raffles[0].prize_refunded);
+    }
+
+    #[test]
+    fn failed_raffle_is_terminal() {
+        Harness::run(&[
+            Step::Create {
+                creator: 0,
+                max_tickets: 4,
+                min_tickets: 3,
+                price_steps: 0,
+                two_tiers: false,
+                algorithm: 2,
+                charge_fee: false,
+            },
+            Step::DepositPrize { raffle: 0 },
+            Step::BuyTickets { raffle: 0, buyer: 0, quantity: 1 },
+            Step::Advance { ledgers: 0, seconds: u16::MAX },
+            Step::Finalize { raffle: 0 },
+            Step::Cancel { raffle: 0, by_admin: false },
+            Step::Cancel { raffle: 0, by_admin: true },
+            Step::RefundTicket { raffle: 0, ticket: 0 },
+            Step::RefundPrize { raffle: 0 },
+        ]);
+    }
+
+    #[test]
+    fn transition_table_rejects_leaving_terminal_states() {
+        use RaffleStatus::*;
+        for terminal in [Cancelled, Failed, Claimed] {
+            for next in [PendingPrize, Active, Drawing, Finalized, Cancelled, Failed, Claimed] {
+                assert_eq!(is_legal_transition(&terminal, &next), terminal == next);
+            }
+        }
+        assert!(!is_legal_transition(&Active, &Finalized));
+        assert!(!is_legal_transition(&PendingPrize, &Drawing));
+    }
+}
@@ -1,7 +1,35 @@
-import { TxSubmitterService } from './tx-submitter.service';
+import { randomBytes } from 'crypto';
+import { xdr } from '@stellar/stellar-sdk';
+import { buildProvideRandomnessArgs, TxSubmitterService } from './tx-submitter.service';
 import { KeyService } from '../keys/key.service';
 import { buildVrfProofMessage } from '../vrf/proof-message';
 
+describe('buildProvideRandomnessArgs', () => {
+  const params = {
+    raffleContract: 'CAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAABSC4',
+    randomSeed: new Uint8Array(randomBytes(32)),
+    publicKey: new Uint8Array(32).fill(1),
+    proof: new Uint8Array(64).fill(2),
+    requestId: 9n,
+  };
+
+  it('passes the seed as BytesN<32>', () => {
+    const [seed, publicKey, proof, requestId] = buildProvideRandomnessArgs(params);
+
+    expect(seed.switch()).toBe(xdr.ScValType.scvBytes());
+    expect(Buffer.from(seed.bytes()).equals(Buffer.from(params.randomSeed))).toBe(true);
+    expect(publicKey.bytes()).toHaveLength(32);
+    expect(proof.bytes()).toHaveLength(64);
+    expect(requestId.u64().toBigInt()).toBe(9n);
+  });
+
+  it('rejects a 64-bit seed', () => {
+    expect(() => buildProvideRandomnessArgs({ ...params, randomSeed: new Uint8Array(8) })).toThrow(
+      'randomSeed must be 32 bytes, got 8',
+    );
+  });
+});
+
 /**
  * Integration test — skipped unless STELLAR_INTEGRATION_TEST=1 and env vars are set.
  * Run against testnet with a funded oracle account and deployed raffle contract.
@@ -21,7 +49,12 @@ describe('TxSubmitterService integration', () => {
         throw new Error('RAFFLE_CONTRACT_ADDRESS and RANDOMNESS_REQUEST_ID required');
       }
 
-      const randomSeed = BigInt(process.env.RANDOMNESS_SEED ?? '42');
+      // RANDOMNESS_SEED, if set, is 64 hex characters.
+      const randomSeed = new Uint8Array(
+        process.env.RANDOMNESS_SEED
+          ? Buffer.from(process.env.RANDOMNESS_SEED, 'hex')
+          : randomBytes(32),
+      );
       const message = buildVrfProofMessage(raffleContract, BigInt(requestId), randomSeed);
       const proof = keyService.sign(message);
       const publicKey = keyService.getPublicKeyBytes();
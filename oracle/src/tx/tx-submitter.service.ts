@@ -5,20 +5,36 @@ import {
   rpc as SorobanRpc,
   TransactionBuilder,
   nativeToScVal,
+  xdr,
 } from '@stellar/stellar-sdk';
 import { KeyService } from '../keys/key.service';
+import { assertRandomSeed } from '../vrf/proof-message';
 
 const MAX_RETRIES = 5;
 const BASE_BACKOFF_MS = 500;
 
 export interface ProvideRandomnessParams {
   raffleContract: string;
-  randomSeed: bigint;
+  randomSeed: Uint8Array;
   publicKey: Uint8Array;
   proof: Uint8Array;
   requestId: bigint;
 }
 
+/**
+ * Arguments of `provide_randomness(random_seed: BytesN<32>, public_key:
+ * BytesN<32>, proof: BytesN<64>, request_id: u64)`, in order.
+ */
+export function buildProvideRandomnessArgs(params: ProvideRandomnessParams): xdr.ScVal[] {
+  assertRandomSeed(params.randomSeed);
+  return [
+    nativeToScVal(Buffer.from(params.randomSeed), { type: 'bytes' }),
+    nativeToScVal(Buffer.from(params.publicKey), { type: 'bytes' }),
+    nativeToScVal(Buffer.from(params.proof), { type: 'bytes' }),
+    nativeToScVal(params.requestId, { type: 'u64' }),
+  ];
+}
+
 export class TxSubmitterService {
   private readonly server: SorobanRpc.Server;
   private sequenceCache?: string;
@@ -72,10 +88,7 @@ export class TxSubmitterService {
     const contract = new Contract(params.raffleContract);
     const operation = contract.call(
       'provide_randomness',
-      nativeToScVal(params.randomSeed, { type: 'u64' }),
-      nativeToScVal(Buffer.from(params.publicKey), { type: 'bytes' }),
-      nativeToScVal(Buffer.from(params.proof), { type: 'bytes' }),
-      nativeToScVal(params.requestId, { type: 'u64' }),
+      ...buildProvideRandomnessArgs(params),
     );
 
     let tx = new TransactionBuilder(sourceAccount, {
@@ -1,5 +1,8 @@
 import { Address, nativeToScVal, xdr } from '@stellar/stellar-sdk';
 
+/** Length in bytes of a draw seed (`BytesN<32>` on chain). */
+export const RANDOM_SEED_BYTES = 32;
+
 /**
  * Builds the Ed25519 message that must be signed for `provide_randomness`.
  * Must match `build_vrf_proof_message` in the on-chain raffle-instance contract.
@@ -7,13 +10,23 @@ import { Address, nativeToScVal, xdr } from '@stellar/stellar-sdk';
 export function buildVrfProofMessage(
   raffleContract: string,
   requestId: bigint,
-  randomSeed: bigint,
+  randomSeed: Uint8Array,
 ): Buffer {
+  assertRandomSeed(randomSeed);
   const address = new Address(raffleContract);
   const scVal = xdr.ScVal.scvVec([
     address.toScVal(),
     nativeToScVal(requestId, { type: 'u64' }),
-    nativeToScVal(randomSeed, { type: 'u64' }),
+    nativeToScVal(Buffer.from(randomSeed), { type: 'bytes' }),
   ]);
   return Buffer.from(scVal.toXDR());
 }
+
+/** Throws unless `randomSeed` is exactly `RANDOM_SEED_BYTES` long. */
+export function assertRandomSeed(randomSeed: Uint8Array): void {
+  if (randomSeed.length !== RANDOM_SEED_BYTES) {
+    throw new Error(
+      `randomSeed must be ${RANDOM_SEED_BYTES} bytes, got ${randomSeed.length}`,
+    );
+  }
+}
@@ -1,18 +1,35 @@
-import { Keypair } from '@stellar/stellar-sdk';
+import { Keypair, xdr } from '@stellar/stellar-sdk';
 import { KeyService } from '../keys/key.service';
 import { VrfService } from './vrf.service';
-import { buildVrfProofMessage } from './proof-message';
+import { buildVrfProofMessage, RANDOM_SEED_BYTES } from './proof-message';
+
+const RAFFLE_CONTRACT = 'CAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAABSC4';
+
+/** Seed bytes 0x00, 0x01, ..., 0x1f. */
+const countingSeed = (): Uint8Array => Uint8Array.from({ length: RANDOM_SEED_BYTES }, (_, i) => i);
 
 describe('buildVrfProofMessage', () => {
+  it('matches the contract encoding of (address, u64, BytesN<32>)', () => {
+    // Produced by `build_vrf_proof_message` in raffle-instance for the same
+    // contract, request 7 and the counting seed.
+    const expected =
+      '00000010000000010000000300000012000000010000000000000000000000000000000000000000' +
+      '000000000000000000000000000000050000000000000007' +
+      '0000000d00000020000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f';
+
+    const message = buildVrfProofMessage(RAFFLE_CONTRACT, 7n, countingSeed());
+
+    expect(message.toString('hex')).toBe(expected);
+    const seed = xdr.ScVal.fromXDR(message).vec()![2];
+    expect(seed.switch()).toBe(xdr.ScValType.scvBytes());
+    expect(seed.bytes()).toHaveLength(RANDOM_SEED_BYTES);
+  });
+
   it('produces distinct messages for different raffle contracts', () => {
     const requestId = 42n;
-    const seed = 99n;
+    const seed = countingSeed();
 
-    const messageA = buildVrfProofMessage(
-      'CAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAABSC4',
-      requestId,
-      seed,
-    );
+    const messageA = buildVrfProofMessage(RAFFLE_CONTRACT, requestId, seed);
     const messageB = buildVrfProofMessage(
       'CAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAD2KM',
       requestId,
@@ -21,6 +38,14 @@ describe('buildVrfProofMessage', () => {
 
     expect(messageA.equals(messageB)).toBe(false);
   });
+
+  it('rejects seeds that are not 32 bytes', () => {
+    for (const length of [0, 8, 31, 33]) {
+      expect(() => buildVrfProofMessage(RAFFLE_CONTRACT, 1n, new Uint8Array(length))).toThrow(
+        `randomSeed must be 32 bytes, got ${length}`,
+      );
+    }
+  });
 });
 
 describe('VrfService', () => {
@@ -39,14 +64,14 @@ describe('VrfService', () => {
     await keyService.initialize();
 
     const vrf = new VrfService(keyService);
-    const raffleContract = 'CAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAABSC4';
     const requestId = 7n;
-    const randomSeed = 123456789n;
+    const randomSeed = countingSeed();
 
-    const signed = vrf.signRandomnessProof(raffleContract, requestId, randomSeed);
-    const message = buildVrfProofMessage(raffleContract, requestId, randomSeed);
+    const signed = vrf.signRandomnessProof(RAFFLE_CONTRACT, requestId, randomSeed);
+    const message = buildVrfProofMessage(RAFFLE_CONTRACT, requestId, randomSeed);
 
     expect(signed.publicKey).toEqual(keyService.getPublicKeyBytes());
+    expect(signed.randomSeed).toEqual(randomSeed);
     expect(keypair.verify(message, Buffer.from(signed.proof))).toBe(true);
   });
 });
@@ -2,7 +2,7 @@ import { KeyService } from '../keys/key.service';
 import { buildVrfProofMessage } from './proof-message';
 
 export interface RandomnessProof {
-  randomSeed: bigint;
+  randomSeed: Uint8Array;
   publicKey: Uint8Array;
   proof: Uint8Array;
   requestId: bigint;
@@ -17,7 +17,7 @@ export class VrfService {
   signRandomnessProof(
     raffleContract: string,
     requestId: bigint,
-    randomSeed: bigint,
+    randomSeed: Uint8Array,
   ): RandomnessProof {
     const message = buildVrfProofMessage(raffleContract, requestId, randomSeed);
     const proof = this.keyService.sign(message);
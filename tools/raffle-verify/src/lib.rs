@@ -0,0 +1,471 @@
+//! Offline verification of Tikka raffle draws.
+//!
+//! Given the `FairnessData` a raffle exposes through `get_fairness_data`, the
+//! raffle's prize tiers from `get_raffle` (and optionally its
+//! `RaffleFinalized` event), this crate re-runs the winner selection with the
+//! exact code the contract executes, from `raffle_instance::randomness`, and
+//! reports per tier whether the recorded winners match.  For oracle-backed draws it also rebuilds the VRF message
+//! with `build_vrf_proof_message` and checks the oracle's signature over it.
+//!
+//! Inputs are Soroban `ScVal`s, either base64 XDR or the JSON form emitted by
+//! the Stellar CLI and RPC.
+
+use core::fmt;
+
+use ed25519_dalek::{Signature, VerifyingKey};
+use raffle_instance::randomness::{
+    build_vrf_proof_message, select_winners_with, LcgRejectionWinnerSelection,
+    Sha256CounterWinnerSelection,
+};
+use raffle_shared::constants::FAIRNESS_VERSION_SEED256;
+use raffle_shared::{FairnessData, RandomnessSource, SelectionAlgorithm};
+use soroban_sdk::xdr::{Limits, ReadXdr, ScVal};
+use soroban_sdk::{contract, Address, Env, Map, Symbol, TryFromVal, Val, Vec};
+
+/// Empty contract registered so host-only code (`env.prng()`,
+/// `current_contract_address`) runs in a contract frame.
+#[contract]
+pub struct VerifierContext;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// Input was neither valid JSON nor base64 XDR of an `ScVal`.
+    Decode(String),
+    /// Input decoded but does not have the expected shape.
+    Schema(String),
+    /// The VRF evidence is malformed or does not apply to this draw.
+    Vrf(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Decode(msg) => write!(f, "decode error: {msg}"),
+            Error::Schema(msg) => write!(f, "unexpected input: {msg}"),
+            Error::Vrf(msg) => write!(f, "vrf check: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Winner data taken from a `RaffleFinalized` event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FinalizedEvent {
+    pub winning_ticket_ids: std::vec::Vec<u32>,
+    pub total_tickets_sold: u32,
+    pub selection_algorithm: Option<SelectionAlgorithm>,
+}
+
+/// Oracle evidence for an externally seeded draw, as submitted to
+/// `provide_randomness`.
+#[derive(Debug, Clone)]
+pub struct VrfEvidence {
+    /// Raffle instance contract address (strkey).
+    pub raffle: String,
+    pub request_id: u64,
+    pub public_key: [u8; 32],
+    pub proof: [u8; 64],
+}
+
+/// Outcome for one prize tier.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TierReport {
+    pub tier: u32,
+    pub recomputed: Option<u32>,
+    pub recorded: Option<u32>,
+    /// Winner index from the `RaffleFinalized` event, when one was supplied.
+    pub event: Option<u32>,
+}
+
+impl TierReport {
+    pub fn matches(&self) -> bool {
+        self.recomputed.is_some()
+            && self.recomputed == self.recorded
+            && (self.event.is_none() || self.event == self.recomputed)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub version: u32,
+    pub selection_algorithm: SelectionAlgorithm,
+    pub total_tickets: u32,
+    pub tiers: std::vec::Vec<TierReport>,
+    /// Problems found outside the per-tier comparison (e.g. ticket count or
+    /// algorithm disagreeing with the event).
+    pub notes: std::vec::Vec<String>,
+    /// `Some(valid)` when VRF evidence was checked.
+    pub vrf_signature_valid: Option<bool>,
+}
+
+impl Report {
+    pub fn is_match(&self) -> bool {
+        self.notes.is_empty()
+            && self.tiers.iter().all(TierReport::matches)
+            && self.vrf_signature_valid != Some(false)
+    }
+}
+
+/// Decodes an `ScVal` from JSON (when the input starts with `{`) or base64 XDR.
+pub fn decode_scval(input: &str) -> Result<ScVal, Error> {
+    let input = input.trim();
+    if input.starts_with('{') {
+        serde_json::from_str(input).map_err(|e| Error::Decode(e.to_string()))
+    } else {
+        ScVal::from_xdr_base64(input, Limits::none()).map_err(|e| Error::Decode(e.to_string()))
+    }
+}
+
+fn to_val(env: &Env, scval: &ScVal) -> Result<Val, Error> {
+    Val::try_from_val(env, scval).map_err(|e| Error::Decode(format!("{e:?}")))
+}
+
+/// Decodes the `FairnessData` returned by `get_fairness_data`.
+pub fn decode_fairness(env: &Env, scval: &ScVal) -> Result<FairnessData, Error> {
+    let val = to_val(env, scval)?;
+    FairnessData::try_from_val(env, &val)
+        .map_err(|_| Error::Schema("value is not a FairnessData record".into()))
+}
+
+/// Reads the number of prize tiers from the `Raffle` returned by
+/// `get_raffle`.  Only the `prizes` field is decoded, so records from other
+/// contract versions still read.
+pub fn decode_prize_tiers(env: &Env, scval: &ScVal) -> Result<u32, Error> {
+    let val = to_val(env, scval)?;
+    let map = Map::<Symbol, Val>::try_from_val(env, &val)
+        .map_err(|_| Error::Schema("raffle is not a map".into()))?;
+    let prizes = map
+        .get(Symbol::new(env, "prizes"))
+        .ok_or_else(|| Error::Schema("raffle has no `prizes` field".into()))?;
+    let prizes = Vec::<u32>::try_from_val(env, &prizes)
+        .map_err(|_| Error::Schema("`prizes` is not a Vec<u32>".into()))?;
+    Ok(prizes.len())
+}
+
+/// Decodes the data map of a `RaffleFinalized` event.
+///
+/// `selection_algorithm` is optional so events from before the field existed
+/// still decode.
+pub fn decode_finalized(env: &Env, scval: &ScVal) -> Result<FinalizedEvent, Error> {
+    let val = to_val(env, scval)?;
+    let map = Map::<Symbol, Val>::try_from_val(env, &val)
+        .map_err(|_| Error::Schema("event data is not a map".into()))?;
+
+    let field = |name: &str| -> Result<Val, Error> {
+        map.get(Symbol::new(env, name))
+            .ok_or_else(|| Error::Schema(format!("event data has no `{name}` field")))
+    };
+
+    let winners = Vec::<u32>::try_from_val(env, &field("winning_ticket_ids")?)
+        .map_err(|_| Error::Schema("`winning_ticket_ids` is not a Vec<u32>".into()))?;
+    let total_tickets_sold = u32::try_from_val(env, &field("total_tickets_sold")?)
+        .map_err(|_| Error::Schema("`total_tickets_sold` is not a u32".into()))?;
+    let selection_algorithm = match map.get(Symbol::new(env, "selection_algorithm")) {
+        Some(v) => Some(
+            SelectionAlgorithm::try_from_val(env, &v)
+                .map_err(|_| Error::Schema("`selection_algorithm` is not a SelectionAlgorithm".into()))?,
+        ),
+        None => None,
+    };
+
+    Ok(FinalizedEvent {
+        winning_ticket_ids: winners.iter().collect(),
+        total_tickets_sold,
+        selection_algorithm,
+    })
+}
+
+/// Re-runs winner selection for a fairness record with the contract's code,
+/// drawing one winner per prize tier.
+pub fn recompute_winners(env: &Env, fairness: &FairnessData, prize_tiers: u32) -> std::vec::Vec<u32> {
+    let seed = fairness.seed.to_array();
+    let total = fairness.ticket_ids.len();
+    let count = prize_tiers;
+    match fairness.selection_algorithm {
+        SelectionAlgorithm::LcgRejectionV1 => {
+            LcgRejectionWinnerSelection::new(seed).select_winner_indices_pure(total, count)
+        }
+        SelectionAlgorithm::Sha256CounterV2 => {
+            Sha256CounterWinnerSelection::new(seed).select_winner_indices_pure(total, count)
+        }
+        // The host PRNG has no off-chain implementation; run it in a host.
+        SelectionAlgorithm::EnvPrng => {
+            let context = env.register(VerifierContext, ());
+            env.as_contract(&context, || {
+                select_winners_with(env, &fairness.selection_algorithm, &fairness.seed, total, count)
+                    .iter()
+                    .collect()
+            })
+        }
+    }
+}
+
+/// Checks the oracle's Ed25519 signature over the VRF message for this draw.
+pub fn check_vrf(env: &Env, fairness: &FairnessData, evidence: &VrfEvidence) -> Result<bool, Error> {
+    if fairness.randomness_source != RandomnessSource::External {
+        return Err(Error::Vrf("draw was not seeded by the oracle".into()));
+    }
+    if fairness.version != FAIRNESS_VERSION_SEED256 {
+        return Err(Error::Vrf(format!(
+            "fairness record version {} predates 256-bit VRF seeds",
+            fairness.version
+        )));
+    }
+
+    let raffle = Address::from_str(env, &evidence.raffle);
+    env.register_at(&raffle, VerifierContext, ());
+    let message = env.as_contract(&raffle, || {
+        build_vrf_proof_message(env, evidence.request_id, &fairness.seed)
+    });
+    let mut buf = std::vec![0u8; message.len() as usize];
+    message.copy_into_slice(&mut buf);
+
+    let key = VerifyingKey::from_bytes(&evidence.public_key)
+        .map_err(|_| Error::Vrf("public key is not a valid Ed25519 point".into()))?;
+    let signature = Signature::from_bytes(&evidence.proof);
+    Ok(key.verify_strict(&buf, &signature).is_ok())
+}
+
+/// Verifies a draw and reports the result per tier.  `prize_tiers` comes
+/// from the raffle, not the fairness record, so a record missing winners
+/// fails the tiers it dropped.
+pub fn verify(
+    env: &Env,
+    fairness: &FairnessData,
+    prize_tiers: u32,
+    finalized: Option<&FinalizedEvent>,
+    vrf: Option<&VrfEvidence>,
+) -> Result<Report, Error> {
+    let total_tickets = fairness.ticket_ids.len();
+    if prize_tiers == 0 || prize_tiers > total_tickets {
+        return Err(Error::Schema(format!(
+            "raffle has {prize_tiers} prize tiers for {total_tickets} tickets"
+        )));
+    }
+    let recomputed = recompute_winners(env, fairness, prize_tiers);
+    let recorded: std::vec::Vec<u32> = fairness.winning_ticket_indices.iter().collect();
+
+    let mut notes = std::vec::Vec::new();
+    if recorded.len() != prize_tiers as usize {
+        notes.push(format!(
+            "fairness record has {} winners, raffle has {} prize tiers",
+            recorded.len(),
+            prize_tiers
+        ));
+    }
+    if let Some(event) = finalized {
+        if event.total_tickets_sold != total_tickets {
+            notes.push(format!(
+                "event reports {} tickets sold, fairness record has {}",
+                event.total_tickets_sold, total_tickets
+            ));
+        }
+        if let Some(algorithm) = event.selection_algorithm {
+            if algorithm != fairness.selection_algorithm {
+                notes.push(format!(
+                    "event selection algorithm {:?} differs from fairness record {:?}",
+                    algorithm, fairness.selection_algorithm
+                ));
+            }
+        }
+        if event.winning_ticket_ids.len() != recorded.len() {
+            notes.push(format!(
+                "event has {} winners, fairness record has {}",
+                event.winning_ticket_ids.len(),
+                recorded.len()
+            ));
+        }
+    }
+
+    let tier_count = recorded
+        .len()
+        .max(recomputed.len())
+        .max(finalized.map_or(0, |e| e.winning_ticket_ids.len()));
+    let tiers = (0..tier_count)
+        .map(|i| TierReport {
+            tier: i as u32,
+            recomputed: recomputed.get(i).copied(),
+            recorded: recorded.get(i).copied(),
+            event: finalized.and_then(|e| e.winning_ticket_ids.get(i).copied()),
+        })
+        .collect();
+
+    let vrf_signature_valid = match vrf {
+        Some(evidence) => Some(check_vrf(env, fairness, evidence)?),
+        None => None,
+    };
+
+    Ok(Report {
+        version: fairness.version,
+        selection_algorithm: fairness.selection_algorithm,
+        total_tickets,
+        tiers,
+        notes,
+        vrf_signature_valid,
+    })
+}
+
+/// Parses a fixed-length hex string, as used for keys and proofs on the CLI.
+pub fn parse_hex<const N: usize>(name: &str, value: &str) -> Result<[u8; N], Error> {
+    let bytes = hex::decode(value.trim().trim_start_matches("0x"))
+        .map_err(|e| Error::Decode(format!("{name}: {e}")))?;
+    bytes
+        .try_into()
+        .map_err(|_| Error::Decode(format!("{name}: expected {N} bytes")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+    use raffle_instance::randomness::widen_legacy_seed;
+    use raffle_shared::constants::FAIRNESS_VERSION_SEED64;
+    use soroban_sdk::xdr::WriteXdr;
+    use soroban_sdk::{vec, BytesN, IntoVal};
+
+    fn fairness(env: &Env, algorithm: SelectionAlgorithm, seed: [u8; 32], winners: &[u32]) -> FairnessData {
+        let mut ticket_ids = Vec::new(env);
+        for id in 1..=10u32 {
+            ticket_ids.push_back(id);
+        }
+        let mut winning = Vec::new(env);
+        for w in winners {
+            winning.push_back(*w);
+        }
+        FairnessData {
+            version: FAIRNESS_VERSION_SEED256,
+            seed: BytesN::from_array(env, &seed),
+            randomness_source: RandomnessSource::Internal,
+            selection_algorithm: algorithm,
+            ticket_ids,
+            winning_ticket_indices: winning,
+            draw_timestamp: 1_700_000_000,
+            draw_sequence: 42,
+        }
+    }
+
+    fn to_scval(env: &Env, data: &FairnessData) -> ScVal {
+        let val: Val = data.into_val(env);
+        ScVal::try_from_val(env, &val).unwrap()
+    }
+
+    #[test]
+    fn matching_draw_round_trips_through_xdr_and_json() {
+        let env = Env::default();
+        let data = fairness(&env, SelectionAlgorithm::LcgRejectionV1, widen_legacy_seed(42), &[2, 3, 0]);
+        let scval = to_scval(&env, &data);
+
+        let xdr = scval.to_xdr_base64(Limits::none()).unwrap();
+        let json = serde_json::to_string(&scval).unwrap();
+        for input in [xdr, json] {
+            let decoded = decode_fairness(&env, &decode_scval(&input).unwrap()).unwrap();
+            let report = verify(&env, &decoded, 3, None, None).unwrap();
+            assert!(report.is_match());
+            assert_eq!(report.tiers.len(), 3);
+        }
+    }
+
+    #[test]
+    fn tampered_tier_is_reported() {
+        let env = Env::default();
+        let probe = fairness(&env, SelectionAlgorithm::Sha256CounterV2, [0x5A; 32], &[0, 0, 0]);
+        let mut winners = recompute_winners(&env, &probe, 3);
+        winners[2] = (winners[2] + 1) % 10;
+        let data = fairness(&env, SelectionAlgorithm::Sha256CounterV2, [0x5A; 32], &winners);
+
+        let report = verify(&env, &data, 3, None, None).unwrap();
+        assert!(!report.is_match());
+        let failing: std::vec::Vec<u32> =
+            report.tiers.iter().filter(|t| !t.matches()).map(|t| t.tier).collect();
+        assert_eq!(failing, std::vec![2]);
+    }
+
+    #[test]
+    fn truncated_record_fails_dropped_tiers() {
+        let env = Env::default();
+        let probe = fairness(&env, SelectionAlgorithm::Sha256CounterV2, [0x5A; 32], &[]);
+        let winners = recompute_winners(&env, &probe, 3);
+        let data = fairness(&env, SelectionAlgorithm::Sha256CounterV2, [0x5A; 32], &winners[..2]);
+
+        let report = verify(&env, &data, 3, None, None).unwrap();
+        assert!(!report.is_match());
+        assert_eq!(report.tiers.len(), 3);
+        assert!(report.tiers[0].matches() && report.tiers[1].matches());
+        assert_eq!(report.tiers[2].recorded, None);
+        assert_eq!(report.notes.len(), 1);
+    }
+
+    #[test]
+    fn prize_tiers_are_read_from_raffle() {
+        let env = Env::default();
+        let mut raffle = Map::<Symbol, Val>::new(&env);
+        raffle.set(Symbol::new(&env, "prizes"), vec![&env, 5_000u32, 3_000, 2_000].into_val(&env));
+        raffle.set(Symbol::new(&env, "max_tickets"), 10u32.into_val(&env));
+        let raffle_val: Val = raffle.into_val(&env);
+        let scval = ScVal::try_from_val(&env, &raffle_val).unwrap();
+        assert_eq!(decode_prize_tiers(&env, &scval), Ok(3));
+
+        let data = fairness(&env, SelectionAlgorithm::Sha256CounterV2, [0x5A; 32], &[]);
+        assert!(verify(&env, &data, 11, None, None).is_err());
+    }
+
+    #[test]
+    fn env_prng_draw_is_replayed_in_host() {
+        let env = Env::default();
+        let seed = [0x5A; 32];
+        let probe = fairness(&env, SelectionAlgorithm::EnvPrng, seed, &[0, 0, 0]);
+        let winners = recompute_winners(&env, &probe, 3);
+
+        let data = fairness(&env, SelectionAlgorithm::EnvPrng, seed, &winners);
+        assert!(verify(&env, &data, 3, None, None).unwrap().is_match());
+    }
+
+    #[test]
+    fn finalized_event_is_compared_per_tier() {
+        let env = Env::default();
+        let data = fairness(&env, SelectionAlgorithm::LcgRejectionV1, widen_legacy_seed(42), &[2, 3, 0]);
+
+        let mut map = Map::<Symbol, Val>::new(&env);
+        map.set(Symbol::new(&env, "winning_ticket_ids"), vec![&env, 2u32, 3, 1].into_val(&env));
+        map.set(Symbol::new(&env, "total_tickets_sold"), 10u32.into_val(&env));
+        map.set(
+            Symbol::new(&env, "selection_algorithm"),
+            SelectionAlgorithm::LcgRejectionV1.into_val(&env),
+        );
+        let map_val: Val = map.into_val(&env);
+        let event = decode_finalized(&env, &ScVal::try_from_val(&env, &map_val).unwrap()).unwrap();
+
+        let report = verify(&env, &data, 3, Some(&event), None).unwrap();
+        assert!(report.tiers[0].matches());
+        assert!(report.tiers[1].matches());
+        assert!(!report.tiers[2].matches());
+    }
+
+    #[test]
+    fn vrf_signature_is_checked_against_contract_message() {
+        let env = Env::default();
+        let raffle = env.register(VerifierContext, ());
+        let mut data = fairness(&env, SelectionAlgorithm::Sha256CounterV2, [0x11; 32], &[]);
+        data.randomness_source = RandomnessSource::External;
+
+        let message = env.as_contract(&raffle, || build_vrf_proof_message(&env, 7, &data.seed));
+        let mut buf = std::vec![0u8; message.len() as usize];
+        message.copy_into_slice(&mut buf);
+        let key = SigningKey::from_bytes(&[9u8; 32]);
+
+        let mut evidence = VrfEvidence {
+            raffle: raffle.to_string().to_string(),
+            request_id: 7,
+            public_key: key.verifying_key().to_bytes(),
+            proof: key.sign(&buf).to_bytes(),
+        };
+        assert_eq!(check_vrf(&env, &data, &evidence), Ok(true));
+
+        evidence.request_id = 8;
+        assert_eq!(check_vrf(&env, &data, &evidence), Ok(false));
+
+        data.version = FAIRNESS_VERSION_SEED64;
+        assert!(check_vrf(&env, &data, &evidence).is_err());
+    }
+}
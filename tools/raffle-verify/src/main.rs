@@ -0,0 +1,156 @@
+//! `raffle-verify` — independently re-check a raffle draw.
+//!
+//! ```text
+//! raffle-verify --fairness <FILE> --raffle-data <FILE> [--finalized <FILE>]
+//!               [--raffle <C...> --request-id <N> --public-key <HEX> --proof <HEX>]
+//! ```
+//!
+//! `--fairness` takes the `get_fairness_data` result, `--raffle-data` the
+//! `get_raffle` result, whose prize tiers set the winner count, and
+//! `--finalized` the `RaffleFinalized` event data, each as base64 XDR or
+//! Stellar JSON.  The VRF
+//! options are the arguments the oracle passed to `provide_randomness`.
+//!
+//! Exit status: 0 when everything matches, 1 on any mismatch, 2 on bad input.
+
+use std::process::ExitCode;
+
+use raffle_verify::{
+    decode_fairness, decode_finalized, decode_prize_tiers, decode_scval, parse_hex, verify, Error,
+    Report, VrfEvidence,
+};
+use soroban_sdk::Env;
+
+const USAGE: &str = "usage: raffle-verify --fairness <FILE> --raffle-data <FILE> [--finalized <FILE>] \
+[--raffle <ADDRESS> --request-id <N> --public-key <HEX> --proof <HEX>]";
+
+#[derive(Default)]
+struct Args {
+    fairness: Option<String>,
+    raffle_data: Option<String>,
+    finalized: Option<String>,
+    raffle: Option<String>,
+    request_id: Option<String>,
+    public_key: Option<String>,
+    proof: Option<String>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args::default();
+    let mut iter = std::env::args().skip(1);
+    while let Some(flag) = iter.next() {
+        let slot = match flag.as_str() {
+            "--fairness" => &mut args.fairness,
+            "--raffle-data" => &mut args.raffle_data,
+            "--finalized" => &mut args.finalized,
+            "--raffle" => &mut args.raffle,
+            "--request-id" => &mut args.request_id,
+            "--public-key" => &mut args.public_key,
+            "--proof" => &mut args.proof,
+            "-h" | "--help" => return Err(USAGE.into()),
+            other => return Err(format!("unknown argument `{other}`\n{USAGE}")),
+        };
+        *slot = Some(iter.next().ok_or_else(|| format!("{flag} needs a value"))?);
+    }
+    Ok(args)
+}
+
+fn read(path: &str) -> Result<String, Error> {
+    std::fs::read_to_string(path).map_err(|e| Error::Decode(format!("{path}: {e}")))
+}
+
+fn vrf_evidence(args: &Args) -> Result<Option<VrfEvidence>, Error> {
+    let given = [&args.raffle, &args.request_id, &args.public_key, &args.proof];
+    if given.iter().all(|v| v.is_none()) {
+        return Ok(None);
+    }
+    let (Some(raffle), Some(request_id), Some(public_key), Some(proof)) =
+        (&args.raffle, &args.request_id, &args.public_key, &args.proof)
+    else {
+        return Err(Error::Vrf(
+            "--raffle, --request-id, --public-key and --proof must be given together".into(),
+        ));
+    };
+    Ok(Some(VrfEvidence {
+        raffle: raffle.clone(),
+        request_id: request_id
+            .parse()
+            .map_err(|e| Error::Decode(format!("--request-id: {e}")))?,
+        public_key: parse_hex("--public-key", public_key)?,
+        proof: parse_hex("--proof", proof)?,
+    }))
+}
+
+fn run(args: &Args) -> Result<Report, Error> {
+    let env = Env::default();
+    let path = args
+        .fairness
+        .as_deref()
+        .ok_or_else(|| Error::Decode(format!("--fairness is required\n{USAGE}")))?;
+    let fairness = decode_fairness(&env, &decode_scval(&read(path)?)?)?;
+    let path = args
+        .raffle_data
+        .as_deref()
+        .ok_or_else(|| Error::Decode(format!("--raffle-data is required\n{USAGE}")))?;
+    let prize_tiers = decode_prize_tiers(&env, &decode_scval(&read(path)?)?)?;
+    let finalized = match &args.finalized {
+        Some(path) => Some(decode_finalized(&env, &decode_scval(&read(path)?)?)?),
+        None => None,
+    };
+    let vrf = vrf_evidence(args)?;
+    verify(&env, &fairness, prize_tiers, finalized.as_ref(), vrf.as_ref())
+}
+
+fn show(value: Option<u32>) -> String {
+    value.map_or_else(|| "-".into(), |v| v.to_string())
+}
+
+fn print_report(report: &Report) {
+    println!(
+        "fairness record v{}, {:?}, {} tickets",
+        report.version, report.selection_algorithm, report.total_tickets
+    );
+    for tier in &report.tiers {
+        println!(
+            "tier {}: recomputed {} recorded {} event {} -> {}",
+            tier.tier,
+            show(tier.recomputed),
+            show(tier.recorded),
+            show(tier.event),
+            if tier.matches() { "MATCH" } else { "MISMATCH" }
+        );
+    }
+    for note in &report.notes {
+        println!("MISMATCH: {note}");
+    }
+    match report.vrf_signature_valid {
+        Some(true) => println!("vrf signature: MATCH"),
+        Some(false) => println!("vrf signature: MISMATCH"),
+        None => {}
+    }
+    println!("result: {}", if report.is_match() { "MATCH" } else { "MISMATCH" });
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(msg) => {
+            eprintln!("{msg}");
+            return ExitCode::from(2);
+        }
+    };
+    match run(&args) {
+        Ok(report) => {
+            print_report(&report);
+            if report.is_match() {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::from(1)
+            }
+        }
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::from(2)
+        }
+    }
+}